@@ -1,12 +1,12 @@
 use std::collections::BTreeMap;
 use std::fs;
 
-use asc7::{Asc7Profile, asc7_kernel_cert, normalize_str, verify_terminal};
+use asc7::{Asc7Profile, asc7_kernel_cert, normalize_str, register_asc7_kernels, verify_terminal};
 use asc7::confusables::confusables_kernel_cert;
-use collapse_core::{CertChain, CertItem, sem_entropy_bits};
-use sembit::{Test, TestFamily, sembit_quotient, tests_hash_hex, quotient_digest_hex, sembit_kernel_cert};
+use collapse_core::{CertBundle, CertChain, CertGraph, CertItem, HashAlg, KernelRegistry, Policy, Provenance, TaggedDigest, TransparencyLog, canon_bytes, chain_drift, read_bundle, sem_entropy_bits, write_bundle};
+use sembit::{Test, TestFamily, register_sembit_kernels, sembit_quotient, tests_digest, quotient_digest, sembit_kernel_cert};
 use structural_numbers::{QE, domain_qe_bounded};
-use structural_numbers::q_e::domain_digest_hex;
+use structural_numbers::q_e::domain_digest;
 
 fn t_sign(x: &QE) -> bool { x.num() > 0 }
 fn t_is_int(x: &QE) -> bool { x.den() == 1 }
@@ -14,23 +14,33 @@ fn t_den_gt_3(x: &QE) -> bool { x.den() > 3 }
 
 #[derive(Clone, Debug)]
 struct SpineDigests {
-    asc7_hash: String,
-    confusables_hash: String,
-    domain_digest: String,
-    tests_hash: String,
-    sembit_hash: String,
-    chain_hash: String,
+    asc7_hash: TaggedDigest,
+    confusables_hash: TaggedDigest,
+    domain_digest: TaggedDigest,
+    tests_hash: TaggedDigest,
+    sembit_hash: TaggedDigest,
+    chain_hash: TaggedDigest,
+    bundle: CertBundle,
+}
+
+/// Every kernel a spine bundle can carry, with their payload schemas.
+fn spine_kernels() -> KernelRegistry {
+    let mut kernels = KernelRegistry::new();
+    register_asc7_kernels(&mut kernels).unwrap();
+    register_sembit_kernels(&mut kernels).unwrap();
+    kernels.register::<Provenance>().unwrap();
+    kernels
 }
 
 fn compute_spine() -> SpineDigests {
     let profile = Asc7Profile::code_safe();
-    let asc7_cert = asc7_kernel_cert(&profile);
-    let asc7_hash = asc7_cert.kernel_hash_hex();
-    let conf_cert = confusables_kernel_cert();
-    let confusables_hash = conf_cert.kernel_hash_hex();
+    let asc7_cert = asc7_kernel_cert(&profile).unwrap();
+    let asc7_hash = asc7_cert.kernel_hash.clone();
+    let conf_cert = confusables_kernel_cert().unwrap();
+    let confusables_hash = conf_cert.kernel_hash.clone();
 
     let domain: Vec<QE> = domain_qe_bounded(20, 20);
-    let domain_digest = domain_digest_hex(&domain);
+    let domain_digest = domain_digest(&domain, HashAlg::Sha256);
 
     let id1 = normalize_str(&profile, "sign", true).unwrap();
     let id2 = normalize_str(&profile, "is_int", true).unwrap();
@@ -42,47 +52,58 @@ fn compute_spine() -> SpineDigests {
         Test { id_norm: id3, f: t_den_gt_3 },
     ]);
 
-    let tests_hash = tests_hash_hex(&tf, "impl:static_v1");
+    let tests_hash = tests_digest(&tf, "impl:static_v1", HashAlg::Sha256);
     let q = sembit_quotient(&domain, &tf);
     let h = sem_entropy_bits(q.size());
-    let qdig = quotient_digest_hex(&q);
+    let qdig = quotient_digest(&q, HashAlg::Sha256);
 
     let sembit_cert = sembit_kernel_cert(
-        &asc7_hash,
-        &confusables_hash,
+        &asc7_cert,
+        &conf_cert,
         &tests_hash,
         &domain_digest,
         q.size(),
         h,
         &qdig,
-    );
+    )
+    .unwrap();
 
-    let sembit_hash = sembit_cert.kernel_hash_hex();
+    let sembit_hash = sembit_cert.kernel_hash.clone();
 
     let chain = CertChain::build(vec![
-        CertItem { name: "asc7".to_string(), hash_hex: asc7_hash.clone() },
-        CertItem { name: "asc7_confusables".to_string(), hash_hex: confusables_hash.clone() },
-        CertItem { name: "sembit".to_string(), hash_hex: sembit_hash.clone() },
+        CertItem::new("asc7", asc7_hash.clone()),
+        CertItem::new("asc7_confusables", confusables_hash.clone()),
+        CertItem::new("sembit", sembit_hash.clone()),
     ]);
 
+    let chain_hash = chain.chain_hash.clone();
+    let bundle = CertBundle::new(vec![asc7_cert, conf_cert, sembit_cert], chain)
+        .with_meta("generator", "collapse_spine_demo")
+        .with_meta("profile", &profile.params.name);
+    if let Err(e) = bundle.verify_kernels(&spine_kernels()) {
+        panic!("spine bundle does not verify: {e}");
+    }
+
     SpineDigests {
         asc7_hash,
         confusables_hash,
         domain_digest,
         tests_hash,
         sembit_hash,
-        chain_hash: chain.chain_hash_hex,
+        chain_hash,
+        bundle,
     }
 }
 
+/// `expected.json` pins bare SHA-256 hex, which is how `TaggedDigest` serializes.
 fn write_expected_json(d: &SpineDigests) {
     let mut obj = BTreeMap::new();
-    obj.insert("confusables_hash", d.confusables_hash.clone());
-    obj.insert("asc7_hash", d.asc7_hash.clone());
-    obj.insert("domain_digest", d.domain_digest.clone());
-    obj.insert("tests_hash", d.tests_hash.clone());
-    obj.insert("sembit_hash", d.sembit_hash.clone());
-    obj.insert("chain_hash", d.chain_hash.clone());
+    obj.insert("confusables_hash", &d.confusables_hash);
+    obj.insert("asc7_hash", &d.asc7_hash);
+    obj.insert("domain_digest", &d.domain_digest);
+    obj.insert("tests_hash", &d.tests_hash);
+    obj.insert("sembit_hash", &d.sembit_hash);
+    obj.insert("chain_hash", &d.chain_hash);
 
     let v = serde_json::to_value(obj).unwrap();
     let s = serde_json::to_string_pretty(&v).unwrap();
@@ -91,21 +112,95 @@ fn write_expected_json(d: &SpineDigests) {
     fs::write("gates/expected.json", s + "\n").unwrap();
 }
 
+fn show(d: &Option<TaggedDigest>) -> String {
+    d.as_ref().map_or("(missing)".to_string(), |d| d.to_string())
+}
+
 fn main() {
     let args: Vec<String> = std::env::args().collect();
     let do_freeze = args.iter().any(|a| a == "--freeze");
 
+    if args.iter().any(|a| a == "--schemas") {
+        let schemas = spine_kernels().schemas().to_json_schemas();
+        println!("{}", String::from_utf8(canon_bytes(&schemas)).unwrap());
+        return;
+    }
+
     let profile = Asc7Profile::code_safe();
     let sample = "Hell0 W0r1d (O0I1)";
     let norm = normalize_str(&profile, sample, true).unwrap();
     let is_terminal = verify_terminal(&profile, &norm);
 
-    let d = compute_spine();
+    let mut d = compute_spine();
+    if args.iter().any(|a| a == "--with-provenance") {
+        let lock = include_bytes!("../../../Cargo.lock");
+        d.bundle = d.bundle.with_provenance(&Provenance::current(lock)).unwrap();
+    }
+
+    if args.iter().any(|a| a == "--dot") {
+        print!("{}", CertGraph::new(d.bundle.certs.clone()).to_dot());
+        return;
+    }
+
+    if let Some(i) = args.iter().position(|a| a == "--bundle") {
+        let path = args.get(i + 1).expect("--bundle needs a path");
+        write_bundle(path, &d.bundle).unwrap();
+        let back = read_bundle(path, spine_kernels().schemas()).expect("written bundle does not read back");
+        println!("Wrote {path} ({} certs, chain_hash = {})", back.certs.len(), back.chain.chain_hash);
+        return;
+    }
+
+    if let Some(i) = args.iter().position(|a| a == "--drift") {
+        let path = args.get(i + 1).expect("--drift needs a path");
+        let stored = read_bundle(path, spine_kernels().schemas()).unwrap();
+        if let Err(e) = stored.verify() {
+            panic!("{path} does not verify: {e}");
+        }
+        let drift = chain_drift(&stored.chain, &d.bundle.chain);
+        for it in &drift.semantic {
+            println!("semantic drift:   {} {} -> {}", it.name, show(&it.expected), show(&it.found));
+        }
+        if let (false, Some(old), Some(new)) = (drift.provenance.is_empty(), stored.provenance(), d.bundle.provenance()) {
+            print!("provenance drift:\n{}", old.diff(&new));
+        } else {
+            for it in &drift.provenance {
+                println!("provenance drift: {} {} -> {}", it.name, show(&it.expected), show(&it.found));
+            }
+        }
+        if drift.is_empty() {
+            println!("No drift from {path}");
+        }
+        if !drift.semantic.is_empty() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(i) = args.iter().position(|a| a == "--policy") {
+        let path = args.get(i + 1).expect("--policy needs a path");
+        let policy = Policy::from_bytes(&fs::read(path).unwrap()).unwrap();
+        let report = policy.evaluate(&d.bundle).unwrap();
+        print!("{report}");
+        if !report.passed() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(i) = args.iter().position(|a| a == "--log") {
+        let path = args.get(i + 1).expect("--log needs a path");
+        let mut log = TransparencyLog::open(path).unwrap();
+        let index = log.append(&d.bundle.chain).unwrap();
+        let head = log.tree_head();
+        println!("Logged chain_hash as entry {index} of {path}");
+        println!("tree_size = {}, root_hash = {}", head.tree_size, head.root_hash);
+        return;
+    }
 
     if do_freeze {
         write_expected_json(&d);
         println!("Wrote gates/expected.json");
-        println!("chain_hash = sha256:{}", d.chain_hash);
+        println!("chain_hash = {}", d.chain_hash);
         return;
     }
 
@@ -116,10 +211,10 @@ fn main() {
     println!();
 
     println!("=== Spine digests ===");
-    println!("confusables_hash = sha256:{}", d.confusables_hash);
-        println!("asc7_hash     = sha256:{}", d.asc7_hash);
-    println!("domain_digest = sha256:{}", d.domain_digest);
-    println!("tests_hash    = sha256:{}", d.tests_hash);
-    println!("sembit_hash   = sha256:{}", d.sembit_hash);
-    println!("chain_hash    = sha256:{}", d.chain_hash);
+    println!("confusables_hash = {}", d.confusables_hash);
+        println!("asc7_hash     = {}", d.asc7_hash);
+    println!("domain_digest = {}", d.domain_digest);
+    println!("tests_hash    = {}", d.tests_hash);
+    println!("sembit_hash   = {}", d.sembit_hash);
+    println!("chain_hash    = {}", d.chain_hash);
 }
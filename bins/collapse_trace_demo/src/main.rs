@@ -8,25 +8,25 @@ use asc7::confusables::confusables_kernel_cert;
 use collapse_core::canon::canon_bytes;
 use collapse_core::cert::KernelCert;
 use collapse_core::quotient::{Quotient, Signature};
-use collapse_core::{CertChain, CertItem, sem_entropy_bits};
+use collapse_core::{CertChain, CertItem, HashAlg, TaggedDigest, sem_entropy_bits};
 
-use sembit::{Test, TestFamily, tests_hash_hex, quotient_digest_hex, sembit_kernel_cert};
+use sembit::{Test, TestFamily, tests_digest, quotient_digest, sembit_kernel_cert};
 
 use structural_numbers::{QE, domain_qe_bounded};
 use structural_numbers::{domain_ne, domain_digest_hex_ne, domain_view_ne};
 use structural_numbers::{domain_ze, domain_digest_hex_ze, domain_view_ze};
-use structural_numbers::q_e::domain_digest_hex;
+use structural_numbers::q_e::domain_digest;
 
 use traceutil::{Trace, run_stamp};
 
-fn trace_kernel(trace: &mut Trace, label: &str, cert: &KernelCert) -> String {
+fn trace_kernel(trace: &mut Trace, label: &str, cert: &KernelCert) -> TaggedDigest {
     trace.section(&format!("KERNEL: {label}"));
     let bytes = canon_bytes(&cert.payload);
     trace.bytes_hex_preview("canonical_bytes", &bytes);
     let h = cert.kernel_hash_hex();
-    trace.kv("sha256(canonical_bytes)", &h);
+    trace.kv(&format!("{}(canonical_bytes)", cert.kernel_hash.alg()), &h);
     trace.kv("kernel_hash_hex", &h);
-    h
+    cert.kernel_hash.clone()
 }
 
 fn t_positive(x: &QE) -> bool { x.num() > 0 }
@@ -54,20 +54,20 @@ fn main() {
     tr_asc7.kv("profile", "Asc7Profile::code_safe()");
 
     tr_asc7.section("ASC7 KERNEL CERT");
-    let asc7_cert = asc7_kernel_cert(&profile);
+    let asc7_cert = asc7_kernel_cert(&profile).unwrap();
     let asc7_hash = trace_kernel(&mut tr_asc7, "asc7", &asc7_cert);
 
     tr_asc7.section("CONFUSABLES KERNEL CERT");
-    let conf_cert = confusables_kernel_cert();
+    let conf_cert = confusables_kernel_cert().unwrap();
     let conf_hash = trace_kernel(&mut tr_asc7, "asc7_confusables", &conf_cert);
 
     tr_asc7.section("ASC7 SEMANTIC KERNEL CERT");
     let semantic_predicates = vec![
-        SemanticPredicateDef { bit_index: 0, id: 1, kind: "intrinsic".to_string(), name: "upper_initial".to_string(), resource_hash_hex: None },
-        SemanticPredicateDef { bit_index: 1, id: 2, kind: "intrinsic".to_string(), name: "contains_confusable".to_string(), resource_hash_hex: None },
-        SemanticPredicateDef { bit_index: 2, id: 3, kind: "intrinsic".to_string(), name: "token_len_ge_6".to_string(), resource_hash_hex: None },
+        SemanticPredicateDef { bit_index: 0, id: 1, kind: "intrinsic".to_string(), name: "upper_initial".to_string(), resource_hash: None },
+        SemanticPredicateDef { bit_index: 1, id: 2, kind: "intrinsic".to_string(), name: "contains_confusable".to_string(), resource_hash: None },
+        SemanticPredicateDef { bit_index: 2, id: 3, kind: "intrinsic".to_string(), name: "token_len_ge_6".to_string(), resource_hash: None },
     ];
-    let asc7_sem_cert = asc7_semantic_kernel_cert(&asc7_hash, semantic_predicates);
+    let asc7_sem_cert = asc7_semantic_kernel_cert(&asc7_cert, semantic_predicates).unwrap();
     let asc7_sem_hash = trace_kernel(&mut tr_asc7, "asc7_semantic", &asc7_sem_cert);
 
     tr_asc7.section("CONFUSABLES EXPLANATION");
@@ -92,8 +92,8 @@ fn main() {
     }
 
     tr_struct.section("QE DOMAIN DIGEST");
-    let qe_digest = domain_digest_hex(&domain_qe);
-    tr_struct.kv("domain_digest_hex(QE)", &qe_digest);
+    let qe_digest = domain_digest(&domain_qe, HashAlg::Sha256);
+    tr_struct.kv("domain_digest_hex(QE)", &qe_digest.hex());
 
     tr_struct.section("STAGE 2: DEFINING NATURAL ENUMERATIONS (NE)");
     let ne_domain = domain_ne(40);
@@ -138,8 +138,8 @@ fn main() {
         Test { id_norm: id7.clone(), f: t_num_abs_le_5 },
     ]);
 
-    let tests_hash = tests_hash_hex(&tf, "impl:static_v4_bucket_bits_proper_numabs");
-    tr_sembit.kv("tests_hash_hex", &tests_hash);
+    let tests_hash = tests_digest(&tf, "impl:static_v4_bucket_bits_proper_numabs", HashAlg::Sha256);
+    tr_sembit.kv("tests_hash_hex", &tests_hash.hex());
     tr_sembit.kv("test_id_1", &id1);
     tr_sembit.kv("test_id_2", &id2);
     tr_sembit.kv("test_id_3", &id3);
@@ -403,7 +403,7 @@ fn main() {
     }
 
     tr_sembit.section("DOMAIN DIGEST (QE)");
-    tr_sembit.kv("domain_digest_hex(QE)", &qe_digest);
+    tr_sembit.kv("domain_digest_hex(QE)", &qe_digest.hex());
 
     tr_sembit.section("QUOTIENT: EXECUTE TESTS → BUILD SIGNATURES → PARTITION");
     let q: Quotient<QE> = Quotient::from_signatures(&domain_qe, |x| {
@@ -499,45 +499,49 @@ fn main() {
         }
     }
 
-    let qdig = quotient_digest_hex(&q);
-    tr_sembit.kv("quotient_digest_hex", &qdig);
+    let qdig = quotient_digest(&q, HashAlg::Sha256);
+    tr_sembit.kv("quotient_digest_hex", &qdig.hex());
 
     tr_sembit.section("SEMBITS CERT: EMBED UPSTREAM HASHES");
     let sb_cert = sembit_kernel_cert(
-        &asc7_hash,
-        &conf_hash,
+        &asc7_cert,
+        &conf_cert,
         &tests_hash,
         &qe_digest,
         q.size(),
         h,
         &qdig,
-    );
+    )
+    .unwrap();
     let sb_hash = trace_kernel(&mut tr_sembit, "sembit", &sb_cert);
 
     tr_sembit.section("CERT CHAIN: asc7 → confusables → asc7_semantic → sembit");
     let chain = CertChain::build(vec![
-        CertItem { name: "asc7".to_string(), hash_hex: asc7_hash.clone() },
-          CertItem { name: "asc7_semantic".to_string(), hash_hex: asc7_sem_hash.clone() },
-        CertItem { name: "asc7_confusables".to_string(), hash_hex: conf_hash.clone() },
-        CertItem { name: "sembit".to_string(), hash_hex: sb_hash.clone() },
+        CertItem::new("asc7", asc7_hash.clone()),
+          CertItem::new("asc7_semantic", asc7_sem_hash.clone()),
+        CertItem::new("asc7_confusables", conf_hash.clone()),
+        CertItem::new("sembit", sb_hash.clone()),
     ]);
-    tr_sembit.kv("chain_hash", &chain.chain_hash_hex);
+    tr_sembit.kv("chain_hash", &chain.chain_hash.hex());
+
+    let linked = CertChain::build_linked(chain.items.clone());
+    tr_sembit.kv("linked_chain_hash", &linked.chain_hash.hex());
 
     tr_sembit.section("THE ANCHOR");
-    tr_sembit.kv("chain_hash", &chain.chain_hash_hex);
+    tr_sembit.kv("chain_hash", &chain.chain_hash.hex());
     tr_sembit.kv("status", "VERIFIED & TAMPER-PROOF");
 
     tr_sembit.section("SUMMARY (FOR HUMANS)");
     let mut summary = BTreeMap::new();
-    summary.insert("asc7_semantic_hash".to_string(), asc7_sem_hash);
-    summary.insert("asc7_hash".to_string(), asc7_hash);
-    summary.insert("confusables_hash".to_string(), conf_hash);
-    summary.insert("domain_qe_digest".to_string(), qe_digest);
+    summary.insert("asc7_semantic_hash".to_string(), asc7_sem_hash.hex());
+    summary.insert("asc7_hash".to_string(), asc7_hash.hex());
+    summary.insert("confusables_hash".to_string(), conf_hash.hex());
+    summary.insert("domain_qe_digest".to_string(), qe_digest.hex());
     summary.insert("domain_ne_digest".to_string(), ne_digest);
     summary.insert("domain_ze_digest".to_string(), ze_digest);
-    summary.insert("tests_hash".to_string(), tests_hash);
-    summary.insert("sembit_hash".to_string(), sb_hash);
-    summary.insert("chain_hash".to_string(), chain.chain_hash_hex);
+    summary.insert("tests_hash".to_string(), tests_hash.hex());
+    summary.insert("sembit_hash".to_string(), sb_hash.hex());
+    summary.insert("chain_hash".to_string(), chain.chain_hash.hex());
 
     let json = serde_json::to_string_pretty(&summary).unwrap();
     tr_sembit.line(&json);
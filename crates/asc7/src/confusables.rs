@@ -2,6 +2,10 @@ use std::collections::BTreeMap;
 
 use collapse_core::canon::Canon;
 use collapse_core::cert::KernelCert;
+use collapse_core::kernel::Kernel;
+use collapse_core::schema::SchemaError;
+
+use crate::kernel::ConfusablesKernel;
 
 pub fn confusables_min_table() -> BTreeMap<String, String> {
     let mut m = BTreeMap::new();
@@ -30,7 +34,8 @@ pub fn confusables_min_table() -> BTreeMap<String, String> {
     m
 }
 
-pub fn confusables_kernel_cert() -> KernelCert {
+/// Payload of the `asc7_confusables` kernel: `confusables_min_table` as `{src, dst}` pairs.
+pub fn confusables_payload() -> Canon {
     let table = confusables_min_table();
 
     let mut obj = BTreeMap::new();
@@ -44,6 +49,9 @@ pub fn confusables_kernel_cert() -> KernelCert {
         pairs.push(Canon::Obj(p));
     }
     obj.insert("pairs".to_string(), Canon::Arr(pairs));
+    Canon::Obj(obj)
+}
 
-    KernelCert::new("asc7_confusables", "1.0.0", Canon::Obj(obj))
+pub fn confusables_kernel_cert() -> Result<KernelCert, Vec<SchemaError>> {
+    ConfusablesKernel.cert()
 }
@@ -0,0 +1,104 @@
+use std::collections::BTreeSet;
+
+use collapse_core::canon::Canon;
+use collapse_core::cert::CertItem;
+use collapse_core::kernel::{DuplicateKernel, Kernel, KernelError, KernelInputs, KernelRegistry};
+use collapse_core::schema::Schema;
+use collapse_core::{FromCanon, ToCanon};
+
+use crate::confusables::confusables_payload;
+use crate::profile::{ABLATION_RULE, Asc7KernelCert, Asc7Profile, Asc7SemanticKernelCert, FILTER_RULE};
+use crate::schema::{asc7_schema, confusables_schema, semantic_schema};
+
+impl Kernel for Asc7KernelCert {
+    const NAME: &'static str = "asc7";
+    const VERSION: &'static str = "1.0.0";
+    const LEGACY_HASH: bool = true;
+
+    fn payload(&self) -> Canon {
+        self.to_canon()
+    }
+
+    fn schema() -> Option<Schema> {
+        Some(asc7_schema())
+    }
+
+    /// Recompiles the named profile. Only `Asc7Profile::builtin` names can be
+    /// checked this way; a custom profile is not recoverable from its payload.
+    fn verify(payload: &Canon, _inputs: &KernelInputs<'_>) -> Result<(), KernelError> {
+        let cert = Asc7KernelCert::from_canon(payload)?;
+        let profile = Asc7Profile::builtin(&cert.profile_name)
+            .ok_or_else(|| KernelError::invalid("profile_name", format!("unknown profile {:?}", cert.profile_name)))?;
+        let expected = Asc7KernelCert::from_profile(&profile).to_canon();
+        KernelError::check_eq(&expected, payload, "differs from the compiled profile")
+    }
+}
+
+/// The `asc7_confusables` kernel. It has no parameters: the table is built in.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConfusablesKernel;
+
+impl Kernel for ConfusablesKernel {
+    const NAME: &'static str = "asc7_confusables";
+    const VERSION: &'static str = "1.0.0";
+    const LEGACY_HASH: bool = true;
+
+    fn payload(&self) -> Canon {
+        confusables_payload()
+    }
+
+    fn schema() -> Option<Schema> {
+        Some(confusables_schema())
+    }
+
+    fn verify(payload: &Canon, _inputs: &KernelInputs<'_>) -> Result<(), KernelError> {
+        KernelError::check_eq(&confusables_payload(), payload, "differs from the built-in table")
+    }
+}
+
+impl Kernel for Asc7SemanticKernelCert {
+    const NAME: &'static str = "asc7_semantic";
+    const VERSION: &'static str = "1.0.0";
+
+    fn payload(&self) -> Canon {
+        self.to_canon()
+    }
+
+    fn deps_of(payload: &Canon) -> Result<Vec<CertItem>, KernelError> {
+        let cert = Asc7SemanticKernelCert::from_canon(payload)?;
+        Ok(vec![CertItem::new("asc7", cert.base_kernel_hash)])
+    }
+
+    fn schema() -> Option<Schema> {
+        Some(semantic_schema())
+    }
+
+    /// The base `asc7` cert must be an input, the rules must be the ones this
+    /// crate implements, and each predicate needs its own id and signature bit.
+    fn verify(payload: &Canon, inputs: &KernelInputs<'_>) -> Result<(), KernelError> {
+        let cert = Asc7SemanticKernelCert::from_canon(payload)?;
+        inputs.require("asc7", &cert.base_kernel_hash)?;
+        if cert.ablation_rule != ABLATION_RULE {
+            return Err(KernelError::invalid("ablation_rule", "not the implemented rule"));
+        }
+        if cert.filter_rule != FILTER_RULE {
+            return Err(KernelError::invalid("filter_rule", "not the implemented rule"));
+        }
+        let (mut bits, mut ids) = (BTreeSet::new(), BTreeSet::new());
+        for (i, p) in cert.predicates.iter().enumerate() {
+            if p.bit_index >= 64 || !bits.insert(p.bit_index) {
+                return Err(KernelError::invalid(&format!("predicates[{i}].bit_index"), "out of range or reused"));
+            }
+            if !ids.insert(p.id) {
+                return Err(KernelError::invalid(&format!("predicates[{i}].id"), "reused"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Verifiers for every kernel this crate certifies.
+pub fn register_asc7_kernels(registry: &mut KernelRegistry) -> Result<(), DuplicateKernel> {
+    registry.register::<Asc7KernelCert>()?.register::<ConfusablesKernel>()?.register::<Asc7SemanticKernelCert>()?;
+    Ok(())
+}
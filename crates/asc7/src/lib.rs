@@ -2,6 +2,8 @@ pub mod role;
 pub mod profile;
 pub mod normalize;
 pub mod confusables;
+pub mod schema;
+pub mod kernel;
 
 pub use role::{CharRole, classify_role};
 pub use profile::{
@@ -17,3 +19,5 @@ pub use profile::{
     signature_matches,
 };
 pub use normalize::{normalize_str, verify_terminal};
+pub use schema::{asc7_schema, confusables_schema, semantic_schema};
+pub use kernel::{ConfusablesKernel, register_asc7_kernels};
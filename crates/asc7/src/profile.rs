@@ -1,11 +1,14 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
 use crate::role::{CharRole, classify_role};
-use collapse_core::canon::Canon;
 use collapse_core::cert::KernelCert;
+use collapse_core::digest::{Sha256Digest, TaggedDigest};
+use collapse_core::kernel::Kernel;
+use collapse_core::schema::SchemaError;
+use collapse_core::{FromCanon, ToCanon};
 
 /// Universe: printable ASCII 0x20..=0x7E (95 chars)
 pub fn ascii_universe() -> Vec<char> {
@@ -119,7 +122,7 @@ impl Asc7Profile {
         let mut witness_alphabet = Vec::new();
         let mut rep_map = vec!['\0'; n];
 
-        for (_root, class) in &class_map {
+        for class in class_map.values() {
             let rep = pick_rep(class);
             witness_alphabet.push(rep);
             for ch in class {
@@ -132,24 +135,24 @@ impl Asc7Profile {
         // Graph hash covers: universe bytes, equivalence classes, witness alphabet
         let mut hasher = Sha256::new();
         for ch in &universe {
-            hasher.update(&[*ch as u8]);
+            hasher.update([*ch as u8]);
         }
 
         // Sort classes by representative to be deterministic
         let mut classes: Vec<Vec<char>> = class_map.values().cloned().collect();
         classes.sort_by_key(|cls| pick_rep(cls));
         for cls in &classes {
-            hasher.update(&[0u8]);
+            hasher.update([0u8]);
             let mut sorted = cls.clone();
             sorted.sort();
             for ch in &sorted {
-                hasher.update(&[*ch as u8]);
+                hasher.update([*ch as u8]);
             }
         }
 
-        hasher.update(&[1u8]);
+        hasher.update([1u8]);
         for ch in &witness_alphabet {
-            hasher.update(&[*ch as u8]);
+            hasher.update([*ch as u8]);
         }
 
         let graph_hash: [u8; 32] = hasher.finalize().into();
@@ -178,6 +181,15 @@ impl Asc7Profile {
         })
     }
 
+    /// The named profiles this crate ships, by `params.name`.
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "code_safe" => Some(Self::code_safe()),
+            "auth_safe" => Some(Self::auth_safe()),
+            _ => None,
+        }
+    }
+
     pub fn auth_safe() -> Self {
         let mut case_pairs = Vec::new();
         for (lo, hi) in ('a'..='z').zip('A'..='Z') {
@@ -198,18 +210,15 @@ impl Asc7Profile {
 
 /// Collapse kernel cert for ASC7.
 /// Payload is canonical (no floats); kernel_hash = sha256(canon(payload)).
-pub fn asc7_kernel_cert(p: &Asc7Profile) -> KernelCert {
-    let mut obj = BTreeMap::new();
-    obj.insert("profile_name".to_string(), Canon::Str(p.params.name.clone()));
-    obj.insert("syntax_strict".to_string(), Canon::Bool(p.params.syntax_strict));
-    obj.insert("witness_len".to_string(), Canon::U64(p.witness_alphabet.len() as u64));
-    obj.insert("graph_hash_hex".to_string(), Canon::Str(hex::encode(p.graph_hash)));
-    KernelCert::new("asc7", "1.0.0", Canon::Obj(obj))
+pub fn asc7_kernel_cert(p: &Asc7Profile) -> Result<KernelCert, Vec<SchemaError>> {
+    Asc7KernelCert::from_profile(p).cert()
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, ToCanon, FromCanon)]
 pub struct Asc7KernelCert {
-    pub graph_hash_hex: String,
+    #[canon(rename = "graph_hash_hex")]
+    #[serde(rename = "graph_hash_hex")]
+    pub graph_hash: Sha256Digest,
     pub profile_name: String,
     pub syntax_strict: bool,
     pub witness_len: u64,
@@ -218,72 +227,46 @@ pub struct Asc7KernelCert {
 impl Asc7KernelCert {
     pub fn from_profile(p: &Asc7Profile) -> Self {
         Self {
-            graph_hash_hex: hex::encode(p.graph_hash),
+            graph_hash: Sha256Digest(p.graph_hash),
             profile_name: p.params.name.clone(),
             syntax_strict: p.params.syntax_strict,
             witness_len: p.witness_alphabet.len() as u64,
         }
     }
-
-    pub fn to_canon(&self) -> Canon {
-        let mut obj = BTreeMap::new();
-        obj.insert("graph_hash_hex".to_string(), Canon::Str(self.graph_hash_hex.clone()));
-        obj.insert("profile_name".to_string(), Canon::Str(self.profile_name.clone()));
-        obj.insert("syntax_strict".to_string(), Canon::Bool(self.syntax_strict));
-        obj.insert("witness_len".to_string(), Canon::U64(self.witness_len));
-        Canon::Obj(obj)
-    }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, ToCanon, FromCanon)]
 pub struct SemanticPredicateDef {
     pub bit_index: u8,
     pub id: u8,
     pub kind: String,
     pub name: String,
-    pub resource_hash_hex: Option<String>,
+    #[canon(rename = "resource_hash_hex")]
+    #[serde(rename = "resource_hash_hex")]
+    pub resource_hash: Option<TaggedDigest>,
 }
 
-impl SemanticPredicateDef {
-    pub fn to_canon(&self) -> Canon {
-        let mut obj = BTreeMap::new();
-        obj.insert("bit_index".to_string(), Canon::U64(self.bit_index as u64));
-        obj.insert("id".to_string(), Canon::U64(self.id as u64));
-        obj.insert("kind".to_string(), Canon::Str(self.kind.clone()));
-        obj.insert("name".to_string(), Canon::Str(self.name.clone()));
-        match &self.resource_hash_hex {
-            Some(v) => obj.insert("resource_hash_hex".to_string(), Canon::Str(v.clone())),
-            None => obj.insert("resource_hash_hex".to_string(), Canon::Null),
-        };
-        Canon::Obj(obj)
-    }
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, ToCanon, FromCanon)]
 pub struct Asc7SemanticKernelCert {
     pub ablation_rule: String,
-    pub base_kernel_hash_hex: String,
+    #[canon(rename = "base_kernel_hash_hex")]
+    #[serde(rename = "base_kernel_hash_hex")]
+    pub base_kernel_hash: TaggedDigest,
     pub filter_rule: String,
     pub predicate_registry_version: String,
     pub predicates: Vec<SemanticPredicateDef>,
 }
 
 impl Asc7SemanticKernelCert {
-    pub fn to_canon(&self) -> Canon {
-        let mut obj = BTreeMap::new();
-        obj.insert("ablation_rule".to_string(), Canon::Str(self.ablation_rule.clone()));
-        obj.insert("base_kernel_hash_hex".to_string(), Canon::Str(self.base_kernel_hash_hex.clone()));
-        obj.insert("filter_rule".to_string(), Canon::Str(self.filter_rule.clone()));
-        obj.insert("predicate_registry_version".to_string(), Canon::Str(self.predicate_registry_version.clone()));
-        obj.insert("predicates".to_string(), Canon::Arr(self.predicates.iter().map(|p| p.to_canon()).collect()));
-        Canon::Obj(obj)
-    }
-
-    pub fn to_kernel_cert(&self) -> KernelCert {
-        KernelCert::new("asc7_semantic", "1.0.0", self.to_canon())
+    /// Declares the `asc7` cert named by `base_kernel_hash` as its dep.
+    pub fn to_kernel_cert(&self) -> Result<KernelCert, Vec<SchemaError>> {
+        self.cert()
     }
 }
 
+pub(crate) const ABLATION_RULE: &str = "ablate_signature(sig, keep_mask) = sig & keep_mask";
+pub(crate) const FILTER_RULE: &str = "SET_BIT(mask,value): match iff (sig & mask) == value";
+
 pub fn semantic_mask_value(bit_index: u8, enabled: bool) -> (u64, u64) {
     let bit = 1u64 << bit_index;
     let mask = bit;
@@ -299,11 +282,15 @@ pub fn ablate_signature(sig: u64, keep_mask: u64) -> u64 {
     sig & keep_mask
 }
 
-pub fn asc7_semantic_kernel_cert(base_kernel_hash_hex: &str, predicates: Vec<SemanticPredicateDef>) -> KernelCert {
+/// Built on top of `base` (the `asc7` cert), which becomes its declared dep.
+pub fn asc7_semantic_kernel_cert(
+    base: &KernelCert,
+    predicates: Vec<SemanticPredicateDef>,
+) -> Result<KernelCert, Vec<SchemaError>> {
     Asc7SemanticKernelCert {
-        ablation_rule: "ablate_signature(sig, keep_mask) = sig & keep_mask".to_string(),
-        base_kernel_hash_hex: base_kernel_hash_hex.to_string(),
-        filter_rule: "SET_BIT(mask,value): match iff (sig & mask) == value".to_string(),
+        ablation_rule: ABLATION_RULE.to_string(),
+        base_kernel_hash: base.kernel_hash.clone(),
+        filter_rule: FILTER_RULE.to_string(),
         predicate_registry_version: "1.0.0".to_string(),
         predicates,
     }.to_kernel_cert()
@@ -0,0 +1,37 @@
+use collapse_core::schema::{ObjectSchema, Schema};
+
+/// Payload schemas for the `asc7`, `asc7_confusables` and `asc7_semantic` kernels,
+/// collected by `register_asc7_kernels`.
+pub fn asc7_schema() -> Schema {
+    ObjectSchema::new()
+        .required("graph_hash_hex", Schema::Hex(64))
+        .required("profile_name", Schema::Str)
+        .required("syntax_strict", Schema::Bool)
+        .required("witness_len", Schema::U64)
+        .build()
+}
+
+pub fn confusables_schema() -> Schema {
+    let pair = ObjectSchema::new().required("dst", Schema::Str).required("src", Schema::Str).build();
+    ObjectSchema::new()
+        .required("pairs", Schema::array_of(pair))
+        .required("table_size", Schema::U64)
+        .build()
+}
+
+pub fn semantic_schema() -> Schema {
+    let predicate = ObjectSchema::new()
+        .required("bit_index", Schema::U64)
+        .required("id", Schema::U64)
+        .required("kind", Schema::Str)
+        .required("name", Schema::Str)
+        .required("resource_hash_hex", Schema::Digest.nullable())
+        .build();
+    ObjectSchema::new()
+        .required("ablation_rule", Schema::Str)
+        .required("base_kernel_hash_hex", Schema::Digest)
+        .required("filter_rule", Schema::Str)
+        .required("predicate_registry_version", Schema::Str)
+        .required("predicates", Schema::array_of(predicate))
+        .build()
+}
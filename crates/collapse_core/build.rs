@@ -0,0 +1,29 @@
+//! Captures the compiler, target and enabled features for `Provenance::current`.
+//! The lock file is supplied by the caller: as a dependency, this crate cannot
+//! see the workspace that depends on it.
+
+use std::env;
+use std::process::Command;
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    // A provenance cert with an empty compiler would look valid, so fail instead.
+    let version = Command::new(&rustc)
+        .arg("-V")
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| panic!("cannot read the compiler version from `{rustc} -V`"));
+    println!("cargo:rustc-env=COLLAPSE_RUSTC_VERSION={version}");
+    println!("cargo:rustc-env=COLLAPSE_TARGET={}", env::var("TARGET").unwrap());
+
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(k, _)| k.strip_prefix("CARGO_FEATURE_").map(|f| f.to_lowercase().replace('_', "-")))
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=COLLAPSE_FEATURES={}", features.join(","));
+    println!("cargo:rerun-if-changed=build.rs");
+}
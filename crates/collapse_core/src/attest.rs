@@ -0,0 +1,269 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use crate::bundle::{BundleError, CertBundle};
+use crate::canon::{Canon, canon_bytes};
+use crate::convert::{FromCanonError, obj_field};
+use crate::digest::{HashAlg, TaggedDigest};
+use crate::schema::SchemaRegistry;
+use crate::serde_canon::to_canon_value;
+use crate::sign::{CertSigner, PublicKey, SignatureError, TrustStore};
+
+pub const IN_TOTO_STATEMENT_V1: &str = "https://in-toto.io/Statement/v1";
+
+/// DSSE `payloadType` for in-toto statements.
+pub const IN_TOTO_PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+
+/// `predicateType` of a spine attestation; the predicate is the run's `CertBundle`.
+pub const SPINE_PREDICATE_TYPE_V1: &str =
+    "https://github.com/mauludsadiq/Computation_Foundational_Semantics/attestation/spine/v1";
+
+/// Subject name under which the chain root is attested, next to one subject per cert.
+pub const CHAIN_SUBJECT_NAME: &str = "collapse:cert-chain";
+
+/// in-toto DigestSet key for `alg`.
+pub fn in_toto_digest_name(alg: HashAlg) -> &'static str {
+    match alg {
+        HashAlg::Sha256 => "sha256",
+        HashAlg::Sha512 => "sha512",
+        HashAlg::Sha3_256 => "sha3_256",
+        HashAlg::Blake3 => "blake3",
+    }
+}
+
+/// DSSE v1 pre-authentication encoding: `"DSSEv1" SP len(type) SP type SP len(body) SP body`.
+pub fn dsse_pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut out = format!("DSSEv1 {} {payload_type} {} ", payload_type.len(), payload.len()).into_bytes();
+    out.extend_from_slice(payload);
+    out
+}
+
+/// One DSSE signature. `keyid` is the signer's hex Ed25519 public key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DsseSignature {
+    pub keyid: String,
+    pub sig: Vec<u8>,
+}
+
+/// A DSSE envelope. `payload` holds the raw (decoded) statement bytes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DsseEnvelope {
+    pub payload_type: String,
+    pub payload: Vec<u8>,
+    pub signatures: Vec<DsseSignature>,
+}
+
+/// Why an attestation was rejected on import.
+#[derive(Debug)]
+pub enum AttestationError {
+    /// The envelope is not DSSE JSON, or a field is not valid base64.
+    Envelope(String),
+    /// A `payloadType`, `_type` or `predicateType` other than the spine's.
+    UnsupportedType(String),
+    Signature(SignatureError),
+    /// The payload is not JSON, or holds a value canonical JSON cannot (a float).
+    StatementParse(String),
+    Statement(FromCanonError),
+    Bundle(BundleError),
+    /// A cert, or the chain, that has no subject.
+    MissingSubject { name: String },
+    /// A subject whose digest is not the recomputed hash.
+    SubjectMismatch { name: String, expected: TaggedDigest },
+    /// A subject the predicate does not account for.
+    UnexpectedSubject { name: String },
+    /// Two certs, or a cert and the chain root, that would share a subject name.
+    AmbiguousSubject { name: String },
+}
+
+impl fmt::Display for AttestationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttestationError::Envelope(msg) => write!(f, "malformed DSSE envelope: {msg}"),
+            AttestationError::UnsupportedType(t) => write!(f, "unsupported attestation type {t:?}"),
+            AttestationError::Signature(e) => write!(f, "envelope: {e}"),
+            AttestationError::StatementParse(e) => write!(f, "statement is not JSON: {e}"),
+            AttestationError::Statement(e) => write!(f, "malformed statement: {e}"),
+            AttestationError::Bundle(e) => write!(f, "predicate: {e}"),
+            AttestationError::MissingSubject { name } => write!(f, "no subject for {name}"),
+            AttestationError::SubjectMismatch { name, expected } => {
+                write!(f, "subject {name} does not carry the recomputed digest {expected}")
+            }
+            AttestationError::UnexpectedSubject { name } => write!(f, "subject {name} is not in the predicate"),
+            AttestationError::AmbiguousSubject { name } => write!(f, "subject name {name} is not unique in the bundle"),
+        }
+    }
+}
+
+impl std::error::Error for AttestationError {}
+
+impl From<FromCanonError> for AttestationError {
+    fn from(e: FromCanonError) -> Self {
+        AttestationError::Statement(e)
+    }
+}
+
+/// `(name, digest)` for every cert, then the chain root.
+fn subjects_of(bundle: &CertBundle) -> Vec<(String, TaggedDigest)> {
+    let mut out: Vec<(String, TaggedDigest)> =
+        bundle.certs.iter().map(|c| (c.kernel_name.clone(), c.kernel_hash.clone())).collect();
+    out.push((CHAIN_SUBJECT_NAME.to_string(), bundle.chain.chain_hash.clone()));
+    out
+}
+
+fn subject_canon(name: &str, digest: &TaggedDigest) -> Canon {
+    let mut set = BTreeMap::new();
+    set.insert(in_toto_digest_name(digest.alg()).to_string(), Canon::Str(digest.hex()));
+    let mut obj = BTreeMap::new();
+    obj.insert("digest".to_string(), Canon::Obj(set));
+    obj.insert("name".to_string(), Canon::Str(name.to_string()));
+    Canon::Obj(obj)
+}
+
+/// in-toto Statement v1 with one subject per cert plus the chain root; the
+/// predicate is `bundle.to_canon()`.
+pub fn in_toto_statement(bundle: &CertBundle) -> Canon {
+    let subjects = subjects_of(bundle).iter().map(|(n, d)| subject_canon(n, d)).collect();
+    let mut obj = BTreeMap::new();
+    obj.insert("_type".to_string(), Canon::Str(IN_TOTO_STATEMENT_V1.to_string()));
+    obj.insert("predicate".to_string(), bundle.to_canon());
+    obj.insert("predicateType".to_string(), Canon::Str(SPINE_PREDICATE_TYPE_V1.to_string()));
+    obj.insert("subject".to_string(), Canon::Arr(subjects));
+    Canon::Obj(obj)
+}
+
+/// Wrap `bundle` as an in-toto statement (canonical JSON) in a DSSE envelope
+/// signed by each of `signers`.
+pub fn export_attestation(bundle: &CertBundle, signers: &[&CertSigner]) -> DsseEnvelope {
+    let mut env = DsseEnvelope {
+        payload_type: IN_TOTO_PAYLOAD_TYPE.to_string(),
+        payload: canon_bytes(&in_toto_statement(bundle)),
+        signatures: Vec::new(),
+    };
+    for s in signers {
+        env.sign(s);
+    }
+    env
+}
+
+/// Checks the envelope signatures against `trust`, then re-verifies the bundle
+/// in the predicate (payload schemas, every kernel hash, the chain, deps) and requires the
+/// subjects to be exactly its certs and chain root under the recomputed digests.
+/// A bundle with a cert named `CHAIN_SUBJECT_NAME`, or two certs of one kernel,
+/// has no unambiguous subjects and is rejected. The statement may be any JSON
+/// layout another producer chose; only its parsed values are canonicalized.
+pub fn import_attestation(
+    env: &DsseEnvelope,
+    trust: &TrustStore,
+    threshold: usize,
+    schemas: &SchemaRegistry,
+) -> Result<CertBundle, AttestationError> {
+    if env.payload_type != IN_TOTO_PAYLOAD_TYPE {
+        return Err(AttestationError::UnsupportedType(env.payload_type.clone()));
+    }
+    env.verify_trusted(trust, threshold)?;
+    let json: serde_json::Value =
+        serde_json::from_slice(&env.payload).map_err(|e| AttestationError::StatementParse(e.to_string()))?;
+    let statement = to_canon_value(&json).map_err(|e| AttestationError::StatementParse(e.to_string()))?;
+    let Canon::Obj(obj) = &statement else {
+        return Err(FromCanonError::expected("object", &statement).into());
+    };
+    for (key, want) in [("_type", IN_TOTO_STATEMENT_V1), ("predicateType", SPINE_PREDICATE_TYPE_V1)] {
+        let got: String = obj_field(obj, key)?;
+        if got != want {
+            return Err(AttestationError::UnsupportedType(got));
+        }
+    }
+    let predicate = obj.get("predicate").unwrap_or(&Canon::Null);
+    let bundle = CertBundle::from_canon(predicate, schemas).map_err(AttestationError::Bundle)?;
+    let expected = subjects_of(&bundle);
+    let mut names = BTreeSet::new();
+    if let Some((name, _)) = expected.iter().find(|(n, _)| !names.insert(n.as_str())) {
+        return Err(AttestationError::AmbiguousSubject { name: name.clone() });
+    }
+
+    let mut seen: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    let raw: Vec<Canon> = obj_field(obj, "subject")?;
+    for (i, s) in raw.iter().enumerate() {
+        let Canon::Obj(s) = s else {
+            return Err(FromCanonError::expected("object", s).at(&format!("[{i}]")).at("subject").into());
+        };
+        let name: String = obj_field(s, "name").map_err(|e| e.at(&format!("[{i}]")).at("subject"))?;
+        let digest = obj_field(s, "digest").map_err(|e| e.at(&format!("[{i}]")).at("subject"))?;
+        if seen.insert(name.clone(), digest).is_some() {
+            let msg = format!("duplicate subject {name:?}");
+            return Err(FromCanonError::new(msg).at(&format!("[{i}]")).at("subject").into());
+        }
+    }
+    for (name, digest) in &expected {
+        let set = seen.get(name).ok_or_else(|| AttestationError::MissingSubject { name: name.clone() })?;
+        if set.get(in_toto_digest_name(digest.alg())) != Some(&digest.hex()) {
+            return Err(AttestationError::SubjectMismatch { name: name.clone(), expected: digest.clone() });
+        }
+    }
+    if let Some(extra) = seen.keys().find(|n| !names.contains(n.as_str())) {
+        return Err(AttestationError::UnexpectedSubject { name: extra.clone() });
+    }
+    Ok(bundle)
+}
+
+impl DsseEnvelope {
+    pub fn sign(&mut self, signer: &CertSigner) {
+        let sig = signer.sign_bytes(&dsse_pae(&self.payload_type, &self.payload)).to_vec();
+        self.signatures.push(DsseSignature { keyid: signer.public_key().to_string(), sig });
+    }
+
+    /// At least `threshold` distinct trusted keys validly signed the PAE. As
+    /// DSSE requires, signatures with an unparseable keyid, an untrusted key or
+    /// a bad signature are skipped rather than rejected.
+    pub fn verify_trusted(&self, trust: &TrustStore, threshold: usize) -> Result<Vec<String>, AttestationError> {
+        let pae = dsse_pae(&self.payload_type, &self.payload);
+        let valid: Vec<PublicKey> = self
+            .signatures
+            .iter()
+            .filter_map(|s| {
+                let public_key: PublicKey = s.keyid.parse().ok()?;
+                trust.name_of(&public_key)?;
+                let sig = <[u8; 64]>::try_from(s.sig.as_slice()).ok()?;
+                public_key.verify_bytes(&pae, &sig).then_some(public_key)
+            })
+            .collect();
+        trust.check_signers(&valid, threshold).map_err(AttestationError::Signature)
+    }
+
+    /// Standard DSSE JSON: `{"payload", "payloadType", "signatures": [{"keyid", "sig"}]}`,
+    /// with `payload` and `sig` in base64.
+    pub fn to_json(&self) -> String {
+        let sigs: Vec<serde_json::Value> = self
+            .signatures
+            .iter()
+            .map(|s| serde_json::json!({ "keyid": s.keyid, "sig": BASE64.encode(&s.sig) }))
+            .collect();
+        serde_json::json!({
+            "payload": BASE64.encode(&self.payload),
+            "payloadType": self.payload_type,
+            "signatures": sigs,
+        })
+        .to_string()
+    }
+
+    /// Accepts any JSON layout; only the fields DSSE defines are read.
+    pub fn from_json(bytes: &[u8]) -> Result<Self, AttestationError> {
+        let bad = |msg: &str| AttestationError::Envelope(msg.to_string());
+        let v: serde_json::Value = serde_json::from_slice(bytes).map_err(|e| AttestationError::Envelope(e.to_string()))?;
+        let field = |v: &serde_json::Value, key: &str| -> Result<String, AttestationError> {
+            v.get(key).and_then(|s| s.as_str()).map(str::to_string).ok_or_else(|| bad(&format!("{key} is not a string")))
+        };
+        let decode = |s: String, key: &str| BASE64.decode(s).map_err(|_| bad(&format!("{key} is not base64")));
+        let payload = decode(field(&v, "payload")?, "payload")?;
+        let payload_type = field(&v, "payloadType")?;
+        let raw = v.get("signatures").and_then(|s| s.as_array()).ok_or_else(|| bad("signatures is not an array"))?;
+        let mut signatures = Vec::with_capacity(raw.len());
+        for s in raw {
+            signatures.push(DsseSignature { keyid: field(s, "keyid")?, sig: decode(field(s, "sig")?, "sig")? });
+        }
+        Ok(Self { payload_type, payload, signatures })
+    }
+}
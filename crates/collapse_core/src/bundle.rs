@@ -0,0 +1,297 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::canon::{Canon, CanonMode, canon_bytes, canon_bytes_with};
+use crate::cbor::parse_cbor;
+use crate::cert::{CertChain, CertItem, CertLoadError, ChainMode, ChainVerifyError, KernelCert};
+use crate::convert::{FromCanonError, ToCanon, obj_field};
+use crate::digest::HashAlg;
+use crate::graph::{CertGraph, CertGraphError};
+use crate::kernel::{KernelCheck, KernelError, KernelInputs, KernelRegistry};
+use crate::parse::{CanonParseError, parse_canon, parse_canon_v2};
+use crate::revoke::{RevocationList, RevocationPolicy, RevokedItem};
+use crate::schema::SchemaRegistry;
+use crate::sign::{CertSignature, CertSigner, PublicKey, SignatureError, SignedSubject, TrustStore};
+
+/// Value of the `format` key in every bundle this version writes.
+pub const BUNDLE_FORMAT_V1: &str = "collapse/bundle/v1";
+
+/// Every cert of a run with full payloads, the chain over them, and free-form
+/// metadata. On disk it is one canonical JSON (v1) document, so the file bytes
+/// are themselves reproducible.
+#[derive(Clone, Debug)]
+pub struct CertBundle {
+    pub certs: Vec<KernelCert>,
+    pub chain: CertChain,
+    pub meta: BTreeMap<String, String>,
+    /// Detached signatures over the chain root or individual certs.
+    pub signatures: Vec<CertSignature>,
+}
+
+/// Why a bundle failed to read or verify.
+#[derive(Debug)]
+pub enum BundleError {
+    Io(std::io::Error),
+    Parse(CanonParseError),
+    Malformed(FromCanonError),
+    UnsupportedFormat(String),
+    Cert { index: usize, error: CertLoadError },
+    Chain(ChainVerifyError),
+    /// A cert whose name and hash do not appear in the chain.
+    CertNotInChain { name: String },
+    /// A chain item with no cert in the bundle to re-derive it from.
+    ItemWithoutCert { name: String },
+    Graph(Vec<CertGraphError>),
+    /// A signature over something that is not this bundle's chain or one of its certs.
+    UnboundSignature { public_key: PublicKey },
+    Signature(SignatureError),
+    /// A cert whose payload its registered kernel rejects.
+    Kernel { index: usize, error: KernelError },
+    /// A revoked cert, under `RevocationPolicy::Deny`.
+    Revoked(Box<RevokedItem>),
+    /// A second cert of a kernel a bundle holds at most once (`with_provenance`).
+    DuplicateCert { name: String },
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::Io(e) => write!(f, "bundle i/o: {e}"),
+            BundleError::Parse(e) => write!(f, "bundle is not canonical JSON: {e}"),
+            BundleError::Malformed(e) => write!(f, "malformed bundle: {e}"),
+            BundleError::UnsupportedFormat(s) => write!(f, "unsupported bundle format {s:?}"),
+            BundleError::Cert { index, error } => write!(f, "certs[{index}]: {error}"),
+            BundleError::Chain(e) => write!(f, "{e}"),
+            BundleError::CertNotInChain { name } => write!(f, "cert {name} is not in the chain"),
+            BundleError::ItemWithoutCert { name } => write!(f, "chain item {name} has no cert in the bundle"),
+            BundleError::Graph(errs) => {
+                let msgs: Vec<String> = errs.iter().map(|e| e.to_string()).collect();
+                write!(f, "dependency graph: {}", msgs.join("; "))
+            }
+            BundleError::UnboundSignature { public_key } => {
+                write!(f, "signature from {public_key} is not over this bundle")
+            }
+            BundleError::Signature(e) => write!(f, "{e}"),
+            BundleError::Kernel { index, error } => write!(f, "certs[{index}]: {error}"),
+            BundleError::Revoked(r) => {
+                write!(f, "certs[{}] ({}) is revoked: {}", r.index, r.name, r.revocation.reason)?;
+                match &r.revocation.superseded_by {
+                    Some(s) => write!(f, ", superseded by {s}"),
+                    None => Ok(()),
+                }
+            }
+            BundleError::DuplicateCert { name } => write!(f, "bundle already has a {name} cert"),
+        }
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+impl From<std::io::Error> for BundleError {
+    fn from(e: std::io::Error) -> Self {
+        BundleError::Io(e)
+    }
+}
+
+impl From<CanonParseError> for BundleError {
+    fn from(e: CanonParseError) -> Self {
+        BundleError::Parse(e)
+    }
+}
+
+impl From<FromCanonError> for BundleError {
+    fn from(e: FromCanonError) -> Self {
+        BundleError::Malformed(e)
+    }
+}
+
+impl CertBundle {
+    pub fn new(certs: Vec<KernelCert>, chain: CertChain) -> Self {
+        Self { certs, chain, meta: BTreeMap::new(), signatures: Vec::new() }
+    }
+
+    /// Bundle `certs` with a chain built over them, in order.
+    pub fn from_certs(certs: Vec<KernelCert>, mode: ChainMode, alg: HashAlg) -> Self {
+        let chain = CertChain::build_with(certs.iter().map(CertItem::from_cert).collect(), mode, alg);
+        Self::new(certs, chain)
+    }
+
+    pub fn with_meta(mut self, key: &str, value: &str) -> Self {
+        self.meta.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Sign the chain root and attach the signature.
+    pub fn sign_chain(&mut self, signer: &CertSigner) {
+        self.signatures.push(signer.sign_chain(&self.chain, &self.certs));
+    }
+
+    /// Panics if the bundle has no cert named `kernel_name`.
+    pub fn sign_cert(&mut self, signer: &CertSigner, kernel_name: &str) {
+        let cert = self.cert(kernel_name).unwrap_or_else(|| panic!("CertBundle::sign_cert: no cert {kernel_name}"));
+        self.signatures.push(signer.sign_cert(cert));
+    }
+
+    pub fn to_canon(&self) -> Canon {
+        let mut obj = BTreeMap::new();
+        obj.insert("certs".to_string(), Canon::Arr(self.certs.iter().map(cert_entry).collect()));
+        obj.insert("chain".to_string(), self.chain.to_canon());
+        obj.insert("format".to_string(), Canon::Str(BUNDLE_FORMAT_V1.to_string()));
+        let meta = self.meta.iter().map(|(k, v)| (k.clone(), Canon::Str(v.clone()))).collect();
+        obj.insert("meta".to_string(), Canon::Obj(meta));
+        if !self.signatures.is_empty() {
+            obj.insert("signatures".to_string(), self.signatures.to_canon());
+        }
+        Canon::Obj(obj)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        canon_bytes(&self.to_canon())
+    }
+
+    /// Loads every cert (payload checked against `schemas`, `kernel_hash`
+    /// recomputed), then `verify`.
+    pub fn from_canon(v: &Canon, schemas: &SchemaRegistry) -> Result<Self, BundleError> {
+        let Canon::Obj(obj) = v else {
+            return Err(FromCanonError::expected("object", v).into());
+        };
+        let format: String = obj_field(obj, "format")?;
+        if format != BUNDLE_FORMAT_V1 {
+            return Err(BundleError::UnsupportedFormat(format));
+        }
+        let entries: Vec<Canon> = obj_field(obj, "certs")?;
+        let mut certs = Vec::with_capacity(entries.len());
+        for (index, e) in entries.iter().enumerate() {
+            let cert = load_cert_entry(e, schemas).map_err(|error| BundleError::Cert { index, error })?;
+            certs.push(cert);
+        }
+        let chain = CertChain::from_canon(obj.get("chain").unwrap_or(&Canon::Null)).map_err(|e| e.at("chain"))?;
+        let meta: BTreeMap<String, String> = obj_field(obj, "meta")?;
+        let signatures = obj_field::<Option<Vec<CertSignature>>>(obj, "signatures")?.unwrap_or_default();
+        let bundle = Self { certs, chain, meta, signatures };
+        bundle.verify()?;
+        Ok(bundle)
+    }
+
+    pub fn from_bytes(bytes: &[u8], schemas: &SchemaRegistry) -> Result<Self, BundleError> {
+        Self::from_canon(&parse_canon(bytes)?, schemas)
+    }
+
+    /// Re-derives everything checkable: each cert hash, the chain hash (and links),
+    /// a one-to-one match between certs and chain items, and declared deps. Every
+    /// attached signature must be valid and over this bundle; trust is not checked
+    /// here, see `verify_trusted`.
+    pub fn verify(&self) -> Result<(), BundleError> {
+        for (index, c) in self.certs.iter().enumerate() {
+            c.verify().map_err(|error| BundleError::Cert { index, error })?;
+        }
+        self.chain.verify().map_err(BundleError::Chain)?;
+        for c in &self.certs {
+            if !self.chain.items.contains(&CertItem::from_cert(c)) {
+                return Err(BundleError::CertNotInChain { name: c.kernel_name.clone() });
+            }
+        }
+        for it in &self.chain.items {
+            if !self.certs.iter().any(|c| CertItem::from_cert(c) == *it) {
+                return Err(BundleError::ItemWithoutCert { name: it.name.clone() });
+            }
+        }
+        CertGraph::new(self.certs.clone()).validate(&self.chain).map_err(BundleError::Graph)?;
+        let chain_subject = SignedSubject::of_chain(&self.chain, &self.certs);
+        for sig in &self.signatures {
+            let bound = sig.subject == chain_subject || self.certs.iter().any(|c| sig.subject == SignedSubject::of_cert(c));
+            if !bound {
+                return Err(BundleError::UnboundSignature { public_key: sig.public_key });
+            }
+            if !sig.is_valid() {
+                return Err(BundleError::Signature(SignatureError::Invalid { public_key: sig.public_key }));
+            }
+        }
+        Ok(())
+    }
+
+    /// `verify`, then require the chain root to be signed by at least `threshold`
+    /// distinct keys from `trust`. Returns the trusted signers' names.
+    pub fn verify_trusted(&self, trust: &TrustStore, threshold: usize) -> Result<Vec<String>, BundleError> {
+        self.verify()?;
+        let subject = SignedSubject::of_chain(&self.chain, &self.certs);
+        trust.check(&subject, &self.signatures, threshold).map_err(BundleError::Signature)
+    }
+
+    /// `verify`, then look every cert (and so every declared dep, which must be
+    /// one of them) up in `revocations`, by `CertId` and by `kernel_hash`.
+    /// Under `Warn` the revoked certs are returned instead of failing.
+    pub fn verify_with_revocations(
+        &self,
+        revocations: &RevocationList,
+        policy: RevocationPolicy,
+    ) -> Result<Vec<RevokedItem>, BundleError> {
+        self.verify()?;
+        let revoked = revocations.revoked_certs(&self.certs);
+        match (policy, revoked.first()) {
+            (RevocationPolicy::Deny, Some(r)) => Err(BundleError::Revoked(Box::new(r.clone()))),
+            _ => Ok(revoked),
+        }
+    }
+
+    /// `verify`, then check every cert's payload with the kernel `registry`
+    /// resolves for its name and version. The bundle's certs are the inputs.
+    pub fn verify_kernels(&self, registry: &KernelRegistry) -> Result<Vec<KernelCheck>, BundleError> {
+        self.verify()?;
+        let inputs = KernelInputs::new(&self.certs);
+        self.certs
+            .iter()
+            .enumerate()
+            .map(|(index, c)| registry.verify_cert(c, &inputs).map_err(|error| BundleError::Kernel { index, error }))
+            .collect()
+    }
+
+    pub fn cert(&self, name: &str) -> Option<&KernelCert> {
+        self.certs.iter().find(|c| c.kernel_name == name)
+    }
+}
+
+/// v1 and JCS payloads survive a JSON round trip. Type-tagged modes do not
+/// (integer width, exact numbers), so their payload is stored as its encoded
+/// bytes under `payload_bytes` instead.
+fn cert_entry(c: &KernelCert) -> Canon {
+    let mut v = c.to_canon();
+    if let (Canon::Obj(obj), CanonMode::V2 | CanonMode::Cbor) = (&mut v, c.canon_mode) {
+        obj.remove("payload");
+        obj.insert("payload_bytes".to_string(), Canon::Str(hex::encode(canon_bytes_with(&c.payload, c.canon_mode))));
+    }
+    v
+}
+
+fn load_cert_entry(v: &Canon, schemas: &SchemaRegistry) -> Result<KernelCert, CertLoadError> {
+    let Canon::Obj(obj) = v else {
+        return Err(FromCanonError::expected("object", v).into());
+    };
+    let Some(encoded) = obj_field::<Option<String>>(obj, "payload_bytes")? else {
+        return KernelCert::from_canon(v, schemas);
+    };
+    let bad = |msg: String| CertLoadError::from(FromCanonError::new(msg).at("payload_bytes"));
+    let bytes = hex::decode(&encoded).map_err(|e| bad(e.to_string()))?;
+    let mode: String = obj_field(obj, "canon_mode")?;
+    let payload = match CanonMode::from_tag(&mode) {
+        Some(CanonMode::V2) => parse_canon_v2(&bytes),
+        Some(CanonMode::Cbor) => parse_cbor(&bytes),
+        _ => return Err(bad(format!("payload_bytes is not used with canon_mode {mode:?}"))),
+    }
+    .map_err(|e| bad(e.to_string()))?;
+    let mut obj = obj.clone();
+    obj.remove("payload_bytes");
+    obj.insert("payload".to_string(), payload);
+    KernelCert::from_canon(&Canon::Obj(obj), schemas)
+}
+
+pub fn write_bundle(path: impl AsRef<Path>, bundle: &CertBundle) -> Result<(), BundleError> {
+    std::fs::write(path, bundle.to_bytes())?;
+    Ok(())
+}
+
+/// Reads and fully re-verifies a bundle written by `write_bundle`.
+pub fn read_bundle(path: impl AsRef<Path>, schemas: &SchemaRegistry) -> Result<CertBundle, BundleError> {
+    CertBundle::from_bytes(&std::fs::read(path)?, schemas)
+}
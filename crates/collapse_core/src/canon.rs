@@ -1,68 +1,301 @@
 use std::collections::BTreeMap;
 
+use crate::cbor::write_cbor;
+use crate::exact::{Decimal, Rational};
+use crate::stream::CanonSink;
+
 /// Minimal canonical JSON-like value for stable hashing.
 /// Objects are stored in BTreeMap => keys sorted lexicographically.
 /// Arrays preserve order exactly as constructed.
 ///
 /// Note: We deliberately avoid floats in canonical payloads.
-/// If you need to store an f64, store it as a scaled integer.
-#[derive(Clone, Debug)]
+/// If you need to store an f64, store it as a `Decimal` with an explicit scale,
+/// or as an exact `Rational`.
+///
+/// v1 writes `Rat` and `Dec` as plain objects, so they come back from v1 bytes
+/// as `Obj`. That `Obj` is a different value; see `exact_from_v1_compat`.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Canon {
     Null,
     Bool(bool),
     I64(i64),
     U64(u64),
+    /// Reduced `num/den`. v1 and JCS write it as `{"den":..,"num":..}`.
+    Rat(Rational),
+    /// `mantissa * 10^-scale`. v1 and JCS write it as `{"mantissa":..,"scale":..}`.
+    Dec(Decimal),
     Str(String),
     Arr(Vec<Canon>),
     Obj(BTreeMap<String, Canon>),
 }
 
+impl Canon {
+    /// `self` with every object in exactly the v1 form of a `Rat` or `Dec`
+    /// (`{"den","num"}` reduced, `{"mantissa","scale"}`) replaced by that value.
+    /// For reading v1 bytes that are known to hold exact numbers; an object that
+    /// merely looks like one is converted too.
+    pub fn exact_from_v1_compat(&self) -> Canon {
+        match self {
+            Canon::Arr(xs) => Canon::Arr(xs.iter().map(Canon::exact_from_v1_compat).collect()),
+            Canon::Obj(map) => Rational::from_obj(self)
+                .map(Canon::Rat)
+                .or_else(|| Decimal::from_obj(self).map(Canon::Dec))
+                .unwrap_or_else(|| {
+                    Canon::Obj(map.iter().map(|(k, v)| (k.clone(), v.exact_from_v1_compat())).collect())
+                }),
+            _ => self.clone(),
+        }
+    }
+}
+
+/// Byte encoding used to canonicalize a `Canon` value before hashing.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum CanonMode {
+    /// Original encoding: UTF-8 byte key order, minimal escaping, raw control bytes.
+    #[default]
+    V1,
+    /// RFC 8785 (JCS): UTF-16 code-unit key order, JSON escaping of all control
+    /// characters. Integers beyond the IEEE-754 exact range are written as
+    /// decimal strings (RFC 8785 Appendix D).
+    Jcs,
+    /// Type-tagged, length-prefixed binary encoding: `I64(5)` and `U64(5)` differ.
+    V2,
+    /// Deterministic CBOR (RFC 8949 §4.2.1), see `cbor`.
+    Cbor,
+}
+
+impl CanonMode {
+    pub fn tag(&self) -> &'static str {
+        match self {
+            CanonMode::V1 => "v1",
+            CanonMode::Jcs => "jcs",
+            CanonMode::V2 => "v2",
+            CanonMode::Cbor => "cbor",
+        }
+    }
+
+    pub fn from_tag(s: &str) -> Option<Self> {
+        match s {
+            "v1" => Some(CanonMode::V1),
+            "jcs" => Some(CanonMode::Jcs),
+            "v2" => Some(CanonMode::V2),
+            "cbor" => Some(CanonMode::Cbor),
+            _ => None,
+        }
+    }
+}
+
+/// Largest integer magnitude JCS writes as a number (2^53 - 1).
+pub const JCS_MAX_SAFE_INTEGER: u64 = (1u64 << 53) - 1;
+
+/// Leading bytes of every v2 encoding. v1 and JCS output always starts with a
+/// printable ASCII byte, so the two can never collide.
+pub const CANON_V2_MAGIC: &[u8] = b"\x00canon/v2\x00";
+
+/// v2 type tags. Each value is `tag` followed by its body:
+/// integers as 8 big-endian bytes, strings as `u64` BE byte length + UTF-8,
+/// arrays as `u64` BE count + items, objects as `u64` BE count + (key string, value) pairs.
+pub mod v2_tag {
+    pub const NULL: u8 = b'N';
+    pub const FALSE: u8 = b'F';
+    pub const TRUE: u8 = b'T';
+    pub const I64: u8 = b'i';
+    pub const U64: u8 = b'u';
+    /// Body: `num` as 8 BE bytes, then `den` as 8 BE bytes.
+    pub const RAT: u8 = b'r';
+    /// Body: `scale` as 1 byte, then `mantissa` as 8 BE bytes.
+    pub const DEC: u8 = b'd';
+    pub const STR: u8 = b's';
+    pub const ARR: u8 = b'a';
+    pub const OBJ: u8 = b'o';
+}
+
 pub fn canon_bytes(v: &Canon) -> Vec<u8> {
     let mut out = Vec::new();
     write_canon(&mut out, v);
     out
 }
 
-fn write_canon(out: &mut Vec<u8>, v: &Canon) {
-    match v {
-        Canon::Null => out.extend_from_slice(b"null"),
-        Canon::Bool(true) => out.extend_from_slice(b"true"),
-        Canon::Bool(false) => out.extend_from_slice(b"false"),
-        Canon::I64(n) => out.extend_from_slice(n.to_string().as_bytes()),
-        Canon::U64(n) => out.extend_from_slice(n.to_string().as_bytes()),
-        Canon::Str(s) => {
-            out.push(b'"');
-            for &b in s.as_bytes() {
-                match b {
-                    b'\\' => out.extend_from_slice(b"\\\\"),
-                    b'"'  => out.extend_from_slice(b"\\\""),
-                    b'\n' => out.extend_from_slice(b"\\n"),
-                    b'\r' => out.extend_from_slice(b"\\r"),
-                    b'\t' => out.extend_from_slice(b"\\t"),
-                    _ => out.push(b),
-                }
-            }
-            out.push(b'"');
+/// Canonical bytes under an explicit mode. `CanonMode::V1` is identical to `canon_bytes`.
+pub fn canon_bytes_with(v: &Canon, mode: CanonMode) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_canon_with(&mut out, v, mode);
+    out
+}
+
+/// Stream the canonical encoding of `v` into any sink (e.g. a `HashSink`) without
+/// materializing the bytes. Output is byte-identical to `canon_bytes_with`.
+pub fn write_canon_with<S: CanonSink + ?Sized>(out: &mut S, v: &Canon, mode: CanonMode) {
+    match mode {
+        CanonMode::V1 => write_canon(out, v),
+        CanonMode::Jcs => write_jcs(out, v),
+        CanonMode::V2 => {
+            out.write_bytes(CANON_V2_MAGIC);
+            write_v2(out, v);
         }
+        CanonMode::Cbor => write_cbor(out, v),
+    }
+}
+
+pub(crate) fn write_canon<S: CanonSink + ?Sized>(out: &mut S, v: &Canon) {
+    match v {
+        Canon::Null => out.write_bytes(b"null"),
+        Canon::Bool(true) => out.write_bytes(b"true"),
+        Canon::Bool(false) => out.write_bytes(b"false"),
+        Canon::I64(n) => out.write_bytes(n.to_string().as_bytes()),
+        Canon::U64(n) => out.write_bytes(n.to_string().as_bytes()),
+        Canon::Rat(r) => write_canon(out, &r.to_obj()),
+        Canon::Dec(d) => write_canon(out, &d.to_obj()),
+        Canon::Str(s) => write_canon_str(out, s),
         Canon::Arr(xs) => {
-            out.push(b'[');
+            out.write_byte(b'[');
             for (i, x) in xs.iter().enumerate() {
-                if i != 0 { out.push(b','); }
+                if i != 0 { out.write_byte(b','); }
                 write_canon(out, x);
             }
-            out.push(b']');
+            out.write_byte(b']');
         }
         Canon::Obj(map) => {
-            out.push(b'{');
+            out.write_byte(b'{');
             let mut first = true;
             for (k, val) in map.iter() {
-                if !first { out.push(b','); }
+                if !first { out.write_byte(b','); }
                 first = false;
-                write_canon(out, &Canon::Str(k.clone()));
-                out.push(b':');
+                write_canon_str(out, k);
+                out.write_byte(b':');
                 write_canon(out, val);
             }
-            out.push(b'}');
+            out.write_byte(b'}');
+        }
+    }
+}
+
+pub(crate) fn write_canon_str<S: CanonSink + ?Sized>(out: &mut S, s: &str) {
+    out.write_byte(b'"');
+    for &b in s.as_bytes() {
+        match b {
+            b'\\' => out.write_bytes(b"\\\\"),
+            b'"'  => out.write_bytes(b"\\\""),
+            b'\n' => out.write_bytes(b"\\n"),
+            b'\r' => out.write_bytes(b"\\r"),
+            b'\t' => out.write_bytes(b"\\t"),
+            _ => out.write_byte(b),
+        }
+    }
+    out.write_byte(b'"');
+}
+
+fn write_jcs<S: CanonSink + ?Sized>(out: &mut S, v: &Canon) {
+    match v {
+        Canon::Null => out.write_bytes(b"null"),
+        Canon::Bool(true) => out.write_bytes(b"true"),
+        Canon::Bool(false) => out.write_bytes(b"false"),
+        Canon::I64(n) => write_jcs_int(out, n.unsigned_abs(), &n.to_string()),
+        Canon::U64(n) => write_jcs_int(out, *n, &n.to_string()),
+        Canon::Rat(r) => write_jcs(out, &r.to_obj()),
+        Canon::Dec(d) => write_jcs(out, &d.to_obj()),
+        Canon::Str(s) => write_jcs_str(out, s),
+        Canon::Arr(xs) => {
+            out.write_byte(b'[');
+            for (i, x) in xs.iter().enumerate() {
+                if i != 0 { out.write_byte(b','); }
+                write_jcs(out, x);
+            }
+            out.write_byte(b']');
+        }
+        Canon::Obj(map) => {
+            // RFC 8785 §3.2.3: sort by UTF-16 code units, not UTF-8 bytes.
+            let mut entries: Vec<(&String, &Canon)> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.write_byte(b'{');
+            for (i, (k, val)) in entries.into_iter().enumerate() {
+                if i != 0 { out.write_byte(b','); }
+                write_jcs_str(out, k);
+                out.write_byte(b':');
+                write_jcs(out, val);
+            }
+            out.write_byte(b'}');
+        }
+    }
+}
+
+/// A JSON number when a double holds it exactly, else the same digits as a string.
+fn write_jcs_int<S: CanonSink + ?Sized>(out: &mut S, magnitude: u64, digits: &str) {
+    if magnitude <= JCS_MAX_SAFE_INTEGER {
+        out.write_bytes(digits.as_bytes());
+    } else {
+        write_jcs_str(out, digits);
+    }
+}
+
+fn write_jcs_str<S: CanonSink + ?Sized>(out: &mut S, s: &str) {
+    out.write_byte(b'"');
+    for &b in s.as_bytes() {
+        match b {
+            b'\\' => out.write_bytes(b"\\\\"),
+            b'"'  => out.write_bytes(b"\\\""),
+            0x08  => out.write_bytes(b"\\b"),
+            0x0C  => out.write_bytes(b"\\f"),
+            b'\n' => out.write_bytes(b"\\n"),
+            b'\r' => out.write_bytes(b"\\r"),
+            b'\t' => out.write_bytes(b"\\t"),
+            0x00..=0x1F => out.write_bytes(format!("\\u{b:04x}").as_bytes()),
+            _ => out.write_byte(b),
+        }
+    }
+    out.write_byte(b'"');
+}
+
+fn write_v2_len<S: CanonSink + ?Sized>(out: &mut S, n: usize) {
+    out.write_bytes(&(n as u64).to_be_bytes());
+}
+
+fn write_v2_str<S: CanonSink + ?Sized>(out: &mut S, s: &str) {
+    write_v2_len(out, s.len());
+    out.write_bytes(s.as_bytes());
+}
+
+fn write_v2<S: CanonSink + ?Sized>(out: &mut S, v: &Canon) {
+    match v {
+        Canon::Null => out.write_byte(v2_tag::NULL),
+        Canon::Bool(false) => out.write_byte(v2_tag::FALSE),
+        Canon::Bool(true) => out.write_byte(v2_tag::TRUE),
+        Canon::I64(n) => {
+            out.write_byte(v2_tag::I64);
+            out.write_bytes(&n.to_be_bytes());
+        }
+        Canon::U64(n) => {
+            out.write_byte(v2_tag::U64);
+            out.write_bytes(&n.to_be_bytes());
+        }
+        Canon::Rat(r) => {
+            out.write_byte(v2_tag::RAT);
+            out.write_bytes(&r.num().to_be_bytes());
+            out.write_bytes(&r.den().to_be_bytes());
+        }
+        Canon::Dec(d) => {
+            out.write_byte(v2_tag::DEC);
+            out.write_byte(d.scale());
+            out.write_bytes(&d.mantissa().to_be_bytes());
+        }
+        Canon::Str(s) => {
+            out.write_byte(v2_tag::STR);
+            write_v2_str(out, s);
+        }
+        Canon::Arr(xs) => {
+            out.write_byte(v2_tag::ARR);
+            write_v2_len(out, xs.len());
+            for x in xs {
+                write_v2(out, x);
+            }
+        }
+        Canon::Obj(map) => {
+            out.write_byte(v2_tag::OBJ);
+            write_v2_len(out, map.len());
+            for (k, val) in map.iter() {
+                write_v2_str(out, k);
+                write_v2(out, val);
+            }
         }
     }
 }
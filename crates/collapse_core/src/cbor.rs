@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+
+use crate::canon::{Canon, canon_bytes};
+use crate::exact::{Decimal, MAX_DECIMAL_SCALE, Rational};
+use crate::parse::{CanonParseError, CanonParseErrorKind, MAX_CANON_DEPTH, parse_canon};
+use crate::stream::CanonSink;
+
+/// Deterministic CBOR (RFC 8949 §4.2.1 core rules) for `Canon`:
+/// shortest-form heads, definite lengths only, map keys sorted by their encoded bytes.
+///
+/// Like v1, CBOR does not record integer width: non-negative integers decode as
+/// `U64` and negative ones as `I64`, so v1 bytes and CBOR convert losslessly.
+///
+/// `Canon::Dec` uses tag 4 (decimal fraction, `[-scale, mantissa]`) and `Canon::Rat`
+/// tag 30 (rational, `[num, den]`). v1 has no tags, so they come back from v1
+/// bytes as maps; `Canon::exact_from_v1_compat` turns those back into numbers.
+pub fn cbor_bytes(v: &Canon) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_cbor(&mut out, v);
+    out
+}
+
+const MAJOR_UINT: u8 = 0;
+const MAJOR_NINT: u8 = 1;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+const TAG_DECIMAL: u64 = 4;
+const TAG_RATIONAL: u64 = 30;
+const SIMPLE_FALSE: u8 = 0xf4;
+const SIMPLE_TRUE: u8 = 0xf5;
+const SIMPLE_NULL: u8 = 0xf6;
+
+fn write_head<S: CanonSink + ?Sized>(out: &mut S, major: u8, n: u64) {
+    let m = major << 5;
+    if n < 24 {
+        out.write_byte(m | n as u8);
+    } else if n <= u8::MAX as u64 {
+        out.write_byte(m | 24);
+        out.write_byte(n as u8);
+    } else if n <= u16::MAX as u64 {
+        out.write_byte(m | 25);
+        out.write_bytes(&(n as u16).to_be_bytes());
+    } else if n <= u32::MAX as u64 {
+        out.write_byte(m | 26);
+        out.write_bytes(&(n as u32).to_be_bytes());
+    } else {
+        out.write_byte(m | 27);
+        out.write_bytes(&n.to_be_bytes());
+    }
+}
+
+fn encoded_key(k: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(k.len() + 9);
+    write_head(&mut out, MAJOR_TEXT, k.len() as u64);
+    out.extend_from_slice(k.as_bytes());
+    out
+}
+
+pub(crate) fn write_cbor<S: CanonSink + ?Sized>(out: &mut S, v: &Canon) {
+    match v {
+        Canon::Null => out.write_byte(SIMPLE_NULL),
+        Canon::Bool(false) => out.write_byte(SIMPLE_FALSE),
+        Canon::Bool(true) => out.write_byte(SIMPLE_TRUE),
+        Canon::I64(n) if *n >= 0 => write_head(out, MAJOR_UINT, *n as u64),
+        Canon::I64(n) => write_head(out, MAJOR_NINT, !(*n as u64)),
+        Canon::U64(n) => write_head(out, MAJOR_UINT, *n),
+        Canon::Rat(r) => {
+            write_head(out, MAJOR_TAG, TAG_RATIONAL);
+            write_head(out, MAJOR_ARRAY, 2);
+            write_cbor(out, &Canon::I64(r.num()));
+            write_head(out, MAJOR_UINT, r.den());
+        }
+        Canon::Dec(d) => {
+            write_head(out, MAJOR_TAG, TAG_DECIMAL);
+            write_head(out, MAJOR_ARRAY, 2);
+            write_cbor(out, &Canon::I64(-(d.scale() as i64)));
+            write_cbor(out, &Canon::I64(d.mantissa()));
+        }
+        Canon::Str(s) => {
+            write_head(out, MAJOR_TEXT, s.len() as u64);
+            out.write_bytes(s.as_bytes());
+        }
+        Canon::Arr(xs) => {
+            write_head(out, MAJOR_ARRAY, xs.len() as u64);
+            for x in xs {
+                write_cbor(out, x);
+            }
+        }
+        Canon::Obj(map) => {
+            // §4.2.1: bytewise order of encoded keys, i.e. shorter keys first.
+            let mut entries: Vec<(Vec<u8>, &Canon)> = map.iter().map(|(k, v)| (encoded_key(k), v)).collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            write_head(out, MAJOR_MAP, entries.len() as u64);
+            for (k, val) in entries {
+                out.write_bytes(&k);
+                write_cbor(out, val);
+            }
+        }
+    }
+}
+
+/// Strict inverse of `cbor_bytes`: rejects non-shortest heads, indefinite lengths,
+/// unsorted or duplicate keys, floats, tags, byte strings and trailing bytes, and
+/// maps in the form of a rational or decimal, which must be tagged.
+pub fn parse_cbor(bytes: &[u8]) -> Result<Canon, CanonParseError> {
+    let mut p = CborParser { bytes, pos: 0 };
+    let v = p.value(0)?;
+    if p.pos != bytes.len() {
+        return Err(p.err(CanonParseErrorKind::TrailingBytes));
+    }
+    Ok(v)
+}
+
+/// v1 canonical bytes -> deterministic CBOR.
+pub fn v1_bytes_to_cbor(v1: &[u8]) -> Result<Vec<u8>, CanonParseError> {
+    parse_canon(v1).map(|v| cbor_bytes(&v))
+}
+
+/// Deterministic CBOR -> v1 canonical bytes. Inverse of `v1_bytes_to_cbor`.
+pub fn cbor_to_v1_bytes(cbor: &[u8]) -> Result<Vec<u8>, CanonParseError> {
+    parse_cbor(cbor).map(|v| canon_bytes(&v))
+}
+
+struct CborParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl CborParser<'_> {
+    fn err(&self, kind: CanonParseErrorKind) -> CanonParseError {
+        CanonParseError { offset: self.pos, kind }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&[u8], CanonParseError> {
+        if self.bytes.len() - self.pos < n {
+            return Err(CanonParseError { offset: self.bytes.len(), kind: CanonParseErrorKind::UnexpectedEnd });
+        }
+        let s = &self.bytes[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(s)
+    }
+
+    /// Reads a head, returning (major, argument). Enforces shortest encoding.
+    fn head(&mut self) -> Result<(u8, u64), CanonParseError> {
+        let start = self.pos;
+        let ib = *self.take(1)?.first().unwrap();
+        let major = ib >> 5;
+        let info = ib & 0x1f;
+        let (n, min) = match info {
+            0..=23 => (info as u64, 0),
+            24 => (self.take(1)?[0] as u64, 24),
+            25 => (u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as u64, u8::MAX as u64 + 1),
+            26 => (u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64, u16::MAX as u64 + 1),
+            27 => (u64::from_be_bytes(self.take(8)?.try_into().unwrap()), u32::MAX as u64 + 1),
+            _ => return Err(CanonParseError { offset: start, kind: CanonParseErrorKind::UnexpectedByte(ib) }),
+        };
+        if major != 7 && n < min {
+            return Err(CanonParseError { offset: start, kind: CanonParseErrorKind::NonShortestHead });
+        }
+        Ok((major, n))
+    }
+
+    fn len(&mut self, n: u64, start: usize) -> Result<usize, CanonParseError> {
+        if n > (self.bytes.len() - self.pos) as u64 {
+            return Err(CanonParseError { offset: start, kind: CanonParseErrorKind::UnexpectedEnd });
+        }
+        Ok(n as usize)
+    }
+
+    fn text(&mut self, n: u64, start: usize) -> Result<String, CanonParseError> {
+        let n = self.len(n, start)?;
+        let body = self.pos;
+        let raw = self.take(n)?.to_vec();
+        String::from_utf8(raw).map_err(|_| CanonParseError { offset: body, kind: CanonParseErrorKind::InvalidUtf8 })
+    }
+
+    /// Body of tags 4 and 30: a two-element array of integers.
+    fn int_pair(&mut self, start: usize, depth: usize) -> Result<(Canon, Canon), CanonParseError> {
+        let bad = CanonParseError { offset: start, kind: CanonParseErrorKind::NonCanonicalNumber };
+        if self.head()? != (MAJOR_ARRAY, 2) {
+            return Err(bad);
+        }
+        let mut int = || match self.value(depth + 1)? {
+            n @ (Canon::I64(_) | Canon::U64(_)) => Ok(n),
+            _ => Err(bad.clone()),
+        };
+        Ok((int()?, int()?))
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Canon, CanonParseError> {
+        let start = self.pos;
+        let (major, n) = self.head()?;
+        match major {
+            MAJOR_UINT => Ok(Canon::U64(n)),
+            MAJOR_NINT => {
+                if n > i64::MAX as u64 {
+                    return Err(CanonParseError { offset: start, kind: CanonParseErrorKind::IntegerOverflow });
+                }
+                Ok(Canon::I64(-1 - n as i64))
+            }
+            MAJOR_TEXT => Ok(Canon::Str(self.text(n, start)?)),
+            MAJOR_ARRAY | MAJOR_MAP if depth + 1 > MAX_CANON_DEPTH => {
+                Err(CanonParseError { offset: start, kind: CanonParseErrorKind::TooDeep })
+            }
+            MAJOR_ARRAY => {
+                let n = self.len(n, start)?;
+                let mut xs = Vec::with_capacity(n);
+                for _ in 0..n {
+                    xs.push(self.value(depth + 1)?);
+                }
+                Ok(Canon::Arr(xs))
+            }
+            MAJOR_MAP => {
+                let n = self.len(n, start)?;
+                let mut map = BTreeMap::new();
+                let bytes = self.bytes;
+                let mut prev: Option<&[u8]> = None;
+                for _ in 0..n {
+                    let key_start = self.pos;
+                    let (kmajor, klen) = self.head()?;
+                    if kmajor != MAJOR_TEXT {
+                        return Err(CanonParseError {
+                            offset: key_start,
+                            kind: CanonParseErrorKind::UnexpectedByte(self.bytes[key_start]),
+                        });
+                    }
+                    let key = self.text(klen, key_start)?;
+                    let encoded = &bytes[key_start..self.pos];
+                    if let Some(p) = prev {
+                        if p == encoded {
+                            return Err(CanonParseError { offset: key_start, kind: CanonParseErrorKind::DuplicateKey });
+                        }
+                        if p > encoded {
+                            return Err(CanonParseError { offset: key_start, kind: CanonParseErrorKind::UnsortedKey });
+                        }
+                    }
+                    prev = Some(encoded);
+                    let val = self.value(depth + 1)?;
+                    map.insert(key, val);
+                }
+                Ok(Canon::Obj(map))
+            }
+            MAJOR_TAG if n == TAG_RATIONAL || n == TAG_DECIMAL => {
+                let bad = CanonParseError { offset: start, kind: CanonParseErrorKind::NonCanonicalNumber };
+                let i64_of = |v: Canon| match v {
+                    Canon::I64(n) => Some(n),
+                    Canon::U64(n) => i64::try_from(n).ok(),
+                    _ => None,
+                };
+                let (a, b) = self.int_pair(start, depth)?;
+                let v = if n == TAG_RATIONAL {
+                    // The denominator is a u64, so it may exceed i64::MAX.
+                    match (i64_of(a), b) {
+                        (Some(num), Canon::U64(den)) => Rational::from_parts(num, den).map(Canon::Rat),
+                        _ => None,
+                    }
+                } else {
+                    match (i64_of(a), i64_of(b)) {
+                        (Some(e), Some(m)) if (-(MAX_DECIMAL_SCALE as i64)..=0).contains(&e) => {
+                            Some(Canon::Dec(Decimal::new(m, (-e) as u8)))
+                        }
+                        _ => None,
+                    }
+                };
+                v.ok_or(bad)
+            }
+            7 => match self.bytes[start] {
+                SIMPLE_FALSE => Ok(Canon::Bool(false)),
+                SIMPLE_TRUE => Ok(Canon::Bool(true)),
+                SIMPLE_NULL => Ok(Canon::Null),
+                b => Err(CanonParseError { offset: start, kind: CanonParseErrorKind::UnexpectedByte(b) }),
+            },
+            _ => Err(CanonParseError { offset: start, kind: CanonParseErrorKind::UnexpectedByte(self.bytes[start]) }),
+        }
+    }
+}
@@ -1,30 +1,187 @@
 use std::collections::BTreeMap;
+use std::fmt;
 
-use crate::canon::{Canon, canon_bytes};
-use crate::digest::{Sha256Digest, sha256_bytes, sha256_hex};
+use crate::canon::{Canon, CanonMode, canon_bytes, write_canon_with};
+use crate::convert::{FromCanon, FromCanonError, ToCanon, obj_field};
+use crate::disclose::{DisclosureError, merkle_envelope_hash, payload_root};
+use crate::diff::{CanonDiff, canon_diff};
+use crate::digest::{HashAlg, TaggedDigest};
+use crate::merkle::{InclusionProof, inclusion_proof, merkle_leaf_hash, merkle_root};
+use crate::revoke::{RevocationList, RevocationPolicy, RevokedItem};
+use crate::schema::{SchemaError, SchemaRegistry};
+use crate::stream::{CanonSink, write_array, write_object};
+
+/// Domain-separation tag prefixed (with a trailing NUL) to envelope-hashed certs.
+pub const CERT_HASH_DOMAIN_V1: &str = "collapse/kernel-cert/v1";
+
+/// Prefix of `ChainMode::Linked` link hashes, in the same way.
+pub const CHAIN_LINK_DOMAIN_V1: &str = "collapse/cert-chain-link/v1";
+
+/// What `kernel_hash` commits to.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum CertHashScheme {
+    /// `hash(canon(payload))`. Name and version are not covered, so identical
+    /// payloads collide across kernels and versions. Kept for the pinned gates.
+    Legacy,
+    /// `hash(CERT_HASH_DOMAIN_V1 || 0x00 || canon({canon_mode, kernel_name, kernel_version, payload}))`,
+    /// with the envelope encoded under the cert's `canon_mode`. The default.
+    #[default]
+    EnvelopeV1,
+    /// Like `EnvelopeV1`, but the payload enters as the Merkle root of its
+    /// top-level fields, salted with `KernelCert::disclosure_salt`, so fields
+    /// can be disclosed one at a time (`KernelCert::disclose`).
+    MerkleV1,
+}
+
+impl CertHashScheme {
+    pub fn tag(&self) -> &'static str {
+        match self {
+            CertHashScheme::Legacy => "legacy",
+            CertHashScheme::EnvelopeV1 => "envelope/v1",
+            CertHashScheme::MerkleV1 => "merkle/v1",
+        }
+    }
+
+    pub fn from_tag(s: &str) -> Option<Self> {
+        match s {
+            "legacy" => Some(CertHashScheme::Legacy),
+            "envelope/v1" => Some(CertHashScheme::EnvelopeV1),
+            "merkle/v1" => Some(CertHashScheme::MerkleV1),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct KernelCert {
     pub kernel_name: String,
     pub kernel_version: String,
     pub payload: Canon,
-    pub kernel_hash: Sha256Digest,
+    /// Encoding of `payload` that `kernel_hash` was computed over.
+    pub canon_mode: CanonMode,
+    pub hash_scheme: CertHashScheme,
+    pub kernel_hash: TaggedDigest,
+    /// Upstream certs this one was derived from. `kernel_hash` covers them except
+    /// under `Legacy`, where the kernel's `deps_of` must read each from the payload. See `CertGraph`.
+    pub deps: Vec<CertItem>,
+    /// Salt of the `MerkleV1` field leaves, which that scheme requires. It stays
+    /// with the cert holder; without it, guessable fields could be confirmed.
+    pub disclosure_salt: Option<[u8; 32]>,
 }
 
 impl KernelCert {
-    pub fn new(kernel_name: &str, kernel_version: &str, payload: Canon) -> Self {
-        let bytes = canon_bytes(&payload);
-        let kernel_hash = sha256_bytes(&bytes);
-        Self {
+    /// v1 encoding, SHA-256, and a hash that commits to name and version
+    /// (`CertHashScheme::EnvelopeV1`), once `payload` passes its schema in `schemas`.
+    pub fn new_checked(
+        kernel_name: &str,
+        kernel_version: &str,
+        payload: Canon,
+        schemas: &SchemaRegistry,
+    ) -> Result<Self, Vec<SchemaError>> {
+        schemas.validate(kernel_name, kernel_version, &payload)?;
+        Ok(Self::new_unchecked(kernel_name, kernel_version, payload))
+    }
+
+    /// `new_checked` without the schema check, for payloads whose kernel has
+    /// no schema or that are meant not to match it.
+    pub fn new_unchecked(kernel_name: &str, kernel_version: &str, payload: Canon) -> Self {
+        let cert = Self {
             kernel_name: kernel_name.to_string(),
             kernel_version: kernel_version.to_string(),
             payload,
-            kernel_hash,
+            canon_mode: CanonMode::V1,
+            hash_scheme: CertHashScheme::EnvelopeV1,
+            kernel_hash: HashAlg::Sha256.hash(&[]),
+            deps: Vec::new(),
+            disclosure_salt: None,
+        };
+        cert.rehashed(HashAlg::Sha256)
+    }
+
+    /// Every builder keeps a `MerkleV1` cert salted, so only a hand-edited cert can panic here.
+    fn rehashed(mut self, alg: HashAlg) -> Self {
+        self.kernel_hash = compute_kernel_hash(&self, alg).expect("merkle/v1 cert without a disclosure_salt");
+        self
+    }
+
+    /// Opt this cert into the type-tagged v2 encoding. The hash changes meaning,
+    /// so the kernel's major version is bumped (`1.0.0` -> `2.0.0`). A cert that
+    /// is already v2 is returned unchanged.
+    pub fn to_v2(&self) -> Result<Self, VersionBumpError> {
+        if self.canon_mode == CanonMode::V2 {
+            return Ok(self.clone());
+        }
+        let version = bump_major(&self.kernel_version)?;
+        let cert = Self { kernel_version: version, canon_mode: CanonMode::V2, ..self.clone() };
+        Ok(cert.rehashed(self.kernel_hash.alg()))
+    }
+
+    /// Same cert encoded under `canon_mode` and re-hashed. Unlike `to_v2`, the
+    /// version is left as it is.
+    pub fn with_canon_mode(&self, canon_mode: CanonMode) -> Self {
+        Self { canon_mode, ..self.clone() }.rehashed(self.kernel_hash.alg())
+    }
+
+    /// Same cert re-hashed under `alg`. Name, version and payload are unchanged;
+    /// the algorithm travels with the digest.
+    pub fn with_hash_alg(&self, alg: HashAlg) -> Self {
+        self.clone().rehashed(alg)
+    }
+
+    /// Same cert re-hashed under `scheme`. `MerkleV1` needs a salt the cert
+    /// does not have yet to come from `with_disclosure_salt` or
+    /// `with_random_disclosure_salt`, and fails with `DisclosureError::NoSalt` otherwise.
+    pub fn with_hash_scheme(&self, scheme: CertHashScheme) -> Result<Self, DisclosureError> {
+        if scheme == CertHashScheme::MerkleV1 && self.disclosure_salt.is_none() {
+            return Err(DisclosureError::NoSalt);
         }
+        Ok(Self { hash_scheme: scheme, ..self.clone() }.rehashed(self.kernel_hash.alg()))
     }
 
+    /// Same cert under `CertHashScheme::MerkleV1`, its field leaves salted with `salt`.
+    pub fn with_disclosure_salt(&self, salt: [u8; 32]) -> Self {
+        let cert = Self { hash_scheme: CertHashScheme::MerkleV1, disclosure_salt: Some(salt), ..self.clone() };
+        cert.rehashed(self.kernel_hash.alg())
+    }
+
+    /// `with_disclosure_salt` with a salt from the operating system's RNG.
+    pub fn with_random_disclosure_salt(&self) -> Result<Self, DisclosureError> {
+        let mut salt = [0u8; 32];
+        getrandom::getrandom(&mut salt).map_err(DisclosureError::Rng)?;
+        Ok(self.with_disclosure_salt(salt))
+    }
+
+    /// Same cert declaring `deps` as its upstream certs (re-hashed, which
+    /// changes `kernel_hash` under every scheme but `Legacy`).
+    pub fn with_deps(&self, deps: Vec<CertItem>) -> Self {
+        Self { deps, ..self.clone() }.rehashed(self.kernel_hash.alg())
+    }
+
+    /// Recompute `kernel_hash` from the cert's own fields and compare. That says
+    /// nothing about the deps of a `Legacy` cert; see `unbound_deps`.
+    pub fn verify(&self) -> Result<(), CertLoadError> {
+        let computed = compute_kernel_hash(self, self.kernel_hash.alg())
+            .ok_or_else(|| FromCanonError::new("required by merkle/v1").at("disclosure_salt"))?;
+        if computed != self.kernel_hash {
+            return Err(CertLoadError::HashMismatch {
+                claimed: self.kernel_hash.to_compat_string(),
+                computed: computed.to_compat_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Deps that `kernel_hash` does not authenticate: all of them under `Legacy`.
+    /// Only the kernel knows which payload field names each, so they are bound by
+    /// `from_canon` (through `SchemaRegistry::allow_legacy`) and `KernelRegistry::verify_cert`.
+    pub fn unbound_deps(&self) -> impl Iterator<Item = &CertItem> {
+        let legacy = self.hash_scheme == CertHashScheme::Legacy;
+        self.deps.iter().filter(move |_| legacy)
+    }
+
+    /// Bare hex of `kernel_hash`, without the algorithm prefix.
     pub fn kernel_hash_hex(&self) -> String {
-        sha256_hex(self.kernel_hash)
+        self.kernel_hash.hex()
     }
 
     pub fn to_canon(&self) -> Canon {
@@ -32,37 +189,470 @@ impl KernelCert {
         obj.insert("kernel_name".to_string(), Canon::Str(self.kernel_name.clone()));
         obj.insert("kernel_version".to_string(), Canon::Str(self.kernel_version.clone()));
         obj.insert("payload".to_string(), self.payload.clone());
-        obj.insert("kernel_hash".to_string(), Canon::Str(self.kernel_hash_hex()));
+        obj.insert("canon_mode".to_string(), Canon::Str(self.canon_mode.tag().to_string()));
+        obj.insert("hash_scheme".to_string(), Canon::Str(self.hash_scheme.tag().to_string()));
+        obj.insert("kernel_hash".to_string(), Canon::Str(self.kernel_hash.to_compat_string()));
+        if !self.deps.is_empty() {
+            obj.insert("deps".to_string(), deps_to_canon(&self.deps));
+        }
+        if let Some(salt) = self.disclosure_salt {
+            obj.insert("disclosure_salt".to_string(), Canon::Str(hex::encode(salt)));
+        }
         Canon::Obj(obj)
     }
+
+    /// Loads a cert from its `to_canon` form: checks the payload against
+    /// `schemas` and recomputes `kernel_hash`. A missing `canon_mode` means v1, missing
+    /// `deps` means none, and an untagged `kernel_hash` means SHA-256. `hash_scheme`
+    /// is required, and may be `legacy` only for kernels `schemas` allows it for;
+    /// `merkle/v1` also requires `disclosure_salt`.
+    pub fn from_canon(v: &Canon, schemas: &SchemaRegistry) -> Result<Self, CertLoadError> {
+        let Canon::Obj(obj) = v else {
+            return Err(FromCanonError::expected("object", v).into());
+        };
+        let kernel_name: String = obj_field(obj, "kernel_name")?;
+        let kernel_version: String = obj_field(obj, "kernel_version")?;
+        let payload: Canon = obj_field(obj, "payload")?;
+        let claimed: String = obj_field(obj, "kernel_hash")?;
+        let claimed_digest = TaggedDigest::parse_compat(&claimed)
+            .map_err(|e| FromCanonError::new(e.to_string()).at("kernel_hash"))?;
+        let canon_mode = match obj_field::<Option<String>>(obj, "canon_mode")? {
+            None => CanonMode::V1,
+            Some(t) => CanonMode::from_tag(&t)
+                .ok_or_else(|| FromCanonError::new(format!("unknown canon_mode {t:?}")).at("canon_mode"))?,
+        };
+        let scheme: String = obj_field(obj, "hash_scheme")?;
+        let hash_scheme = CertHashScheme::from_tag(&scheme)
+            .ok_or_else(|| FromCanonError::new(format!("unknown hash_scheme {scheme:?}")).at("hash_scheme"))?;
+        let legacy_deps_of = match hash_scheme {
+            CertHashScheme::Legacy => match schemas.legacy_deps_of(&kernel_name, &kernel_version) {
+                Some(deps_of) => Some(deps_of),
+                None => return Err(CertLoadError::LegacyScheme { kernel_name, kernel_version }),
+            },
+            _ => None,
+        };
+        let deps = obj_field::<Option<Vec<CertItem>>>(obj, "deps")?.unwrap_or_default();
+        let disclosure_salt = match obj_field::<Option<String>>(obj, "disclosure_salt")? {
+            None => None,
+            Some(salt) => Some(
+                hex::decode(&salt)
+                    .ok()
+                    .and_then(|b| <[u8; 32]>::try_from(b).ok())
+                    .ok_or_else(|| FromCanonError::new("not 32 hex-encoded bytes").at("disclosure_salt"))?,
+            ),
+        };
+        schemas.validate(&kernel_name, &kernel_version, &payload).map_err(CertLoadError::Schema)?;
+        if let Some(deps_of) = legacy_deps_of {
+            let named = deps_of(&payload).map_err(|e| FromCanonError::new(e.to_string()).at("payload"))?;
+            if let Some(d) = deps.iter().find(|d| !named.contains(d)) {
+                return Err(CertLoadError::UnboundDep { name: d.name.clone() });
+            }
+        }
+        let kernel_hash = claimed_digest;
+        let cert =
+            Self { kernel_name, kernel_version, payload, canon_mode, hash_scheme, kernel_hash, deps, disclosure_salt };
+        cert.verify()?;
+        Ok(cert)
+    }
+
+    /// What changed from `self` to `other`, over the full cert form
+    /// (name, version, mode, hash and every payload path).
+    pub fn diff(&self, other: &KernelCert) -> CanonDiff {
+        canon_diff(&self.to_canon(), &other.to_canon())
+    }
 }
 
+/// Why `KernelCert::from_canon` or `KernelCert::verify` rejected a cert.
 #[derive(Clone, Debug)]
+pub enum CertLoadError {
+    Malformed(FromCanonError),
+    Schema(Vec<SchemaError>),
+    HashMismatch { claimed: String, computed: String },
+    /// A `Legacy` cert declares a dep its kernel's `deps_of` does not read from the payload.
+    UnboundDep { name: String },
+    /// A `Legacy` cert of a kernel not allowed it (`SchemaRegistry::allow_legacy`).
+    LegacyScheme { kernel_name: String, kernel_version: String },
+}
+
+impl From<FromCanonError> for CertLoadError {
+    fn from(e: FromCanonError) -> Self {
+        CertLoadError::Malformed(e)
+    }
+}
+
+impl fmt::Display for CertLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CertLoadError::Malformed(e) => write!(f, "malformed cert: {e}"),
+            CertLoadError::Schema(errs) => {
+                write!(f, "payload violates schema:")?;
+                for e in errs {
+                    write!(f, " {e};")?;
+                }
+                Ok(())
+            }
+            CertLoadError::HashMismatch { claimed, computed } => {
+                write!(f, "kernel_hash mismatch: cert claims {claimed}, payload hashes to {computed}")
+            }
+            CertLoadError::UnboundDep { name } => write!(f, "dep {name} is not committed to by the legacy payload"),
+            CertLoadError::LegacyScheme { kernel_name, kernel_version } => {
+                write!(f, "legacy hash scheme is not allowed for {kernel_name}@{kernel_version}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CertLoadError {}
+
+/// `cert.kernel_hash` is ignored; everything else is read. `None` for a
+/// `MerkleV1` cert without a `disclosure_salt`.
+fn compute_kernel_hash(cert: &KernelCert, alg: HashAlg) -> Option<TaggedDigest> {
+    let (kernel_name, kernel_version, payload) = (&cert.kernel_name, &cert.kernel_version, &cert.payload);
+    let (canon_mode, deps) = (cert.canon_mode, &cert.deps);
+    let mut h = alg.hasher();
+    match cert.hash_scheme {
+        CertHashScheme::Legacy => write_canon_with(&mut h, payload, canon_mode),
+        CertHashScheme::EnvelopeV1 => {
+            h.write_bytes(CERT_HASH_DOMAIN_V1.as_bytes());
+            h.write_byte(0);
+            let mut env = BTreeMap::new();
+            env.insert("canon_mode".to_string(), Canon::Str(canon_mode.tag().to_string()));
+            env.insert("kernel_name".to_string(), Canon::Str(kernel_name.clone()));
+            env.insert("kernel_version".to_string(), Canon::Str(kernel_version.clone()));
+            env.insert("payload".to_string(), payload.clone());
+            // Absent rather than empty, so certs without deps keep their hashes.
+            if !deps.is_empty() {
+                env.insert("deps".to_string(), deps_to_canon(deps));
+            }
+            write_canon_with(&mut h, &Canon::Obj(env), canon_mode);
+        }
+        CertHashScheme::MerkleV1 => {
+            let root = payload_root(alg, cert.disclosure_salt.as_ref()?, payload, canon_mode);
+            return Some(merkle_envelope_hash(kernel_name, kernel_version, canon_mode, deps, &root));
+        }
+    }
+    Some(h.finish())
+}
+
+pub(crate) fn deps_to_canon(deps: &[CertItem]) -> Canon {
+    Canon::Arr(deps.iter().map(CertItem::to_canon).collect())
+}
+
+/// `KernelCert::to_v2` found no numeric major version to bump.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionBumpError {
+    pub version: String,
+}
+
+impl fmt::Display for VersionBumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "kernel_version {:?} has no numeric major", self.version)
+    }
+}
+
+impl std::error::Error for VersionBumpError {}
+
+fn bump_major(version: &str) -> Result<String, VersionBumpError> {
+    let major = version.split('.').next().unwrap_or(version);
+    match major.parse::<u64>() {
+        Ok(m) => Ok(format!("{}.0.0", m + 1)),
+        Err(_) => Err(VersionBumpError { version: version.to_string() }),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CertItem {
     pub name: String,
-    pub hash_hex: String,
+    pub hash: TaggedDigest,
+}
+
+impl CertItem {
+    pub fn new(name: impl Into<String>, hash: impl Into<TaggedDigest>) -> Self {
+        Self { name: name.into(), hash: hash.into() }
+    }
+
+    pub fn from_cert(cert: &KernelCert) -> Self {
+        Self::new(cert.kernel_name.clone(), cert.kernel_hash.clone())
+    }
+
+    /// `{"hash": <compat string>, "name": ..}`, the element form every chain mode hashes.
+    pub fn to_canon(&self) -> Canon {
+        let mut o = BTreeMap::new();
+        o.insert("hash".to_string(), Canon::Str(self.hash.to_compat_string()));
+        o.insert("name".to_string(), Canon::Str(self.name.clone()));
+        Canon::Obj(o)
+    }
+
+    pub fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        let Canon::Obj(obj) = v else {
+            return Err(FromCanonError::expected("object", v));
+        };
+        let name: String = obj_field(obj, "name")?;
+        let hash: TaggedDigest = obj_field(obj, "hash")?;
+        Ok(Self { name, hash })
+    }
+
+    /// `H(0x00 || canon(item))`.
+    pub fn merkle_leaf_hash(&self, alg: HashAlg) -> TaggedDigest {
+        merkle_leaf_hash(alg, &canon_bytes(&self.to_canon()))
+    }
+}
+
+impl ToCanon for CertItem {
+    fn to_canon(&self) -> Canon {
+        CertItem::to_canon(self)
+    }
+}
+
+impl FromCanon for CertItem {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        CertItem::from_canon(v)
+    }
+}
+
+/// How `CertChain::chain_hash` is derived from the items.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum ChainMode {
+    /// Hash of the whole `[{hash,name}, ...]` array. The pinned `chain_hash` uses this.
+    #[default]
+    Flat,
+    /// RFC 6962 Merkle tree hash over the items, so single items can be proven
+    /// with `CertChain::inclusion_proof` without revealing the rest.
+    Merkle,
+    /// Each link commits to the previous link, so order is part of the hash and
+    /// a break can be located. `chain_hash` is the last link.
+    Linked,
+}
+
+impl ChainMode {
+    pub fn tag(&self) -> &'static str {
+        match self {
+            ChainMode::Flat => "flat",
+            ChainMode::Merkle => "merkle",
+            ChainMode::Linked => "linked",
+        }
+    }
+
+    pub fn from_tag(s: &str) -> Option<Self> {
+        match s {
+            "flat" => Some(ChainMode::Flat),
+            "merkle" => Some(ChainMode::Merkle),
+            "linked" => Some(ChainMode::Linked),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct CertChain {
     pub items: Vec<CertItem>,
-    pub chain_hash_hex: String,
+    pub mode: ChainMode,
+    pub chain_hash: TaggedDigest,
+    /// Per-item link hashes in `ChainMode::Linked`; empty in other modes.
+    pub links: Vec<TaggedDigest>,
 }
 
-pub fn cert_chain_hash(items: &[CertItem]) -> String {
-    let mut arr = Vec::new();
+/// Why `CertChain::verify` rejected a chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChainVerifyError {
+    /// The first link whose recorded hash does not match its item and predecessor.
+    BrokenLink { index: usize, name: String, recorded: TaggedDigest, computed: TaggedDigest },
+    LinkCount { items: usize, links: usize },
+    HashMismatch { claimed: TaggedDigest, computed: TaggedDigest },
+    /// An item whose hash is on the revocation list, under `RevocationPolicy::Deny`.
+    Revoked(Box<RevokedItem>),
+}
+
+impl fmt::Display for ChainVerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainVerifyError::BrokenLink { index, name, recorded, computed } => {
+                write!(f, "chain breaks at link {index} ({name}): recorded {recorded}, computed {computed}")
+            }
+            ChainVerifyError::LinkCount { items, links } => write!(f, "chain has {items} items but {links} links"),
+            ChainVerifyError::HashMismatch { claimed, computed } => {
+                write!(f, "chain_hash mismatch: chain claims {claimed}, items hash to {computed}")
+            }
+            ChainVerifyError::Revoked(r) => {
+                write!(f, "chain item {} ({}) is revoked: {}", r.index, r.name, r.revocation.reason)?;
+                match &r.revocation.superseded_by {
+                    Some(s) => write!(f, ", superseded by {s}"),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChainVerifyError {}
+
+/// `alg(CHAIN_LINK_DOMAIN_V1 || 0x00 || canon({hash, name, prev}))`; `prev` is
+/// null for the genesis link.
+pub fn cert_link_hash(prev: Option<&TaggedDigest>, item: &CertItem, alg: HashAlg) -> TaggedDigest {
+    let mut h = alg.hasher();
+    h.write_bytes(CHAIN_LINK_DOMAIN_V1.as_bytes());
+    h.write_bytes(&[0]);
+    write_object(&mut h, |obj| {
+        obj.field("hash", &Canon::Str(item.hash.to_compat_string()));
+        obj.field("name", &Canon::Str(item.name.clone()));
+        obj.field("prev", &prev.map_or(Canon::Null, |p| Canon::Str(p.to_compat_string())));
+    });
+    h.finish()
+}
+
+fn linked_hashes(items: &[CertItem], alg: HashAlg) -> Vec<TaggedDigest> {
+    let mut links: Vec<TaggedDigest> = Vec::with_capacity(items.len());
     for it in items {
-        let mut obj = BTreeMap::new();
-        obj.insert("name".to_string(), Canon::Str(it.name.clone()));
-        obj.insert("hash".to_string(), Canon::Str(it.hash_hex.clone()));
-        arr.push(Canon::Obj(obj));
+        let link = cert_link_hash(links.last(), it, alg);
+        links.push(link);
     }
-    sha256_hex(sha256_bytes(&canon_bytes(&Canon::Arr(arr))))
+    links
+}
+
+pub fn cert_chain_hash(items: &[CertItem]) -> String {
+    cert_chain_digest(items, HashAlg::Sha256).hex()
+}
+
+/// `alg(canon([{hash,name}, ...]))`, streamed. Item hashes enter in compat form
+/// (bare hex for SHA-256), so a chain may mix algorithms.
+pub fn cert_chain_digest(items: &[CertItem], alg: HashAlg) -> TaggedDigest {
+    let mut h = alg.hasher();
+    write_array(&mut h, |arr| {
+        for it in items {
+            arr.item_object(|obj| {
+                obj.field("hash", &Canon::Str(it.hash.to_compat_string()));
+                obj.field("name", &Canon::Str(it.name.clone()));
+            });
+        }
+    });
+    h.finish()
 }
 
 impl CertChain {
     pub fn build(items: Vec<CertItem>) -> Self {
-        let chain_hash_hex = cert_chain_hash(&items);
-        Self { items, chain_hash_hex }
+        Self::build_with_alg(items, HashAlg::Sha256)
     }
+
+    pub fn build_with_alg(items: Vec<CertItem>, alg: HashAlg) -> Self {
+        Self::build_with(items, ChainMode::Flat, alg)
+    }
+
+    pub fn build_merkle(items: Vec<CertItem>) -> Self {
+        Self::build_with(items, ChainMode::Merkle, HashAlg::Sha256)
+    }
+
+    pub fn build_linked(items: Vec<CertItem>) -> Self {
+        Self::build_with(items, ChainMode::Linked, HashAlg::Sha256)
+    }
+
+    /// An empty chain hashes to `alg("")` in the Merkle and Linked modes.
+    pub fn build_with(items: Vec<CertItem>, mode: ChainMode, alg: HashAlg) -> Self {
+        let (chain_hash, links) = compute_chain(&items, mode, alg);
+        Self { items, mode, chain_hash, links }
+    }
+
+    /// Append one item. A linked chain only hashes the new link; other modes rehash.
+    pub fn push(&mut self, item: CertItem) {
+        let alg = self.chain_hash.alg();
+        if self.mode == ChainMode::Linked {
+            let link = cert_link_hash(self.links.last(), &item, alg);
+            self.chain_hash = link.clone();
+            self.links.push(link);
+            self.items.push(item);
+        } else {
+            self.items.push(item);
+            (self.chain_hash, self.links) = compute_chain(&self.items, self.mode, alg);
+        }
+    }
+
+    /// Recompute from the items and compare. Linked chains are walked from
+    /// genesis and report the first link that does not match.
+    pub fn verify(&self) -> Result<(), ChainVerifyError> {
+        let alg = self.chain_hash.alg();
+        let (computed, links) = compute_chain(&self.items, self.mode, alg);
+        if links.len() != self.links.len() {
+            return Err(ChainVerifyError::LinkCount { items: self.items.len(), links: self.links.len() });
+        }
+        for (index, (recorded, computed)) in self.links.iter().zip(links).enumerate() {
+            if *recorded != computed {
+                let name = self.items[index].name.clone();
+                return Err(ChainVerifyError::BrokenLink { index, name, recorded: recorded.clone(), computed });
+            }
+        }
+        if computed != self.chain_hash {
+            return Err(ChainVerifyError::HashMismatch { claimed: self.chain_hash.clone(), computed });
+        }
+        Ok(())
+    }
+
+    /// `verify`, then look every item's hash up in `revocations`. Under `Warn`
+    /// the revoked items are returned instead of failing.
+    pub fn verify_with_revocations(
+        &self,
+        revocations: &RevocationList,
+        policy: RevocationPolicy,
+    ) -> Result<Vec<RevokedItem>, ChainVerifyError> {
+        self.verify()?;
+        let revoked = revocations.revoked_items(self);
+        match (policy, revoked.first()) {
+            (RevocationPolicy::Deny, Some(r)) => Err(ChainVerifyError::Revoked(Box::new(r.clone()))),
+            _ => Ok(revoked),
+        }
+    }
+
+    /// `{chain_hash, items, mode}`, plus `links` for linked chains.
+    pub fn to_canon(&self) -> Canon {
+        let mut obj = BTreeMap::new();
+        obj.insert("chain_hash".to_string(), self.chain_hash.to_canon());
+        obj.insert("items".to_string(), self.items.to_canon());
+        obj.insert("mode".to_string(), Canon::Str(self.mode.tag().to_string()));
+        if self.mode == ChainMode::Linked {
+            obj.insert("links".to_string(), self.links.to_canon());
+        }
+        Canon::Obj(obj)
+    }
+
+    /// Inverse of `to_canon`. Does not verify; call `verify` on the result.
+    pub fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        let Canon::Obj(obj) = v else {
+            return Err(FromCanonError::expected("object", v));
+        };
+        let tag: String = obj_field(obj, "mode")?;
+        let mode = ChainMode::from_tag(&tag)
+            .ok_or_else(|| FromCanonError::new(format!("unknown chain mode {tag:?}")).at("mode"))?;
+        let items: Vec<CertItem> = obj_field(obj, "items")?;
+        let chain_hash: TaggedDigest = obj_field(obj, "chain_hash")?;
+        let links = obj_field::<Option<Vec<TaggedDigest>>>(obj, "links")?.unwrap_or_default();
+        Ok(Self { items, mode, chain_hash, links })
+    }
+
+    /// Proof that `items[index]` is under `chain_hash`. `None` unless the chain
+    /// is in `ChainMode::Merkle` and `index` is in range.
+    pub fn inclusion_proof(&self, index: usize) -> Option<InclusionProof> {
+        if self.mode != ChainMode::Merkle {
+            return None;
+        }
+        let alg = self.chain_hash.alg();
+        inclusion_proof(alg, &merkle_leaves(&self.items, alg), index)
+    }
+}
+
+fn compute_chain(items: &[CertItem], mode: ChainMode, alg: HashAlg) -> (TaggedDigest, Vec<TaggedDigest>) {
+    match mode {
+        ChainMode::Flat => (cert_chain_digest(items, alg), Vec::new()),
+        ChainMode::Merkle => (merkle_root(alg, &merkle_leaves(items, alg)), Vec::new()),
+        ChainMode::Linked => {
+            let links = linked_hashes(items, alg);
+            (links.last().cloned().unwrap_or_else(|| alg.hash(b"")), links)
+        }
+    }
+}
+
+fn merkle_leaves(items: &[CertItem], alg: HashAlg) -> Vec<TaggedDigest> {
+    items.iter().map(|it| it.merkle_leaf_hash(alg)).collect()
+}
+
+/// Checks `item` against a Merkle-mode `chain_hash` using only the proof.
+pub fn verify_cert_inclusion(item: &CertItem, proof: &InclusionProof, chain_hash: &TaggedDigest) -> bool {
+    proof.verify(&item.merkle_leaf_hash(chain_hash.alg()), chain_hash)
 }
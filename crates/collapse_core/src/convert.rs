@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::canon::Canon;
+use crate::exact::{Decimal, Rational};
+
+/// Conversion into the canonical payload form. Derive with `#[derive(ToCanon)]`.
+pub trait ToCanon {
+    fn to_canon(&self) -> Canon;
+}
+
+/// Conversion back from the canonical payload form. Derive with `#[derive(FromCanon)]`.
+pub trait FromCanon: Sized {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError>;
+}
+
+/// Decode failure; `path` locates the offending value (e.g. `predicates[2].name`).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FromCanonError {
+    pub path: String,
+    pub msg: String,
+}
+
+impl FromCanonError {
+    pub fn new(msg: impl Into<String>) -> Self {
+        Self { path: String::new(), msg: msg.into() }
+    }
+
+    pub fn expected(what: &str, got: &Canon) -> Self {
+        Self::new(format!("expected {what}, got {}", canon_kind(got)))
+    }
+
+    /// Prefix the path with an object key or array index segment.
+    pub fn at(mut self, segment: &str) -> Self {
+        self.path = if self.path.is_empty() || self.path.starts_with('[') {
+            format!("{segment}{}", self.path)
+        } else {
+            format!("{segment}.{}", self.path)
+        };
+        self
+    }
+}
+
+impl fmt::Display for FromCanonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.msg)
+        } else {
+            write!(f, "{}: {}", self.path, self.msg)
+        }
+    }
+}
+
+impl std::error::Error for FromCanonError {}
+
+pub fn canon_kind(v: &Canon) -> &'static str {
+    match v {
+        Canon::Null => "null",
+        Canon::Bool(_) => "bool",
+        Canon::I64(_) => "i64",
+        Canon::U64(_) => "u64",
+        Canon::Rat(_) => "rational",
+        Canon::Dec(_) => "decimal",
+        Canon::Str(_) => "string",
+        Canon::Arr(_) => "array",
+        Canon::Obj(_) => "object",
+    }
+}
+
+/// Field lookup used by derived `FromCanon` impls. A missing key reads as `Null`,
+/// so `Option` fields accept either an explicit `null` or an absent key.
+pub fn obj_field<T: FromCanon>(obj: &BTreeMap<String, Canon>, key: &str) -> Result<T, FromCanonError> {
+    T::from_canon(obj.get(key).unwrap_or(&Canon::Null)).map_err(|e| e.at(key))
+}
+
+/// Fixed-point encoding for `#[canon(scale = N)]` fields: `round(x * N)` as `I64`.
+/// Panics, like `Decimal::from_f64`, if `x` is not finite or the result does not fit.
+pub fn scaled_to_canon(x: f64, scale: i64) -> Canon {
+    let n = (x * scale as f64).round();
+    let fits = n.is_finite() && n >= i64::MIN as f64 && n < i64::MAX as f64;
+    assert!(fits, "scaled_to_canon: {x} * {scale} is not a finite i64");
+    Canon::I64(n as i64)
+}
+
+pub fn scaled_from_canon(v: &Canon, scale: i64) -> Result<f64, FromCanonError> {
+    i64::from_canon(v).map(|n| n as f64 / scale as f64)
+}
+
+impl ToCanon for Canon {
+    fn to_canon(&self) -> Canon {
+        self.clone()
+    }
+}
+
+impl FromCanon for Canon {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        Ok(v.clone())
+    }
+}
+
+impl ToCanon for bool {
+    fn to_canon(&self) -> Canon {
+        Canon::Bool(*self)
+    }
+}
+
+impl FromCanon for bool {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        match v {
+            Canon::Bool(b) => Ok(*b),
+            _ => Err(FromCanonError::expected("bool", v)),
+        }
+    }
+}
+
+macro_rules! canon_int {
+    ($variant:ident, $wide:ty, $($t:ty),*) => {$(
+        impl ToCanon for $t {
+            fn to_canon(&self) -> Canon {
+                Canon::$variant(*self as $wide)
+            }
+        }
+
+        impl FromCanon for $t {
+            fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+                let out_of_range = || FromCanonError::new(format!("{v:?} out of range for {}", stringify!($t)));
+                match v {
+                    Canon::I64(n) => <$t>::try_from(*n).map_err(|_| out_of_range()),
+                    Canon::U64(n) => <$t>::try_from(*n).map_err(|_| out_of_range()),
+                    _ => Err(FromCanonError::expected("integer", v)),
+                }
+            }
+        }
+    )*};
+}
+
+canon_int!(U64, u64, u8, u16, u32, u64, usize);
+canon_int!(I64, i64, i8, i16, i32, i64, isize);
+
+impl ToCanon for Rational {
+    fn to_canon(&self) -> Canon {
+        Canon::Rat(*self)
+    }
+}
+
+/// Accepts `Canon::Rat` or its object form, as returned by `parse_canon`.
+impl FromCanon for Rational {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        match v {
+            Canon::Rat(r) => Ok(*r),
+            Canon::Obj(_) => Rational::from_obj(v).ok_or_else(|| FromCanonError::new("expected reduced {den,num} with den > 0")),
+            _ => Err(FromCanonError::expected("rational", v)),
+        }
+    }
+}
+
+impl ToCanon for Decimal {
+    fn to_canon(&self) -> Canon {
+        Canon::Dec(*self)
+    }
+}
+
+/// Accepts `Canon::Dec` or its object form, as returned by `parse_canon`.
+impl FromCanon for Decimal {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        match v {
+            Canon::Dec(d) => Ok(*d),
+            Canon::Obj(_) => Decimal::from_obj(v).ok_or_else(|| FromCanonError::new("expected {mantissa,scale} decimal")),
+            _ => Err(FromCanonError::expected("decimal", v)),
+        }
+    }
+}
+
+impl ToCanon for String {
+    fn to_canon(&self) -> Canon {
+        Canon::Str(self.clone())
+    }
+}
+
+impl FromCanon for String {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        match v {
+            Canon::Str(s) => Ok(s.clone()),
+            _ => Err(FromCanonError::expected("string", v)),
+        }
+    }
+}
+
+impl ToCanon for str {
+    fn to_canon(&self) -> Canon {
+        Canon::Str(self.to_string())
+    }
+}
+
+impl ToCanon for char {
+    fn to_canon(&self) -> Canon {
+        Canon::Str(self.to_string())
+    }
+}
+
+impl FromCanon for char {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        let s = String::from_canon(v)?;
+        let mut it = s.chars();
+        match (it.next(), it.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(FromCanonError::new(format!("expected single char, got {s:?}"))),
+        }
+    }
+}
+
+impl<T: ToCanon> ToCanon for Option<T> {
+    fn to_canon(&self) -> Canon {
+        match self {
+            Some(v) => v.to_canon(),
+            None => Canon::Null,
+        }
+    }
+}
+
+impl<T: FromCanon> FromCanon for Option<T> {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        match v {
+            Canon::Null => Ok(None),
+            _ => T::from_canon(v).map(Some),
+        }
+    }
+}
+
+impl<T: ToCanon> ToCanon for Vec<T> {
+    fn to_canon(&self) -> Canon {
+        Canon::Arr(self.iter().map(ToCanon::to_canon).collect())
+    }
+}
+
+impl<T: FromCanon> FromCanon for Vec<T> {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        match v {
+            Canon::Arr(xs) => xs
+                .iter()
+                .enumerate()
+                .map(|(i, x)| T::from_canon(x).map_err(|e| e.at(&format!("[{i}]"))))
+                .collect(),
+            _ => Err(FromCanonError::expected("array", v)),
+        }
+    }
+}
+
+impl<T: ToCanon> ToCanon for BTreeMap<String, T> {
+    fn to_canon(&self) -> Canon {
+        Canon::Obj(self.iter().map(|(k, v)| (k.clone(), v.to_canon())).collect())
+    }
+}
+
+impl<T: FromCanon> FromCanon for BTreeMap<String, T> {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        match v {
+            Canon::Obj(map) => map.iter().map(|(k, x)| Ok((k.clone(), T::from_canon(x).map_err(|e| e.at(k))?))).collect(),
+            _ => Err(FromCanonError::expected("object", v)),
+        }
+    }
+}
+
+impl<A: ToCanon, B: ToCanon> ToCanon for (A, B) {
+    fn to_canon(&self) -> Canon {
+        Canon::Arr(vec![self.0.to_canon(), self.1.to_canon()])
+    }
+}
+
+impl<A: FromCanon, B: FromCanon> FromCanon for (A, B) {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        match v {
+            Canon::Arr(xs) if xs.len() == 2 => Ok((
+                A::from_canon(&xs[0]).map_err(|e| e.at("[0]"))?,
+                B::from_canon(&xs[1]).map_err(|e| e.at("[1]"))?,
+            )),
+            _ => Err(FromCanonError::expected("2-element array", v)),
+        }
+    }
+}
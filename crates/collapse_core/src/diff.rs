@@ -0,0 +1,139 @@
+use std::fmt;
+
+use crate::canon::{Canon, canon_bytes};
+use crate::convert::canon_kind;
+
+/// One difference between two `Canon` trees, located by a path such as
+/// `payload.graph_hash_hex` or `pairs[3].dst`. The root path is empty.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CanonChange {
+    Added { path: String, value: Canon },
+    Removed { path: String, value: Canon },
+    Changed { path: String, old: Canon, new: Canon },
+}
+
+impl CanonChange {
+    pub fn path(&self) -> &str {
+        match self {
+            CanonChange::Added { path, .. } | CanonChange::Removed { path, .. } | CanonChange::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// Ordered list of changes: object keys in `BTreeMap` order, array indices ascending.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CanonDiff {
+    pub changes: Vec<CanonChange>,
+}
+
+impl CanonDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Human-readable rendering, one change per line:
+    /// `+ path: value`, `- path: value`, `~ path: old -> new`. When `old` and `new`
+    /// print alike, each is followed by its kind: `~ n: 5 (i64) -> 5 (u64)`.
+    pub fn render(&self) -> String {
+        self.to_string()
+    }
+}
+
+fn show(v: &Canon) -> String {
+    String::from_utf8_lossy(&canon_bytes(v)).into_owned()
+}
+
+fn show_path(p: &str) -> &str {
+    if p.is_empty() { "$" } else { p }
+}
+
+impl fmt::Display for CanonDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in &self.changes {
+            match c {
+                CanonChange::Added { path, value } => writeln!(f, "+ {}: {}", show_path(path), show(value))?,
+                CanonChange::Removed { path, value } => writeln!(f, "- {}: {}", show_path(path), show(value))?,
+                CanonChange::Changed { path, old, new } => {
+                    let (o, n) = (show(old), show(new));
+                    if o == n {
+                        // Same v1 text, e.g. `I64(5)` and `U64(5)`: name the kinds.
+                        writeln!(f, "~ {}: {o} ({}) -> {n} ({})", show_path(path), canon_kind(old), canon_kind(new))?
+                    } else {
+                        writeln!(f, "~ {}: {o} -> {n}", show_path(path))?
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Structural diff of `a` (old) against `b` (new). Values of different kinds at
+/// the same path are reported as a single `Changed`, not walked further.
+pub fn canon_diff(a: &Canon, b: &Canon) -> CanonDiff {
+    let mut changes = Vec::new();
+    walk(&mut changes, String::new(), a, b);
+    CanonDiff { changes }
+}
+
+fn key_path(base: &str, key: &str) -> String {
+    let plain = !key.is_empty() && key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_');
+    match (plain, base.is_empty()) {
+        (true, true) => key.to_string(),
+        (true, false) => format!("{base}.{key}"),
+        (false, _) => format!("{base}[{key:?}]"),
+    }
+}
+
+fn walk(out: &mut Vec<CanonChange>, path: String, a: &Canon, b: &Canon) {
+    match (a, b) {
+        _ if a == b => {}
+        // v1 bytes cannot tell a `Rat`/`Dec` from its object form, so that is no change.
+        (Canon::Rat(_) | Canon::Dec(_), Canon::Obj(_)) | (Canon::Obj(_), Canon::Rat(_) | Canon::Dec(_))
+            if a.exact_from_v1_compat() == b.exact_from_v1_compat() => {}
+        (Canon::Obj(ma), Canon::Obj(mb)) => {
+            let mut ia = ma.iter().peekable();
+            let mut ib = mb.iter().peekable();
+            loop {
+                match (ia.peek(), ib.peek()) {
+                    (Some((ka, va)), Some((kb, vb))) => match ka.cmp(kb) {
+                        std::cmp::Ordering::Equal => {
+                            walk(out, key_path(&path, ka), va, vb);
+                            ia.next();
+                            ib.next();
+                        }
+                        std::cmp::Ordering::Less => {
+                            out.push(CanonChange::Removed { path: key_path(&path, ka), value: (*va).clone() });
+                            ia.next();
+                        }
+                        std::cmp::Ordering::Greater => {
+                            out.push(CanonChange::Added { path: key_path(&path, kb), value: (*vb).clone() });
+                            ib.next();
+                        }
+                    },
+                    (Some((ka, va)), None) => {
+                        out.push(CanonChange::Removed { path: key_path(&path, ka), value: (*va).clone() });
+                        ia.next();
+                    }
+                    (None, Some((kb, vb))) => {
+                        out.push(CanonChange::Added { path: key_path(&path, kb), value: (*vb).clone() });
+                        ib.next();
+                    }
+                    (None, None) => break,
+                }
+            }
+        }
+        (Canon::Arr(xa), Canon::Arr(xb)) => {
+            for i in 0..xa.len().max(xb.len()) {
+                let p = format!("{path}[{i}]");
+                match (xa.get(i), xb.get(i)) {
+                    (Some(va), Some(vb)) => walk(out, p, va, vb),
+                    (Some(va), None) => out.push(CanonChange::Removed { path: p, value: va.clone() }),
+                    (None, Some(vb)) => out.push(CanonChange::Added { path: p, value: vb.clone() }),
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+        _ => out.push(CanonChange::Changed { path, old: a.clone(), new: b.clone() }),
+    }
+}
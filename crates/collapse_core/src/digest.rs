@@ -1,8 +1,55 @@
-use sha2::{Digest, Sha256};
+use std::fmt;
+use std::hash::Hash;
+use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256, Sha512};
+use sha3::Sha3_256;
+use subtle::ConstantTimeEq;
+
+use crate::canon::Canon;
+use crate::convert::{FromCanon, FromCanonError, ToCanon};
+use crate::stream::CanonSink;
+
+/// Raw SHA-256 output. Displays, parses, serializes and canonicalizes as bare
+/// lowercase hex; `==` is constant-time.
+#[derive(Clone, Copy, Debug, Eq)]
 pub struct Sha256Digest(pub [u8; 32]);
 
+impl Sha256Digest {
+    pub fn hex(&self) -> String {
+        hex::encode(self.0)
+    }
+}
+
+impl PartialEq for Sha256Digest {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.ct_eq(&other.0).into()
+    }
+}
+
+impl Hash for Sha256Digest {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl fmt::Display for Sha256Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.hex())
+    }
+}
+
+impl FromStr for Sha256Digest {
+    type Err = DigestParseError;
+
+    /// Bare hex, or `sha256:`-prefixed hex.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let d = TaggedDigest::parse_compat(s)?;
+        d.to_sha256().ok_or(DigestParseError::WrongAlg { want: HashAlg::Sha256, got: d.alg() })
+    }
+}
+
 pub fn sha256_bytes(bytes: &[u8]) -> Sha256Digest {
     let mut hasher = Sha256::new();
     hasher.update(bytes);
@@ -12,3 +59,290 @@ pub fn sha256_bytes(bytes: &[u8]) -> Sha256Digest {
 pub fn sha256_hex(d: Sha256Digest) -> String {
     hex::encode(d.0)
 }
+
+/// Hash algorithms a `TaggedDigest` can name. SHA-256 is the default and the
+/// algorithm every pinned digest and untagged hex string uses.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum HashAlg {
+    #[default]
+    Sha256,
+    Sha512,
+    Sha3_256,
+    Blake3,
+}
+
+impl HashAlg {
+    pub const ALL: [HashAlg; 4] = [HashAlg::Sha256, HashAlg::Sha512, HashAlg::Sha3_256, HashAlg::Blake3];
+
+    /// Prefix used in `alg:hex` strings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlg::Sha256 => "sha256",
+            HashAlg::Sha512 => "sha512",
+            HashAlg::Sha3_256 => "sha3-256",
+            HashAlg::Blake3 => "blake3",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        HashAlg::ALL.into_iter().find(|a| a.name() == s)
+    }
+
+    pub fn output_len(&self) -> usize {
+        match self {
+            HashAlg::Sha512 => 64,
+            HashAlg::Sha256 | HashAlg::Sha3_256 | HashAlg::Blake3 => 32,
+        }
+    }
+
+    pub fn hasher(&self) -> Hasher {
+        match self {
+            HashAlg::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlg::Sha512 => Hasher::Sha512(Sha512::new()),
+            HashAlg::Sha3_256 => Hasher::Sha3_256(Sha3_256::new()),
+            HashAlg::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    pub fn hash(&self, bytes: &[u8]) -> TaggedDigest {
+        let mut h = self.hasher();
+        h.write_bytes(bytes);
+        h.finish()
+    }
+}
+
+impl fmt::Display for HashAlg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Streaming hasher for any `HashAlg`; also a `CanonSink`.
+#[derive(Clone)]
+pub enum Hasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Sha3_256(Sha3_256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    pub fn alg(&self) -> HashAlg {
+        match self {
+            Hasher::Sha256(_) => HashAlg::Sha256,
+            Hasher::Sha512(_) => HashAlg::Sha512,
+            Hasher::Sha3_256(_) => HashAlg::Sha3_256,
+            Hasher::Blake3(_) => HashAlg::Blake3,
+        }
+    }
+
+    pub fn finish(self) -> TaggedDigest {
+        let alg = self.alg();
+        let bytes = match self {
+            Hasher::Sha256(h) => h.finalize().to_vec(),
+            Hasher::Sha512(h) => h.finalize().to_vec(),
+            Hasher::Sha3_256(h) => h.finalize().to_vec(),
+            Hasher::Blake3(h) => h.finalize().as_bytes().to_vec(),
+        };
+        TaggedDigest { alg, bytes }
+    }
+}
+
+impl CanonSink for Hasher {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Sha256(h) => h.update(bytes),
+            Hasher::Sha512(h) => h.update(bytes),
+            Hasher::Sha3_256(h) => h.update(bytes),
+            Hasher::Blake3(h) => {
+                h.update(bytes);
+            }
+        }
+    }
+}
+
+/// A digest that knows its algorithm. Displays and parses as `alg:hex`
+/// (e.g. `sha256:ab12..`).
+///
+/// Strings written before algorithms were tagged are bare SHA-256 hex;
+/// `parse_compat` / `to_compat_string` read and write that form, so existing
+/// certs and pinned values keep their exact bytes. `==` is constant-time.
+#[derive(Clone, Debug, Eq, PartialOrd, Ord)]
+pub struct TaggedDigest {
+    alg: HashAlg,
+    bytes: Vec<u8>,
+}
+
+impl TaggedDigest {
+    /// Panics if `bytes` is not `alg.output_len()` long.
+    pub fn new(alg: HashAlg, bytes: Vec<u8>) -> Self {
+        assert_eq!(bytes.len(), alg.output_len(), "TaggedDigest::new: wrong length for {alg}");
+        Self { alg, bytes }
+    }
+
+    pub fn alg(&self) -> HashAlg {
+        self.alg
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Bare hex, without the algorithm prefix.
+    pub fn hex(&self) -> String {
+        hex::encode(&self.bytes)
+    }
+
+    pub fn to_sha256(&self) -> Option<Sha256Digest> {
+        match self.alg {
+            HashAlg::Sha256 => Some(Sha256Digest(self.bytes.as_slice().try_into().unwrap())),
+            _ => None,
+        }
+    }
+
+    /// Bare hex for SHA-256, `alg:hex` otherwise.
+    pub fn to_compat_string(&self) -> String {
+        match self.alg {
+            HashAlg::Sha256 => self.hex(),
+            _ => self.to_string(),
+        }
+    }
+
+    /// Accepts `alg:hex`, or bare hex which is read as SHA-256.
+    pub fn parse_compat(s: &str) -> Result<Self, DigestParseError> {
+        if s.contains(':') {
+            s.parse()
+        } else {
+            Self::from_hex(HashAlg::Sha256, s)
+        }
+    }
+
+    /// Lowercase hex only, so each digest has one text form.
+    pub fn from_hex(alg: HashAlg, hex_str: &str) -> Result<Self, DigestParseError> {
+        if hex_str.bytes().any(|b| b.is_ascii_uppercase()) {
+            return Err(DigestParseError::BadHex(hex_str.to_string()));
+        }
+        let bytes = hex::decode(hex_str).map_err(|_| DigestParseError::BadHex(hex_str.to_string()))?;
+        if bytes.len() != alg.output_len() {
+            return Err(DigestParseError::BadLength { alg, got: bytes.len() });
+        }
+        Ok(Self { alg, bytes })
+    }
+}
+
+impl PartialEq for TaggedDigest {
+    fn eq(&self, other: &Self) -> bool {
+        self.alg == other.alg && bool::from(self.bytes.ct_eq(&other.bytes))
+    }
+}
+
+impl Hash for TaggedDigest {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        Hash::hash(&self.alg, state);
+        state.write(&self.bytes);
+    }
+}
+
+impl fmt::Display for TaggedDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.alg, self.hex())
+    }
+}
+
+impl FromStr for TaggedDigest {
+    type Err = DigestParseError;
+
+    /// Strict `alg:hex`; see `parse_compat` for untagged input.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (alg, hex_str) = s.split_once(':').ok_or_else(|| DigestParseError::MissingAlg(s.to_string()))?;
+        let alg = HashAlg::from_name(alg).ok_or_else(|| DigestParseError::UnknownAlg(alg.to_string()))?;
+        Self::from_hex(alg, hex_str)
+    }
+}
+
+impl From<Sha256Digest> for TaggedDigest {
+    fn from(d: Sha256Digest) -> Self {
+        Self { alg: HashAlg::Sha256, bytes: d.0.to_vec() }
+    }
+}
+
+impl PartialEq<Sha256Digest> for TaggedDigest {
+    fn eq(&self, other: &Sha256Digest) -> bool {
+        self.alg == HashAlg::Sha256 && bool::from(self.bytes.ct_eq(&other.0))
+    }
+}
+
+impl Serialize for Sha256Digest {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&self.hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Sha256Digest {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        String::deserialize(d)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Serialized in compat form: bare hex for SHA-256, `alg:hex` otherwise.
+impl Serialize for TaggedDigest {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&self.to_compat_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for TaggedDigest {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        TaggedDigest::parse_compat(&String::deserialize(d)?).map_err(serde::de::Error::custom)
+    }
+}
+
+impl ToCanon for Sha256Digest {
+    fn to_canon(&self) -> Canon {
+        Canon::Str(self.hex())
+    }
+}
+
+impl FromCanon for Sha256Digest {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        String::from_canon(v)?.parse().map_err(|e: DigestParseError| FromCanonError::new(e.to_string()))
+    }
+}
+
+/// Payload form is the compat string, so SHA-256 digests keep their pinned bytes.
+impl ToCanon for TaggedDigest {
+    fn to_canon(&self) -> Canon {
+        Canon::Str(self.to_compat_string())
+    }
+}
+
+impl FromCanon for TaggedDigest {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        TaggedDigest::parse_compat(&String::from_canon(v)?).map_err(|e| FromCanonError::new(e.to_string()))
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DigestParseError {
+    MissingAlg(String),
+    UnknownAlg(String),
+    BadHex(String),
+    BadLength { alg: HashAlg, got: usize },
+    WrongAlg { want: HashAlg, got: HashAlg },
+}
+
+impl fmt::Display for DigestParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DigestParseError::MissingAlg(s) => write!(f, "digest {s:?} has no alg: prefix"),
+            DigestParseError::UnknownAlg(a) => write!(f, "unknown hash algorithm {a:?}"),
+            DigestParseError::BadHex(s) => write!(f, "digest {s:?} is not lowercase hex"),
+            DigestParseError::BadLength { alg, got } => {
+                write!(f, "{alg} digest must be {} bytes, got {got}", alg.output_len())
+            }
+            DigestParseError::WrongAlg { want, got } => write!(f, "expected a {want} digest, got {got}"),
+        }
+    }
+}
+
+impl std::error::Error for DigestParseError {}
@@ -0,0 +1,293 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::canon::{Canon, CanonMode, canon_bytes_with, write_canon_with};
+use crate::cbor::parse_cbor;
+use crate::cert::{CertHashScheme, CertItem, KernelCert, deps_to_canon};
+use crate::convert::{FromCanonError, ToCanon, obj_field};
+use crate::digest::{HashAlg, TaggedDigest};
+use crate::merkle::{InclusionProof, inclusion_proof, merkle_leaf_hash, merkle_root};
+use crate::parse::parse_canon_v2;
+use crate::stream::CanonSink;
+
+/// Prefix of `CertHashScheme::MerkleV1` kernel hashes.
+pub const CERT_MERKLE_DOMAIN_V1: &str = "collapse/kernel-cert-merkle/v1";
+
+const FIELD_SALT_DOMAIN_V1: &str = "collapse/payload-field-salt/v1";
+
+/// Top-level fields in key order. A payload that is not an object is a single
+/// field with the empty key.
+fn payload_fields(payload: &Canon) -> Vec<(&str, &Canon)> {
+    match payload {
+        Canon::Obj(obj) => obj.iter().map(|(k, v)| (k.as_str(), v)).collect(),
+        other => vec![("", other)],
+    }
+}
+
+/// `alg(FIELD_SALT_DOMAIN_V1 || 0x00 || salt || key)`. Revealed with its field
+/// only, so hidden fields cannot be guessed from their leaf hashes.
+fn field_salt(alg: HashAlg, salt: &[u8; 32], key: &str) -> TaggedDigest {
+    let mut h = alg.hasher();
+    h.write_bytes(FIELD_SALT_DOMAIN_V1.as_bytes());
+    h.write_byte(0);
+    h.write_bytes(salt);
+    h.write_bytes(key.as_bytes());
+    h.finish()
+}
+
+/// Merkle leaf over `canon({key, salt, value})` in the cert's encoding.
+fn field_leaf(key: &str, salt: &TaggedDigest, value: &Canon, mode: CanonMode) -> TaggedDigest {
+    let mut obj = BTreeMap::new();
+    obj.insert("key".to_string(), Canon::Str(key.to_string()));
+    obj.insert("salt".to_string(), salt.to_canon());
+    obj.insert("value".to_string(), value.clone());
+    let mut data = Vec::new();
+    write_canon_with(&mut data, &Canon::Obj(obj), mode);
+    merkle_leaf_hash(salt.alg(), &data)
+}
+
+fn field_leaves(alg: HashAlg, salt: &[u8; 32], payload: &Canon, mode: CanonMode) -> Vec<TaggedDigest> {
+    payload_fields(payload).into_iter().map(|(k, v)| field_leaf(k, &field_salt(alg, salt, k), v, mode)).collect()
+}
+
+pub(crate) fn payload_root(alg: HashAlg, salt: &[u8; 32], payload: &Canon, mode: CanonMode) -> TaggedDigest {
+    merkle_root(alg, &field_leaves(alg, salt, payload, mode))
+}
+
+/// `alg(CERT_MERKLE_DOMAIN_V1 || 0x00 || canon({canon_mode, deps?, kernel_name, kernel_version, payload_root}))`.
+pub(crate) fn merkle_envelope_hash(
+    kernel_name: &str,
+    kernel_version: &str,
+    canon_mode: CanonMode,
+    deps: &[CertItem],
+    payload_root: &TaggedDigest,
+) -> TaggedDigest {
+    let mut h = payload_root.alg().hasher();
+    h.write_bytes(CERT_MERKLE_DOMAIN_V1.as_bytes());
+    h.write_byte(0);
+    let mut env = BTreeMap::new();
+    env.insert("canon_mode".to_string(), Canon::Str(canon_mode.tag().to_string()));
+    env.insert("kernel_name".to_string(), Canon::Str(kernel_name.to_string()));
+    env.insert("kernel_version".to_string(), Canon::Str(kernel_version.to_string()));
+    env.insert("payload_root".to_string(), payload_root.to_canon());
+    if !deps.is_empty() {
+        env.insert("deps".to_string(), deps_to_canon(deps));
+    }
+    write_canon_with(&mut h, &Canon::Obj(env), canon_mode);
+    h.finish()
+}
+
+/// One revealed payload field and its proof against `payload_root`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DisclosedField {
+    pub key: String,
+    pub value: Canon,
+    pub salt: TaggedDigest,
+    pub proof: InclusionProof,
+}
+
+/// What an auditor gets instead of the full cert: everything `kernel_hash`
+/// covers except the payload, which is replaced by its field root, plus the
+/// fields the holder chose to reveal. The other fields' keys and values stay
+/// hidden; only their number (`tree_size` of the proofs) is visible.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PayloadDisclosure {
+    pub kernel_name: String,
+    pub kernel_version: String,
+    pub canon_mode: CanonMode,
+    pub deps: Vec<CertItem>,
+    pub kernel_hash: TaggedDigest,
+    pub payload_root: TaggedDigest,
+    pub fields: Vec<DisclosedField>,
+}
+
+/// Why a `PayloadDisclosure` was rejected.
+#[derive(Clone, Debug)]
+pub enum DisclosureError {
+    Malformed(FromCanonError),
+    /// The envelope does not hash to the claimed `kernel_hash`.
+    HashMismatch { claimed: TaggedDigest, computed: TaggedDigest },
+    /// A revealed field that is not under `payload_root`.
+    BadProof { key: String },
+    /// `KernelCert::disclose` on a cert whose hash is not `CertHashScheme::MerkleV1`.
+    NotMerkle { scheme: CertHashScheme },
+    /// `KernelCert::disclose` asked for a key the payload does not have.
+    NoSuchField { key: String },
+    /// A `CertHashScheme::MerkleV1` cert without a `disclosure_salt`.
+    NoSalt,
+    /// `KernelCert::with_random_disclosure_salt` could not read the system RNG.
+    Rng(getrandom::Error),
+}
+
+impl fmt::Display for DisclosureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisclosureError::Malformed(e) => write!(f, "malformed disclosure: {e}"),
+            DisclosureError::HashMismatch { claimed, computed } => {
+                write!(f, "kernel_hash mismatch: disclosure claims {claimed}, envelope hashes to {computed}")
+            }
+            DisclosureError::BadProof { key } => write!(f, "field {key:?} is not under the payload root"),
+            DisclosureError::NotMerkle { scheme } => {
+                write!(f, "cert uses the {} hash scheme, not merkle/v1", scheme.tag())
+            }
+            DisclosureError::NoSuchField { key } => write!(f, "payload has no field {key:?}"),
+            DisclosureError::NoSalt => write!(f, "merkle/v1 needs a disclosure_salt"),
+            DisclosureError::Rng(e) => write!(f, "cannot draw a disclosure salt: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DisclosureError {}
+
+impl From<FromCanonError> for DisclosureError {
+    fn from(e: FromCanonError) -> Self {
+        DisclosureError::Malformed(e)
+    }
+}
+
+impl KernelCert {
+    /// Reveal the payload fields named in `keys`. The cert must use
+    /// `CertHashScheme::MerkleV1` and every key must be a top-level payload field.
+    pub fn disclose(&self, keys: &[&str]) -> Result<PayloadDisclosure, DisclosureError> {
+        if self.hash_scheme != CertHashScheme::MerkleV1 {
+            return Err(DisclosureError::NotMerkle { scheme: self.hash_scheme });
+        }
+        let salt = self.disclosure_salt.ok_or(DisclosureError::NoSalt)?;
+        let alg = self.kernel_hash.alg();
+        let leaves = field_leaves(alg, &salt, &self.payload, self.canon_mode);
+        let all = payload_fields(&self.payload);
+        let fields = keys
+            .iter()
+            .map(|&key| {
+                let index = all.iter().position(|(k, _)| *k == key);
+                let Some((index, proof)) = index.and_then(|i| Some((i, inclusion_proof(alg, &leaves, i)?))) else {
+                    return Err(DisclosureError::NoSuchField { key: key.to_string() });
+                };
+                let salt = field_salt(alg, &salt, key);
+                Ok(DisclosedField { key: key.to_string(), value: all[index].1.clone(), salt, proof })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(PayloadDisclosure {
+            kernel_name: self.kernel_name.clone(),
+            kernel_version: self.kernel_version.clone(),
+            canon_mode: self.canon_mode,
+            deps: self.deps.clone(),
+            kernel_hash: self.kernel_hash.clone(),
+            payload_root: merkle_root(alg, &leaves),
+            fields,
+        })
+    }
+}
+
+impl PayloadDisclosure {
+    /// Checks the envelope against `kernel_hash` and every field against
+    /// `payload_root`. Whether `kernel_hash` itself is trusted (chain,
+    /// signatures, revocations) is up to the caller.
+    pub fn verify(&self) -> Result<(), DisclosureError> {
+        let computed = merkle_envelope_hash(
+            &self.kernel_name,
+            &self.kernel_version,
+            self.canon_mode,
+            &self.deps,
+            &self.payload_root,
+        );
+        if computed != self.kernel_hash {
+            return Err(DisclosureError::HashMismatch { claimed: self.kernel_hash.clone(), computed });
+        }
+        for f in &self.fields {
+            let leaf = field_leaf(&f.key, &f.salt, &f.value, self.canon_mode);
+            if f.salt.alg() != self.payload_root.alg() || !f.proof.verify(&leaf, &self.payload_root) {
+                return Err(DisclosureError::BadProof { key: f.key.clone() });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn field(&self, key: &str) -> Option<&Canon> {
+        self.fields.iter().find(|f| f.key == key).map(|f| &f.value)
+    }
+
+    /// Values are stored as in a bundle: as-is for v1 and JCS, as encoded
+    /// bytes (`value_bytes`) for the type-tagged modes.
+    pub fn to_canon(&self) -> Canon {
+        let fields = self
+            .fields
+            .iter()
+            .map(|f| {
+                let mut obj = BTreeMap::new();
+                obj.insert("key".to_string(), Canon::Str(f.key.clone()));
+                obj.insert("leaf_index".to_string(), Canon::U64(f.proof.leaf_index));
+                obj.insert("path".to_string(), f.proof.path.to_canon());
+                obj.insert("salt".to_string(), f.salt.to_canon());
+                obj.insert("tree_size".to_string(), Canon::U64(f.proof.tree_size));
+                match self.canon_mode {
+                    CanonMode::V1 | CanonMode::Jcs => obj.insert("value".to_string(), f.value.clone()),
+                    mode => obj.insert(
+                        "value_bytes".to_string(),
+                        Canon::Str(hex::encode(canon_bytes_with(&f.value, mode))),
+                    ),
+                };
+                Canon::Obj(obj)
+            })
+            .collect();
+        let mut obj = BTreeMap::new();
+        obj.insert("canon_mode".to_string(), Canon::Str(self.canon_mode.tag().to_string()));
+        if !self.deps.is_empty() {
+            obj.insert("deps".to_string(), deps_to_canon(&self.deps));
+        }
+        obj.insert("fields".to_string(), Canon::Arr(fields));
+        obj.insert("kernel_hash".to_string(), self.kernel_hash.to_canon());
+        obj.insert("kernel_name".to_string(), Canon::Str(self.kernel_name.clone()));
+        obj.insert("kernel_version".to_string(), Canon::Str(self.kernel_version.clone()));
+        obj.insert("payload_root".to_string(), self.payload_root.to_canon());
+        Canon::Obj(obj)
+    }
+
+    /// Inverse of `to_canon`, then `verify`.
+    pub fn from_canon(v: &Canon) -> Result<Self, DisclosureError> {
+        let Canon::Obj(obj) = v else {
+            return Err(FromCanonError::expected("object", v).into());
+        };
+        let tag: String = obj_field(obj, "canon_mode")?;
+        let canon_mode = CanonMode::from_tag(&tag)
+            .ok_or_else(|| FromCanonError::new(format!("unknown canon_mode {tag:?}")).at("canon_mode"))?;
+        let raw: Vec<Canon> = obj_field(obj, "fields")?;
+        let mut fields = Vec::with_capacity(raw.len());
+        for (i, f) in raw.iter().enumerate() {
+            fields.push(field_from_canon(f, canon_mode).map_err(|e| e.at(&format!("[{i}]")).at("fields"))?);
+        }
+        let d = Self {
+            kernel_name: obj_field(obj, "kernel_name")?,
+            kernel_version: obj_field(obj, "kernel_version")?,
+            canon_mode,
+            deps: obj_field::<Option<Vec<CertItem>>>(obj, "deps")?.unwrap_or_default(),
+            kernel_hash: obj_field(obj, "kernel_hash")?,
+            payload_root: obj_field(obj, "payload_root")?,
+            fields,
+        };
+        d.verify()?;
+        Ok(d)
+    }
+}
+
+fn field_from_canon(v: &Canon, mode: CanonMode) -> Result<DisclosedField, FromCanonError> {
+    let Canon::Obj(obj) = v else {
+        return Err(FromCanonError::expected("object", v));
+    };
+    let value = match mode {
+        CanonMode::V1 | CanonMode::Jcs => obj_field(obj, "value")?,
+        CanonMode::V2 | CanonMode::Cbor => {
+            let bad = |msg: String| FromCanonError::new(msg).at("value_bytes");
+            let encoded: String = obj_field(obj, "value_bytes")?;
+            let bytes = hex::decode(&encoded).map_err(|e| bad(e.to_string()))?;
+            let parsed = if mode == CanonMode::V2 { parse_canon_v2(&bytes) } else { parse_cbor(&bytes) };
+            parsed.map_err(|e| bad(e.to_string()))?
+        }
+    };
+    let proof = InclusionProof {
+        leaf_index: obj_field(obj, "leaf_index")?,
+        tree_size: obj_field(obj, "tree_size")?,
+        path: obj_field(obj, "path")?,
+    };
+    Ok(DisclosedField { key: obj_field(obj, "key")?, value, salt: obj_field(obj, "salt")?, proof })
+}
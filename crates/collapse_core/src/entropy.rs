@@ -1,3 +1,5 @@
+use crate::exact::Decimal;
+
 pub fn log2_u64(n: u64) -> f64 {
     (n as f64).log2()
 }
@@ -5,3 +7,8 @@ pub fn log2_u64(n: u64) -> f64 {
 pub fn sem_entropy_bits(num_classes: usize) -> f64 {
     log2_u64(num_classes as u64)
 }
+
+/// `sem_entropy_bits` as a payload-ready fixed-point value with `scale` decimal places.
+pub fn sem_entropy_decimal(num_classes: usize, scale: u8) -> Decimal {
+    Decimal::from_f64(sem_entropy_bits(num_classes), scale)
+}
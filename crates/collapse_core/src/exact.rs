@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::canon::Canon;
+
+/// Exact rational `num/den`, always reduced with `den > 0`.
+///
+/// In v1 and JCS it encodes as the object `{"den":d,"num":n}`, the same shape
+/// `domain_digest_hex` has always used for `QE`; v2 and CBOR tag it explicitly.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Rational {
+    num: i64,
+    den: u64,
+}
+
+fn gcd_u64(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+impl Rational {
+    /// Reduces and normalizes the sign. Panics on a zero denominator or if the
+    /// reduced value does not fit (`i64::MIN / -1`).
+    pub fn new(num: i64, den: i64) -> Self {
+        if den == 0 {
+            panic!("Rational::new: zero denominator");
+        }
+        let (mut n, mut d) = (num as i128, den as i128);
+        if d < 0 {
+            n = -n;
+            d = -d;
+        }
+        let g = gcd_u64(n.unsigned_abs() as u64, d as u64).max(1) as i128;
+        let (n, d) = (n / g, d / g);
+        match i64::try_from(n) {
+            Ok(num) => Self { num, den: d as u64 },
+            Err(_) => panic!("Rational::new: {num}/{den} overflows i64"),
+        }
+    }
+
+    /// Accepts only already-canonical parts (reduced, `den > 0`); used by decoders.
+    pub fn from_parts(num: i64, den: u64) -> Option<Self> {
+        (den != 0 && gcd_u64(num.unsigned_abs(), den) == 1).then_some(Self { num, den })
+    }
+
+    pub fn num(&self) -> i64 { self.num }
+    pub fn den(&self) -> u64 { self.den }
+
+    pub fn to_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    /// The v1/JCS object form.
+    pub fn to_obj(&self) -> Canon {
+        let mut o = BTreeMap::new();
+        o.insert("den".to_string(), Canon::U64(self.den));
+        o.insert("num".to_string(), Canon::I64(self.num));
+        Canon::Obj(o)
+    }
+
+    /// Inverse of `to_obj`, accepting any integer variants; `None` if not canonical.
+    pub fn from_obj(v: &Canon) -> Option<Self> {
+        let Canon::Obj(o) = v else { return None };
+        if o.len() != 2 {
+            return None;
+        }
+        let num = match o.get("num")? {
+            Canon::I64(n) => *n,
+            Canon::U64(n) => i64::try_from(*n).ok()?,
+            _ => return None,
+        };
+        let den = match o.get("den")? {
+            Canon::U64(n) => *n,
+            Canon::I64(n) => u64::try_from(*n).ok()?,
+            _ => return None,
+        };
+        Self::from_parts(num, den)
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.num, self.den)
+    }
+}
+
+/// Largest `Decimal` scale; `10^18` is the largest power of ten in `i64`.
+pub const MAX_DECIMAL_SCALE: u8 = 18;
+
+/// Fixed-point decimal `mantissa * 10^-scale`.
+///
+/// The scale is part of the value, not normalized away: `1.50` (150, 2) and
+/// `1.5` (15, 1) are distinct and hash differently. Pick one scale per field.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Decimal {
+    mantissa: i64,
+    scale: u8,
+}
+
+impl Decimal {
+    pub fn new(mantissa: i64, scale: u8) -> Self {
+        assert!(scale <= MAX_DECIMAL_SCALE, "Decimal::new: scale {scale} exceeds {MAX_DECIMAL_SCALE}");
+        Self { mantissa, scale }
+    }
+
+    /// `round(x * 10^scale)`, half away from zero. Panics if `x` is not finite
+    /// or the result does not fit in `i64`.
+    pub fn from_f64(x: f64, scale: u8) -> Self {
+        assert!(scale <= MAX_DECIMAL_SCALE, "Decimal::from_f64: scale {scale} exceeds {MAX_DECIMAL_SCALE}");
+        let m = (x * 10f64.powi(scale as i32)).round();
+        assert!(m.is_finite() && m >= i64::MIN as f64 && m < i64::MAX as f64, "Decimal::from_f64: {x} out of range");
+        Self { mantissa: m as i64, scale }
+    }
+
+    pub fn mantissa(&self) -> i64 { self.mantissa }
+    pub fn scale(&self) -> u8 { self.scale }
+
+    pub fn to_f64(&self) -> f64 {
+        self.mantissa as f64 / 10f64.powi(self.scale as i32)
+    }
+
+    /// The v1/JCS object form.
+    pub fn to_obj(&self) -> Canon {
+        let mut o = BTreeMap::new();
+        o.insert("mantissa".to_string(), Canon::I64(self.mantissa));
+        o.insert("scale".to_string(), Canon::U64(self.scale as u64));
+        Canon::Obj(o)
+    }
+
+    /// Inverse of `to_obj`, accepting any integer variants.
+    pub fn from_obj(v: &Canon) -> Option<Self> {
+        let Canon::Obj(o) = v else { return None };
+        if o.len() != 2 {
+            return None;
+        }
+        let mantissa = match o.get("mantissa")? {
+            Canon::I64(n) => *n,
+            Canon::U64(n) => i64::try_from(*n).ok()?,
+            _ => return None,
+        };
+        let scale = match o.get("scale")? {
+            Canon::U64(n) => *n,
+            Canon::I64(n) => u64::try_from(*n).ok()?,
+            _ => return None,
+        };
+        (scale <= MAX_DECIMAL_SCALE as u64).then_some(Self { mantissa, scale: scale as u8 })
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.scale == 0 {
+            return write!(f, "{}", self.mantissa);
+        }
+        let digits = format!("{:0>width$}", self.mantissa.unsigned_abs(), width = self.scale as usize + 1);
+        let (int, frac) = digits.split_at(digits.len() - self.scale as usize);
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        write!(f, "{sign}{int}.{frac}")
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::cert::{CertChain, KernelCert};
+use crate::digest::TaggedDigest;
+
+/// Certs as nodes, declared `deps` as edges (upstream -> dependent). A `Legacy`
+/// cert's hash does not cover its deps, and the graph cannot tell which payload
+/// field names each, so those edges are unbound (`KernelCert::unbound_deps`).
+#[derive(Clone, Debug, Default)]
+pub struct CertGraph {
+    certs: Vec<KernelCert>,
+}
+
+/// One problem found by `CertGraph::validate`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CertGraphError {
+    /// `kernel` declares `dep`, but no chain item has that name.
+    MissingDep { kernel: String, dep: String },
+    /// The chain has `dep`, but under a different hash than `kernel` declared.
+    DepHashMismatch { kernel: String, dep: String, declared: TaggedDigest, found: TaggedDigest },
+    /// Kernels (sorted) that depend on each other in a loop.
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for CertGraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CertGraphError::MissingDep { kernel, dep } => write!(f, "{kernel}: dependency {dep} is not in the chain"),
+            CertGraphError::DepHashMismatch { kernel, dep, declared, found } => {
+                write!(f, "{kernel}: dependency {dep} declared as {declared}, chain has {found}")
+            }
+            CertGraphError::Cycle(names) => write!(f, "dependency cycle among {}", names.join(", ")),
+        }
+    }
+}
+
+impl std::error::Error for CertGraphError {}
+
+impl CertGraph {
+    pub fn new(certs: Vec<KernelCert>) -> Self {
+        Self { certs }
+    }
+
+    pub fn certs(&self) -> &[KernelCert] {
+        &self.certs
+    }
+
+    /// Upstream-first order. Deps that are not nodes of the graph are ignored.
+    pub fn topo_order(&self) -> Result<Vec<&KernelCert>, CertGraphError> {
+        let index: BTreeMap<&str, usize> =
+            self.certs.iter().enumerate().map(|(i, c)| (c.kernel_name.as_str(), i)).collect();
+        let mut indegree = vec![0usize; self.certs.len()];
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); self.certs.len()];
+        for (i, c) in self.certs.iter().enumerate() {
+            for d in &c.deps {
+                if let Some(&j) = index.get(d.name.as_str()) {
+                    dependents[j].push(i);
+                    indegree[i] += 1;
+                }
+            }
+        }
+        let mut ready: Vec<usize> = (0..self.certs.len()).filter(|&i| indegree[i] == 0).rev().collect();
+        let mut order = Vec::with_capacity(self.certs.len());
+        while let Some(i) = ready.pop() {
+            order.push(&self.certs[i]);
+            for &k in dependents[i].iter().rev() {
+                indegree[k] -= 1;
+                if indegree[k] == 0 {
+                    ready.push(k);
+                }
+            }
+        }
+        if order.len() < self.certs.len() {
+            let stuck: BTreeSet<String> = (0..self.certs.len())
+                .filter(|&i| indegree[i] > 0)
+                .map(|i| self.certs[i].kernel_name.clone())
+                .collect();
+            return Err(CertGraphError::Cycle(stuck.into_iter().collect()));
+        }
+        Ok(order)
+    }
+
+    /// Every declared dependency must appear in `chain` with the declared hash, and
+    /// the graph must be acyclic. Collects all problems. Unbound deps are not
+    /// authenticated here; `KernelRegistry::verify_cert` binds them.
+    pub fn validate(&self, chain: &CertChain) -> Result<(), Vec<CertGraphError>> {
+        let mut errs = Vec::new();
+        for c in &self.certs {
+            for d in &c.deps {
+                let mut found = chain.items.iter().filter(|it| it.name == d.name).peekable();
+                let Some(first) = found.peek().map(|it| it.hash.clone()) else {
+                    errs.push(CertGraphError::MissingDep { kernel: c.kernel_name.clone(), dep: d.name.clone() });
+                    continue;
+                };
+                if !found.any(|it| it.hash == d.hash) {
+                    errs.push(CertGraphError::DepHashMismatch {
+                        kernel: c.kernel_name.clone(),
+                        dep: d.name.clone(),
+                        declared: d.hash.clone(),
+                        found: first,
+                    });
+                }
+            }
+        }
+        if let Err(e) = self.topo_order() {
+            errs.push(e);
+        }
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+
+    /// Graphviz DOT. Nodes are labelled `name@version` and the short hash; deps
+    /// that are not nodes of the graph are drawn dashed, and unbound edges dotted.
+    pub fn to_dot(&self) -> String {
+        let nodes: BTreeSet<&str> = self.certs.iter().map(|c| c.kernel_name.as_str()).collect();
+        let mut out = String::from("digraph certs {\n    rankdir=LR;\n");
+        for c in &self.certs {
+            let short = &c.kernel_hash.hex()[..12];
+            let label = format!("{}@{}\\n{}:{short}", c.kernel_name, c.kernel_version, c.kernel_hash.alg());
+            out.push_str(&format!("    {} [label={}];\n", dot_str(&c.kernel_name), dot_str(&label)));
+        }
+        let mut external = BTreeSet::new();
+        for c in &self.certs {
+            let unbound: Vec<&str> = c.unbound_deps().map(|d| d.name.as_str()).collect();
+            for d in &c.deps {
+                if !nodes.contains(d.name.as_str()) && external.insert(d.name.as_str()) {
+                    out.push_str(&format!("    {} [style=dashed];\n", dot_str(&d.name)));
+                }
+                let style = if unbound.contains(&d.name.as_str()) { " [style=dotted]" } else { "" };
+                out.push_str(&format!("    {} -> {}{style};\n", dot_str(&d.name), dot_str(&c.kernel_name)));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Quoted DOT string; `\n` sequences already in `s` are kept as line breaks.
+fn dot_str(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\\\""))
+}
@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::canon::Canon;
+use crate::cert::{CertHashScheme, CertItem, KernelCert};
+use crate::convert::FromCanonError;
+use crate::diff::canon_diff;
+use crate::digest::TaggedDigest;
+use crate::schema::{Schema, SchemaConflict, SchemaError, SchemaRegistry};
+
+/// A certified component: something that produces a payload under a fixed
+/// `(NAME, VERSION)` and can check any payload claiming that identity.
+///
+/// `verify` takes no `self`, so a `KernelRegistry` can hold it without an
+/// instance of the kernel's configuration.
+pub trait Kernel {
+    const NAME: &'static str;
+    const VERSION: &'static str;
+    /// Older versions of this kernel whose payloads `verify` also accepts.
+    const COMPATIBLE: &'static [&'static str] = &[];
+    /// Hash certs with `CertHashScheme::Legacy`, for kernels whose hashes are pinned.
+    const LEGACY_HASH: bool = false;
+
+    fn payload(&self) -> Canon;
+
+    /// Upstream certs a payload of this kernel was derived from, as named by
+    /// the payload itself. `KernelRegistry::verify_cert` holds a cert's `deps` to it.
+    fn deps_of(_payload: &Canon) -> Result<Vec<CertItem>, KernelError> {
+        Ok(Vec::new())
+    }
+
+    /// Payload schema, collected by `KernelRegistry::register`.
+    fn schema() -> Option<Schema> {
+        None
+    }
+
+    /// Checks the payload against `schema` first. A payload `deps_of` cannot
+    /// read gets no deps, and fails `verify_cert`.
+    fn cert(&self) -> Result<KernelCert, Vec<SchemaError>> {
+        let (name, version, payload) = (Self::NAME, Self::VERSION, self.payload());
+        if let Some(schema) = Self::schema() {
+            schema.validate(&payload)?;
+        }
+        let deps = Self::deps_of(&payload).unwrap_or_default();
+        let mut cert = KernelCert::new_unchecked(name, version, payload);
+        if Self::LEGACY_HASH {
+            cert = cert.with_hash_scheme(CertHashScheme::Legacy).expect("only merkle/v1 needs a salt");
+        }
+        Ok(cert.with_deps(deps))
+    }
+
+    /// Re-derive what can be re-derived from `payload` and the certs it refers to.
+    fn verify(payload: &Canon, inputs: &KernelInputs<'_>) -> Result<(), KernelError>;
+}
+
+/// Why a payload failed its kernel's check.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum KernelError {
+    /// No registered kernel verifies this name and version.
+    Unregistered { name: String, version: String },
+    /// The payload refers to a cert that is not among the inputs.
+    MissingInput { name: String, hash: TaggedDigest },
+    Payload(FromCanonError),
+    /// A payload location that does not hold what the kernel would produce.
+    Invalid { path: String, reason: String },
+    /// A cert whose `deps` are not the ones its payload names.
+    DepsMismatch { declared: Vec<CertItem>, expected: Vec<CertItem> },
+}
+
+impl KernelError {
+    pub fn invalid(path: &str, reason: impl Into<String>) -> Self {
+        KernelError::Invalid { path: path.to_string(), reason: reason.into() }
+    }
+
+    /// `Invalid` at the first difference, or `Ok` if there is none.
+    pub fn check_eq(expected: &Canon, found: &Canon, reason: &str) -> Result<(), Self> {
+        match canon_diff(expected, found).changes.first() {
+            Some(c) => Err(Self::invalid(c.path(), reason)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for KernelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KernelError::Unregistered { name, version } => write!(f, "no kernel registered for {name}@{version}"),
+            KernelError::MissingInput { name, hash } => write!(f, "input {name} ({hash}) is missing"),
+            KernelError::Payload(e) => write!(f, "malformed payload: {e}"),
+            KernelError::Invalid { path, reason } => {
+                let path = if path.is_empty() { "$" } else { path };
+                write!(f, "{path}: {reason}")
+            }
+            KernelError::DepsMismatch { declared, expected } => {
+                let list = |deps: &[CertItem]| {
+                    deps.iter().map(|d| format!("{}@{}", d.name, d.hash)).collect::<Vec<_>>().join(", ")
+                };
+                write!(f, "declared deps [{}] are not the payload's [{}]", list(declared), list(expected))
+            }
+        }
+    }
+}
+
+impl std::error::Error for KernelError {}
+
+impl From<FromCanonError> for KernelError {
+    fn from(e: FromCanonError) -> Self {
+        KernelError::Payload(e)
+    }
+}
+
+/// The certs a payload may refer to. In a bundle, every cert in it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KernelInputs<'a> {
+    certs: &'a [KernelCert],
+}
+
+impl<'a> KernelInputs<'a> {
+    pub fn new(certs: &'a [KernelCert]) -> Self {
+        Self { certs }
+    }
+
+    pub fn find(&self, name: &str, hash: &TaggedDigest) -> Option<&'a KernelCert> {
+        self.certs.iter().find(|c| c.kernel_name == name && c.kernel_hash == *hash)
+    }
+
+    pub fn require(&self, name: &str, hash: &TaggedDigest) -> Result<&'a KernelCert, KernelError> {
+        self.find(name, hash).ok_or_else(|| KernelError::MissingInput { name: name.to_string(), hash: hash.clone() })
+    }
+}
+
+type VerifyFn = fn(&Canon, &KernelInputs<'_>) -> Result<(), KernelError>;
+/// `Kernel::deps_of`.
+pub type DepsFn = fn(&Canon) -> Result<Vec<CertItem>, KernelError>;
+
+struct Registered {
+    version: String,
+    compatible: &'static [&'static str],
+    verify: VerifyFn,
+    deps_of: DepsFn,
+}
+
+/// `KernelRegistry::register` was given a kernel version it already has.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DuplicateKernel {
+    pub kernel_name: String,
+    pub kernel_version: String,
+}
+
+impl fmt::Display for DuplicateKernel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{} is already registered", self.kernel_name, self.kernel_version)
+    }
+}
+
+impl std::error::Error for DuplicateKernel {}
+
+impl From<SchemaConflict> for DuplicateKernel {
+    fn from(c: SchemaConflict) -> Self {
+        DuplicateKernel { kernel_name: c.kernel_name, kernel_version: c.kernel_version }
+    }
+}
+
+/// Which implementation verified a cert. `verified_by` differs from `version`
+/// when a newer kernel accepted an older payload.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KernelCheck {
+    pub name: String,
+    pub version: String,
+    pub verified_by: String,
+}
+
+/// Kernel verifiers keyed by `(kernel_name, kernel_version)`, with their payload schemas.
+#[derive(Default)]
+pub struct KernelRegistry {
+    kernels: BTreeMap<String, Vec<Registered>>,
+    schemas: SchemaRegistry,
+}
+
+/// Dotted numeric order, so `1.10.0` sorts after `1.9.0`. Non-numeric parts sort first.
+fn version_key(v: &str) -> Vec<u64> {
+    v.split('.').map(|p| p.parse().unwrap_or(0)).collect()
+}
+
+impl KernelRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also records `K::schema()`, and lets `K`'s certs load as `Legacy`, with
+    /// deps bound by `K::deps_of`, if `K::LEGACY_HASH`. Fails if `K::NAME@K::VERSION` is already registered.
+    pub fn register<K: Kernel>(&mut self) -> Result<&mut Self, DuplicateKernel> {
+        let versions = self.kernels.entry(K::NAME.to_string()).or_default();
+        if versions.iter().any(|r| r.version == K::VERSION) {
+            return Err(DuplicateKernel { kernel_name: K::NAME.to_string(), kernel_version: K::VERSION.to_string() });
+        }
+        // Schemas are only added here, so a conflict is the same duplicate.
+        if let Some(s) = K::schema() {
+            self.schemas.register(K::NAME, K::VERSION, s)?;
+        }
+        if K::LEGACY_HASH {
+            self.schemas.allow_legacy(K::NAME, K::VERSION, K::deps_of);
+        }
+        let version = K::VERSION.to_string();
+        versions.push(Registered { version, compatible: K::COMPATIBLE, verify: K::verify, deps_of: K::deps_of });
+        versions.sort_by_key(|r| version_key(&r.version));
+        Ok(self)
+    }
+
+    /// Schemas of the registered kernels, for loaders such as `CertBundle::from_bytes`.
+    pub fn schemas(&self) -> &SchemaRegistry {
+        &self.schemas
+    }
+
+    pub fn contains(&self, name: &str, version: &str) -> bool {
+        self.resolve(name, version).is_some()
+    }
+
+    /// The exact version if registered, else the newest registered version
+    /// that declares `version` compatible.
+    fn resolve(&self, name: &str, version: &str) -> Option<&Registered> {
+        let versions = self.kernels.get(name)?;
+        versions
+            .iter()
+            .find(|r| r.version == version)
+            .or_else(|| versions.iter().rev().find(|r| r.compatible.contains(&version)))
+    }
+
+    /// Runs the kernel's check on the payload and requires `cert.deps` to be
+    /// exactly the deps the payload names.
+    pub fn verify_cert(&self, cert: &KernelCert, inputs: &KernelInputs<'_>) -> Result<KernelCheck, KernelError> {
+        let (name, version) = (&cert.kernel_name, &cert.kernel_version);
+        let r = self
+            .resolve(name, version)
+            .ok_or_else(|| KernelError::Unregistered { name: name.clone(), version: version.clone() })?;
+        (r.verify)(&cert.payload, inputs)?;
+        let expected = (r.deps_of)(&cert.payload)?;
+        if expected != cert.deps {
+            return Err(KernelError::DepsMismatch { declared: cert.deps.clone(), expected });
+        }
+        Ok(KernelCheck { name: name.clone(), version: version.clone(), verified_by: r.version.clone() })
+    }
+}
@@ -1,11 +1,68 @@
 pub mod digest;
 pub mod canon;
+pub mod exact;
+pub mod parse;
+pub mod cbor;
+pub mod diff;
+pub mod stream;
+pub mod serde_canon;
+pub mod convert;
+pub mod schema;
 pub mod quotient;
 pub mod entropy;
+pub mod merkle;
 pub mod cert;
+pub mod kernel;
+pub mod disclose;
+pub mod graph;
+pub mod provenance;
+pub mod sign;
+pub mod bundle;
+pub mod attest;
+pub mod revoke;
+pub mod policy;
+pub mod translog;
 
-pub use digest::{Sha256Digest, sha256_bytes, sha256_hex};
-pub use canon::{Canon, canon_bytes};
+pub use digest::{DigestParseError, HashAlg, Hasher, Sha256Digest, TaggedDigest, sha256_bytes, sha256_hex};
+pub use canon::{Canon, CanonMode, canon_bytes, canon_bytes_with, write_canon_with};
+pub use exact::{Decimal, MAX_DECIMAL_SCALE, Rational};
+pub use stream::{CanonSink, HashSink, canon_digest_with, canon_sha256, canon_sha256_with, write_array, write_object};
+pub use diff::{CanonChange, CanonDiff, canon_diff};
+pub use cbor::{cbor_bytes, cbor_to_v1_bytes, parse_cbor, v1_bytes_to_cbor};
+pub use parse::{CanonParseError, CanonParseErrorKind, parse_canon, parse_canon_v2, parse_canon_versioned};
+pub use serde_canon::{CanonSerdeError, from_canon_value, to_canon_value};
+pub use convert::{FromCanon, FromCanonError, ToCanon};
+pub use collapse_derive::{FromCanon, ToCanon};
 pub use quotient::{Quotient, Signature};
-pub use entropy::{log2_u64, sem_entropy_bits};
-pub use cert::{KernelCert, CertChain, CertItem, cert_chain_hash};
+pub use entropy::{log2_u64, sem_entropy_bits, sem_entropy_decimal};
+pub use schema::{ObjectSchema, Schema, SchemaConflict, SchemaError, SchemaRegistry};
+pub use merkle::{
+    ConsistencyProof, InclusionProof, consistency_proof, inclusion_proof, merkle_leaf_hash, merkle_node_hash, merkle_root,
+};
+pub use cert::{
+    CERT_HASH_DOMAIN_V1, CHAIN_LINK_DOMAIN_V1, CertChain, CertHashScheme, CertItem, CertLoadError, ChainMode,
+    ChainVerifyError, KernelCert, VersionBumpError, cert_chain_digest, cert_chain_hash, cert_link_hash, verify_cert_inclusion,
+};
+pub use kernel::{DepsFn, DuplicateKernel, Kernel, KernelCheck, KernelError, KernelInputs, KernelRegistry};
+pub use disclose::{CERT_MERKLE_DOMAIN_V1, DisclosedField, DisclosureError, PayloadDisclosure};
+pub use graph::{CertGraph, CertGraphError};
+pub use provenance::{
+    ChainDrift, ItemDrift, PROVENANCE_KERNEL, PROVENANCE_VERSION, Provenance, chain_drift, provenance_schema,
+};
+pub use bundle::{BUNDLE_FORMAT_V1, BundleError, CertBundle, read_bundle, write_bundle};
+pub use attest::{
+    AttestationError, CHAIN_SUBJECT_NAME, DsseEnvelope, DsseSignature, IN_TOTO_PAYLOAD_TYPE, IN_TOTO_STATEMENT_V1,
+    SPINE_PREDICATE_TYPE_V1, dsse_pae, export_attestation, import_attestation, in_toto_digest_name, in_toto_statement,
+};
+pub use sign::{
+    CertHeader, CertSignature, CertSigner, PublicKey, SIGNATURE_DOMAIN_V1, SignatureError, SignedSubject, TrustStore,
+};
+pub use revoke::{
+    CertId, REVOCATION_FORMAT_V1, Revocation, RevocationList, RevocationLoadError, RevocationPolicy, RevocationTarget,
+    RevokedItem,
+};
+pub use policy::{
+    Condition, POLICY_FORMAT_V1, PayloadPath, Policy, PolicyLoadError, PolicyReport, PolicyRule, PolicyViolation,
+    RuleCheck, RuleOutcome,
+};
+pub use translog::{LogEntry, LogError, LogVerifier, TransparencyLog, TreeHead};
@@ -0,0 +1,194 @@
+use crate::digest::{HashAlg, TaggedDigest};
+use crate::stream::CanonSink;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// `H(0x00 || data)` (RFC 6962 §2.1).
+pub fn merkle_leaf_hash(alg: HashAlg, data: &[u8]) -> TaggedDigest {
+    let mut h = alg.hasher();
+    h.write_bytes(&[LEAF_PREFIX]);
+    h.write_bytes(data);
+    h.finish()
+}
+
+/// `H(0x01 || left || right)`. Panics if the two sides use different algorithms.
+pub fn merkle_node_hash(left: &TaggedDigest, right: &TaggedDigest) -> TaggedDigest {
+    assert_eq!(left.alg(), right.alg(), "merkle_node_hash: mixed hash algorithms");
+    let mut h = left.alg().hasher();
+    h.write_bytes(&[NODE_PREFIX]);
+    h.write_bytes(left.as_bytes());
+    h.write_bytes(right.as_bytes());
+    h.finish()
+}
+
+/// Largest power of two strictly less than `n` (`n >= 2`).
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+/// Merkle tree hash over already leaf-hashed entries. The empty tree hashes to `H("")`.
+pub fn merkle_root(alg: HashAlg, leaves: &[TaggedDigest]) -> TaggedDigest {
+    match leaves.len() {
+        0 => alg.hash(b""),
+        1 => leaves[0].clone(),
+        n => {
+            let k = split_point(n);
+            merkle_node_hash(&merkle_root(alg, &leaves[..k]), &merkle_root(alg, &leaves[k..]))
+        }
+    }
+}
+
+/// Audit path for one leaf, ordered from the leaf up to the root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InclusionProof {
+    pub leaf_index: u64,
+    pub tree_size: u64,
+    pub path: Vec<TaggedDigest>,
+}
+
+fn audit_path(alg: HashAlg, m: usize, leaves: &[TaggedDigest], out: &mut Vec<TaggedDigest>) {
+    let n = leaves.len();
+    if n <= 1 {
+        return;
+    }
+    let k = split_point(n);
+    if m < k {
+        audit_path(alg, m, &leaves[..k], out);
+        out.push(merkle_root(alg, &leaves[k..]));
+    } else {
+        audit_path(alg, m - k, &leaves[k..], out);
+        out.push(merkle_root(alg, &leaves[..k]));
+    }
+}
+
+/// `None` if `index` is out of range.
+pub fn inclusion_proof(alg: HashAlg, leaves: &[TaggedDigest], index: usize) -> Option<InclusionProof> {
+    if index >= leaves.len() {
+        return None;
+    }
+    let mut path = Vec::new();
+    audit_path(alg, index, leaves, &mut path);
+    Some(InclusionProof { leaf_index: index as u64, tree_size: leaves.len() as u64, path })
+}
+
+impl InclusionProof {
+    /// RFC 9162 §2.1.3.2. Every digest must use `root`'s algorithm.
+    pub fn verify(&self, leaf_hash: &TaggedDigest, root: &TaggedDigest) -> bool {
+        let alg = root.alg();
+        if self.leaf_index >= self.tree_size
+            || leaf_hash.alg() != alg
+            || self.path.iter().any(|p| p.alg() != alg)
+        {
+            return false;
+        }
+        let (mut fnode, mut snode) = (self.leaf_index, self.tree_size - 1);
+        let mut r = leaf_hash.clone();
+        for p in &self.path {
+            if snode == 0 {
+                return false;
+            }
+            if fnode & 1 == 1 || fnode == snode {
+                r = merkle_node_hash(p, &r);
+                while fnode & 1 == 0 && fnode != 0 {
+                    fnode >>= 1;
+                    snode >>= 1;
+                }
+            } else {
+                r = merkle_node_hash(&r, p);
+            }
+            fnode >>= 1;
+            snode >>= 1;
+        }
+        snode == 0 && r == *root
+    }
+}
+
+/// Proof that the tree of `old_size` leaves is a prefix of the tree of `new_size`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsistencyProof {
+    pub old_size: u64,
+    pub new_size: u64,
+    pub path: Vec<TaggedDigest>,
+}
+
+/// RFC 6962 §2.1.2 SUBPROOF.
+fn subproof(alg: HashAlg, m: usize, leaves: &[TaggedDigest], complete: bool, out: &mut Vec<TaggedDigest>) {
+    let n = leaves.len();
+    if m == n {
+        if !complete {
+            out.push(merkle_root(alg, leaves));
+        }
+        return;
+    }
+    let k = split_point(n);
+    if m <= k {
+        subproof(alg, m, &leaves[..k], complete, out);
+        out.push(merkle_root(alg, &leaves[k..]));
+    } else {
+        subproof(alg, m - k, &leaves[k..], false, out);
+        out.push(merkle_root(alg, &leaves[..k]));
+    }
+}
+
+/// Proof that the first `old_size` of `leaves` form a prefix of all of them.
+/// `None` if `old_size > leaves.len()`.
+pub fn consistency_proof(alg: HashAlg, leaves: &[TaggedDigest], old_size: usize) -> Option<ConsistencyProof> {
+    if old_size > leaves.len() {
+        return None;
+    }
+    let mut path = Vec::new();
+    if old_size > 0 && old_size < leaves.len() {
+        subproof(alg, old_size, leaves, true, &mut path);
+    }
+    Some(ConsistencyProof { old_size: old_size as u64, new_size: leaves.len() as u64, path })
+}
+
+impl ConsistencyProof {
+    /// RFC 9162 §2.1.4.2. An empty old tree is consistent with anything.
+    pub fn verify(&self, old_root: &TaggedDigest, new_root: &TaggedDigest) -> bool {
+        let alg = new_root.alg();
+        if self.old_size > self.new_size || old_root.alg() != alg || self.path.iter().any(|p| p.alg() != alg) {
+            return false;
+        }
+        if self.old_size == 0 {
+            return self.path.is_empty();
+        }
+        if self.old_size == self.new_size {
+            return self.path.is_empty() && old_root == new_root;
+        }
+        let mut path = self.path.clone();
+        if self.old_size.is_power_of_two() {
+            path.insert(0, old_root.clone());
+        }
+        let Some((first, rest)) = path.split_first() else { return false };
+        let (mut fnode, mut snode) = (self.old_size - 1, self.new_size - 1);
+        while fnode & 1 == 1 {
+            fnode >>= 1;
+            snode >>= 1;
+        }
+        let (mut fr, mut sr) = (first.clone(), first.clone());
+        for c in rest {
+            if snode == 0 {
+                return false;
+            }
+            if fnode & 1 == 1 || fnode == snode {
+                fr = merkle_node_hash(c, &fr);
+                sr = merkle_node_hash(c, &sr);
+                while fnode & 1 == 0 && fnode != 0 {
+                    fnode >>= 1;
+                    snode >>= 1;
+                }
+            } else {
+                sr = merkle_node_hash(&sr, c);
+            }
+            fnode >>= 1;
+            snode >>= 1;
+        }
+        snode == 0 && fr == *old_root && sr == *new_root
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::canon::{CANON_V2_MAGIC, Canon, CanonMode, v2_tag};
+use crate::exact::{Decimal, MAX_DECIMAL_SCALE, Rational};
+
+/// Nesting limit for `parse_canon`; deeper input is rejected rather than recursed into.
+pub const MAX_CANON_DEPTH: usize = 128;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CanonParseErrorKind {
+    UnexpectedEnd,
+    UnexpectedByte(u8),
+    TrailingBytes,
+    LeadingZero,
+    NegativeZero,
+    IntegerOverflow,
+    UnknownEscape(u8),
+    UnescapedByte(u8),
+    InvalidUtf8,
+    UnsortedKey,
+    DuplicateKey,
+    TooDeep,
+    BadMagic,
+    UnknownTag(u8),
+    NonShortestHead,
+    /// A rational that is not reduced or has a zero denominator, or a decimal scale out of range.
+    NonCanonicalNumber,
+}
+
+/// Parse failure: what went wrong and the byte offset where it was detected.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CanonParseError {
+    pub offset: usize,
+    pub kind: CanonParseErrorKind,
+}
+
+impl fmt::Display for CanonParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            CanonParseErrorKind::UnexpectedEnd => write!(f, "unexpected end of input at byte {}", self.offset),
+            CanonParseErrorKind::UnexpectedByte(b) => write!(f, "unexpected byte 0x{b:02x} at byte {}", self.offset),
+            CanonParseErrorKind::TrailingBytes => write!(f, "trailing bytes after value at byte {}", self.offset),
+            CanonParseErrorKind::LeadingZero => write!(f, "leading zero in integer at byte {}", self.offset),
+            CanonParseErrorKind::NegativeZero => write!(f, "negative zero at byte {}", self.offset),
+            CanonParseErrorKind::IntegerOverflow => write!(f, "integer out of range at byte {}", self.offset),
+            CanonParseErrorKind::UnknownEscape(b) => write!(f, "unknown escape \\{} at byte {}", *b as char, self.offset),
+            CanonParseErrorKind::UnescapedByte(b) => write!(f, "byte 0x{b:02x} must be escaped at byte {}", self.offset),
+            CanonParseErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8 in string at byte {}", self.offset),
+            CanonParseErrorKind::UnsortedKey => write!(f, "object key out of order at byte {}", self.offset),
+            CanonParseErrorKind::DuplicateKey => write!(f, "duplicate object key at byte {}", self.offset),
+            CanonParseErrorKind::TooDeep => write!(f, "nesting deeper than {MAX_CANON_DEPTH} at byte {}", self.offset),
+            CanonParseErrorKind::BadMagic => write!(f, "missing v2 header at byte {}", self.offset),
+            CanonParseErrorKind::UnknownTag(b) => write!(f, "unknown v2 tag 0x{b:02x} at byte {}", self.offset),
+            CanonParseErrorKind::NonShortestHead => write!(f, "CBOR head not in shortest form at byte {}", self.offset),
+            CanonParseErrorKind::NonCanonicalNumber => write!(f, "non-canonical rational or decimal at byte {}", self.offset),
+        }
+    }
+}
+
+impl std::error::Error for CanonParseError {}
+
+/// Strict inverse of `canon_bytes`: accepts exactly the bytes `write_canon` can produce.
+///
+/// The v1 encoding does not record integer width, so non-negative integers
+/// come back as `Canon::U64` and negative ones as `Canon::I64`. Nor does it tag
+/// exact numbers: `Canon::Rat` and `Canon::Dec` come back in their object forms
+/// (see `Canon::exact_from_v1_compat`).
+/// `canon_bytes(&parse_canon(b)?) == b` holds for every accepted input.
+pub fn parse_canon(bytes: &[u8]) -> Result<Canon, CanonParseError> {
+    let mut p = Parser { bytes, pos: 0 };
+    let v = p.value(0)?;
+    if p.pos != bytes.len() {
+        return Err(p.err(CanonParseErrorKind::TrailingBytes));
+    }
+    Ok(v)
+}
+
+/// Strict inverse of `canon_bytes_with(_, CanonMode::V2)`, including the header.
+/// Unlike v1, integer width survives: `parse_canon_v2(b)` returns exactly the value encoded.
+pub fn parse_canon_v2(bytes: &[u8]) -> Result<Canon, CanonParseError> {
+    if !bytes.starts_with(CANON_V2_MAGIC) {
+        return Err(CanonParseError { offset: 0, kind: CanonParseErrorKind::BadMagic });
+    }
+    let mut p = Parser { bytes, pos: CANON_V2_MAGIC.len() };
+    let v = p.v2_value(0)?;
+    if p.pos != bytes.len() {
+        return Err(p.err(CanonParseErrorKind::TrailingBytes));
+    }
+    Ok(v)
+}
+
+/// Parse either encoding, dispatching on the v2 header. Existing v1 bytes stay readable.
+pub fn parse_canon_versioned(bytes: &[u8]) -> Result<(CanonMode, Canon), CanonParseError> {
+    if bytes.starts_with(CANON_V2_MAGIC) {
+        parse_canon_v2(bytes).map(|v| (CanonMode::V2, v))
+    } else {
+        parse_canon(bytes).map(|v| (CanonMode::V1, v))
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn err(&self, kind: CanonParseErrorKind) -> CanonParseError {
+        CanonParseError { offset: self.pos, kind }
+    }
+
+    fn peek(&self) -> Result<u8, CanonParseError> {
+        self.bytes.get(self.pos).copied().ok_or_else(|| self.err(CanonParseErrorKind::UnexpectedEnd))
+    }
+
+    fn expect(&mut self, b: u8) -> Result<(), CanonParseError> {
+        let got = self.peek()?;
+        if got != b {
+            return Err(self.err(CanonParseErrorKind::UnexpectedByte(got)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, lit: &[u8], v: Canon) -> Result<Canon, CanonParseError> {
+        for &b in lit {
+            self.expect(b)?;
+        }
+        Ok(v)
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CanonParseError> {
+        if self.bytes.len() - self.pos < n {
+            return Err(CanonParseError { offset: self.bytes.len(), kind: CanonParseErrorKind::UnexpectedEnd });
+        }
+        let s = &self.bytes[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(s)
+    }
+
+    fn take_u64(&mut self) -> Result<u64, CanonParseError> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn v2_len(&mut self) -> Result<usize, CanonParseError> {
+        let offset = self.pos;
+        let n = self.take_u64()?;
+        // Every element takes at least one byte, so a count past the end is truncated input.
+        if n > (self.bytes.len() - self.pos) as u64 {
+            return Err(CanonParseError { offset, kind: CanonParseErrorKind::UnexpectedEnd });
+        }
+        Ok(n as usize)
+    }
+
+    fn v2_str(&mut self) -> Result<String, CanonParseError> {
+        let n = self.v2_len()?;
+        let start = self.pos;
+        let raw = self.take(n)?;
+        String::from_utf8(raw.to_vec()).map_err(|_| CanonParseError { offset: start, kind: CanonParseErrorKind::InvalidUtf8 })
+    }
+
+    fn v2_value(&mut self, depth: usize) -> Result<Canon, CanonParseError> {
+        let tag = self.peek()?;
+        if (tag == v2_tag::ARR || tag == v2_tag::OBJ) && depth + 1 > MAX_CANON_DEPTH {
+            return Err(self.err(CanonParseErrorKind::TooDeep));
+        }
+        self.pos += 1;
+        match tag {
+            v2_tag::NULL => Ok(Canon::Null),
+            v2_tag::FALSE => Ok(Canon::Bool(false)),
+            v2_tag::TRUE => Ok(Canon::Bool(true)),
+            v2_tag::I64 => Ok(Canon::I64(self.take_u64()? as i64)),
+            v2_tag::U64 => Ok(Canon::U64(self.take_u64()?)),
+            v2_tag::RAT => {
+                let start = self.pos - 1;
+                let num = self.take_u64()? as i64;
+                let den = self.take_u64()?;
+                Rational::from_parts(num, den)
+                    .map(Canon::Rat)
+                    .ok_or(CanonParseError { offset: start, kind: CanonParseErrorKind::NonCanonicalNumber })
+            }
+            v2_tag::DEC => {
+                let start = self.pos - 1;
+                let scale = self.take(1)?[0];
+                let mantissa = self.take_u64()? as i64;
+                if scale > MAX_DECIMAL_SCALE {
+                    return Err(CanonParseError { offset: start, kind: CanonParseErrorKind::NonCanonicalNumber });
+                }
+                Ok(Canon::Dec(Decimal::new(mantissa, scale)))
+            }
+            v2_tag::STR => Ok(Canon::Str(self.v2_str()?)),
+            v2_tag::ARR => {
+                let n = self.v2_len()?;
+                let mut xs = Vec::with_capacity(n);
+                for _ in 0..n {
+                    xs.push(self.v2_value(depth + 1)?);
+                }
+                Ok(Canon::Arr(xs))
+            }
+            v2_tag::OBJ => {
+                let n = self.v2_len()?;
+                let mut map = BTreeMap::new();
+                let mut prev: Option<String> = None;
+                for _ in 0..n {
+                    let key_offset = self.pos;
+                    let key = self.v2_str()?;
+                    if let Some(p) = &prev {
+                        if *p == key {
+                            return Err(CanonParseError { offset: key_offset, kind: CanonParseErrorKind::DuplicateKey });
+                        }
+                        if *p > key {
+                            return Err(CanonParseError { offset: key_offset, kind: CanonParseErrorKind::UnsortedKey });
+                        }
+                    }
+                    let val = self.v2_value(depth + 1)?;
+                    prev = Some(key.clone());
+                    map.insert(key, val);
+                }
+                Ok(Canon::Obj(map))
+            }
+            _ => {
+                self.pos -= 1;
+                Err(self.err(CanonParseErrorKind::UnknownTag(tag)))
+            }
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Canon, CanonParseError> {
+        match self.peek()? {
+            b'n' => self.literal(b"null", Canon::Null),
+            b't' => self.literal(b"true", Canon::Bool(true)),
+            b'f' => self.literal(b"false", Canon::Bool(false)),
+            b'"' => Ok(Canon::Str(self.string()?)),
+            b'-' | b'0'..=b'9' => self.integer(),
+            b'[' => self.array(depth + 1),
+            b'{' => self.object(depth + 1),
+            b => Err(self.err(CanonParseErrorKind::UnexpectedByte(b))),
+        }
+    }
+
+    fn integer(&mut self) -> Result<Canon, CanonParseError> {
+        let start = self.pos;
+        let negative = self.peek()? == b'-';
+        if negative {
+            self.pos += 1;
+        }
+        let digits_start = self.pos;
+        while let Some(b'0'..=b'9') = self.bytes.get(self.pos) {
+            self.pos += 1;
+        }
+        let digits = &self.bytes[digits_start..self.pos];
+        if digits.is_empty() {
+            self.pos = digits_start;
+            return Err(match self.bytes.get(self.pos) {
+                Some(&b) => self.err(CanonParseErrorKind::UnexpectedByte(b)),
+                None => self.err(CanonParseErrorKind::UnexpectedEnd),
+            });
+        }
+        if digits.len() > 1 && digits[0] == b'0' {
+            return Err(CanonParseError { offset: digits_start, kind: CanonParseErrorKind::LeadingZero });
+        }
+        if negative && digits == b"0" {
+            return Err(CanonParseError { offset: start, kind: CanonParseErrorKind::NegativeZero });
+        }
+        // Digits are ASCII by construction.
+        let text = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap();
+        let overflow = CanonParseError { offset: start, kind: CanonParseErrorKind::IntegerOverflow };
+        if negative {
+            text.parse::<i64>().map(Canon::I64).map_err(|_| overflow)
+        } else {
+            text.parse::<u64>().map(Canon::U64).map_err(|_| overflow)
+        }
+    }
+
+    fn string(&mut self) -> Result<String, CanonParseError> {
+        self.expect(b'"')?;
+        let start = self.pos;
+        let mut buf = Vec::new();
+        loop {
+            let b = self.peek()?;
+            match b {
+                b'"' => break,
+                b'\\' => {
+                    self.pos += 1;
+                    let e = self.peek()?;
+                    let decoded = match e {
+                        b'\\' => b'\\',
+                        b'"' => b'"',
+                        b'n' => b'\n',
+                        b'r' => b'\r',
+                        b't' => b'\t',
+                        _ => return Err(self.err(CanonParseErrorKind::UnknownEscape(e))),
+                    };
+                    buf.push(decoded);
+                }
+                b'\n' | b'\r' | b'\t' => return Err(self.err(CanonParseErrorKind::UnescapedByte(b))),
+                _ => buf.push(b),
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+        String::from_utf8(buf).map_err(|_| CanonParseError { offset: start, kind: CanonParseErrorKind::InvalidUtf8 })
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Canon, CanonParseError> {
+        if depth > MAX_CANON_DEPTH {
+            return Err(self.err(CanonParseErrorKind::TooDeep));
+        }
+        self.expect(b'[')?;
+        let mut xs = Vec::new();
+        if self.peek()? == b']' {
+            self.pos += 1;
+            return Ok(Canon::Arr(xs));
+        }
+        loop {
+            xs.push(self.value(depth)?);
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b']' => {
+                    self.pos += 1;
+                    return Ok(Canon::Arr(xs));
+                }
+                b => return Err(self.err(CanonParseErrorKind::UnexpectedByte(b))),
+            }
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Canon, CanonParseError> {
+        if depth > MAX_CANON_DEPTH {
+            return Err(self.err(CanonParseErrorKind::TooDeep));
+        }
+        self.expect(b'{')?;
+        let mut map = BTreeMap::new();
+        if self.peek()? == b'}' {
+            self.pos += 1;
+            return Ok(Canon::Obj(map));
+        }
+        let mut prev: Option<String> = None;
+        loop {
+            let key_offset = self.pos;
+            let key = self.string()?;
+            if let Some(p) = &prev {
+                if *p == key {
+                    return Err(CanonParseError { offset: key_offset, kind: CanonParseErrorKind::DuplicateKey });
+                }
+                if *p > key {
+                    return Err(CanonParseError { offset: key_offset, kind: CanonParseErrorKind::UnsortedKey });
+                }
+            }
+            self.expect(b':')?;
+            let val = self.value(depth)?;
+            prev = Some(key.clone());
+            map.insert(key, val);
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b'}' => {
+                    self.pos += 1;
+                    return Ok(Canon::Obj(map));
+                }
+                b => return Err(self.err(CanonParseErrorKind::UnexpectedByte(b))),
+            }
+        }
+    }
+}
@@ -0,0 +1,497 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::bundle::{BundleError, CertBundle};
+use crate::canon::{Canon, canon_bytes};
+use crate::convert::{FromCanon, FromCanonError, ToCanon, obj_field};
+use crate::serde_canon::to_canon_value;
+use crate::sign::{SignatureError, SignedSubject, TrustStore};
+
+pub const POLICY_FORMAT_V1: &str = "collapse/policy/v1";
+
+/// What a payload value must satisfy. Values compare by their v1 bytes, so
+/// `5` in a policy matches a payload's `5` whatever its integer width.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Condition {
+    Eq(Canon),
+    In(Vec<Canon>),
+    /// Inclusive bound on an integer value.
+    Min(i64),
+    Max(i64),
+}
+
+impl Condition {
+    const KEYS: [&'static str; 4] = ["eq", "in", "max", "min"];
+
+    fn key(&self) -> &'static str {
+        match self {
+            Condition::Eq(_) => "eq",
+            Condition::In(_) => "in",
+            Condition::Min(_) => "min",
+            Condition::Max(_) => "max",
+        }
+    }
+
+    fn value(&self) -> Canon {
+        match self {
+            Condition::Eq(v) => v.clone(),
+            Condition::In(vs) => Canon::Arr(vs.clone()),
+            Condition::Min(n) | Condition::Max(n) => n.to_canon(),
+        }
+    }
+
+    fn from_key(key: &str, v: &Canon) -> Result<Self, FromCanonError> {
+        let int = |v: &Canon| i64::from_canon(v).map_err(|e| e.at(key));
+        Ok(match key {
+            "eq" => Condition::Eq(v.clone()),
+            "in" => match v {
+                Canon::Arr(vs) => Condition::In(vs.clone()),
+                _ => return Err(FromCanonError::expected("array", v).at(key)),
+            },
+            "min" => Condition::Min(int(v)?),
+            _ => Condition::Max(int(v)?),
+        })
+    }
+
+    /// Why `v` fails this condition, if it does.
+    fn check(&self, v: &Canon) -> Option<String> {
+        let same = |a: &Canon| canon_bytes(a) == canon_bytes(v);
+        let int = match v {
+            Canon::I64(n) => Some(*n as i128),
+            Canon::U64(n) => Some(*n as i128),
+            _ => None,
+        };
+        match self {
+            Condition::Eq(want) if !same(want) => Some(format!("{} is not {}", text(v), text(want))),
+            Condition::In(set) if !set.iter().any(same) => {
+                Some(format!("{} is not one of {}", text(v), text(&Canon::Arr(set.clone()))))
+            }
+            Condition::Min(_) | Condition::Max(_) if int.is_none() => Some(format!("{} is not an integer", text(v))),
+            Condition::Min(n) if int < Some(*n as i128) => Some(format!("{} is below {n}", text(v))),
+            Condition::Max(n) if int > Some(*n as i128) => Some(format!("{} is above {n}", text(v))),
+            _ => None,
+        }
+    }
+}
+
+fn text(v: &Canon) -> String {
+    String::from_utf8_lossy(&canon_bytes(v)).into_owned()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    /// `[*]`: every element of an array.
+    All,
+}
+
+/// A location in a payload, written like the paths in `CanonDiff` and
+/// `FromCanonError` (`predicates[0].kind`), plus `[*]` for every element.
+/// Keys are limited to ASCII letters, digits and `_`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PayloadPath {
+    text: String,
+    segments: Vec<Segment>,
+}
+
+impl PayloadPath {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let bad = || format!("invalid payload path {text:?}");
+        let mut segments = Vec::new();
+        for (i, part) in text.split('.').enumerate() {
+            let (key, mut rest) = part.split_at(part.find('[').unwrap_or(part.len()));
+            let plain = key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_');
+            if !plain || (key.is_empty() && (i > 0 || rest.is_empty())) {
+                return Err(bad());
+            }
+            if !key.is_empty() {
+                segments.push(Segment::Key(key.to_string()));
+            }
+            while !rest.is_empty() {
+                let end = rest.find(']').ok_or_else(bad)?;
+                segments.push(match &rest[1..end] {
+                    "*" => Segment::All,
+                    n => Segment::Index(n.parse().map_err(|_| bad())?),
+                });
+                rest = &rest[end + 1..];
+                if !rest.is_empty() && !rest.starts_with('[') {
+                    return Err(bad());
+                }
+            }
+        }
+        Ok(Self { text: text.to_string(), segments })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Every concrete path the pattern names in `v`, with the value there or
+    /// `None` where the payload stops short of it.
+    fn resolve<'a>(&self, v: &'a Canon) -> Vec<(String, Option<&'a Canon>)> {
+        let mut out = vec![(String::new(), Some(v))];
+        for seg in &self.segments {
+            let mut next = Vec::new();
+            for (path, v) in out {
+                match (seg, v) {
+                    (_, None) => next.push((path, None)),
+                    (Segment::Key(k), Some(v)) => {
+                        let p = if path.is_empty() { k.clone() } else { format!("{path}.{k}") };
+                        let found = match v {
+                            Canon::Obj(o) => o.get(k),
+                            _ => None,
+                        };
+                        next.push((p, found));
+                    }
+                    (Segment::Index(i), Some(v)) => {
+                        let found = match v {
+                            Canon::Arr(xs) => xs.get(*i),
+                            _ => None,
+                        };
+                        next.push((format!("{path}[{i}]"), found));
+                    }
+                    (Segment::All, Some(Canon::Arr(xs))) => {
+                        next.extend(xs.iter().enumerate().map(|(i, x)| (format!("{path}[{i}]"), Some(x))));
+                    }
+                    (Segment::All, Some(_)) => next.push((format!("{path}[*]"), None)),
+                }
+            }
+            out = next;
+        }
+        out
+    }
+}
+
+/// What one rule checks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleCheck {
+    /// `path` in the payload of every cert named `kernel`. A bundle without such a cert fails.
+    Payload { kernel: String, path: PayloadPath, condition: Condition },
+    /// Signatures by at least `threshold` distinct keys of `trust` over the
+    /// chain root, or over the `kernel` cert when one is named.
+    SignedBy { kernel: Option<String>, trust: TrustStore, threshold: usize },
+    /// The chain has an item named `kernel`.
+    ChainIncludes { kernel: String },
+}
+
+impl RuleCheck {
+    /// Fails, at `path`, if `path` does not parse.
+    pub fn payload(kernel: &str, path: &str, condition: Condition) -> Result<Self, FromCanonError> {
+        let path = PayloadPath::parse(path).map_err(|e| FromCanonError::new(e).at("path"))?;
+        Ok(RuleCheck::Payload { kernel: kernel.to_string(), path, condition })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyRule {
+    pub id: String,
+    pub check: RuleCheck,
+}
+
+impl PolicyRule {
+    /// A flat object: `id` plus `kernel`/`path` and one of `eq`, `in`, `min`,
+    /// `max`; or `signed_by` (`{hex key: name}`), `threshold` and optionally
+    /// `kernel`; or `chain_includes`.
+    pub fn to_canon(&self) -> Canon {
+        let mut obj = BTreeMap::new();
+        obj.insert("id".to_string(), Canon::Str(self.id.clone()));
+        match &self.check {
+            RuleCheck::Payload { kernel, path, condition } => {
+                obj.insert("kernel".to_string(), Canon::Str(kernel.clone()));
+                obj.insert("path".to_string(), Canon::Str(path.as_str().to_string()));
+                obj.insert(condition.key().to_string(), condition.value());
+            }
+            RuleCheck::SignedBy { kernel, trust, threshold } => {
+                if let Some(k) = kernel {
+                    obj.insert("kernel".to_string(), Canon::Str(k.clone()));
+                }
+                let keys = trust.iter().map(|(k, n)| (k.to_string(), Canon::Str(n.to_string()))).collect();
+                obj.insert("signed_by".to_string(), Canon::Obj(keys));
+                obj.insert("threshold".to_string(), Canon::U64(*threshold as u64));
+            }
+            RuleCheck::ChainIncludes { kernel } => {
+                obj.insert("chain_includes".to_string(), Canon::Str(kernel.clone()));
+            }
+        }
+        Canon::Obj(obj)
+    }
+
+    /// Unknown keys are rejected, so a misspelt condition cannot pass silently.
+    pub fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        let Canon::Obj(obj) = v else {
+            return Err(FromCanonError::expected("object", v));
+        };
+        let id: String = obj_field(obj, "id")?;
+        let (check, allowed): (RuleCheck, &[&str]) = if obj.contains_key("chain_includes") {
+            (RuleCheck::ChainIncludes { kernel: obj_field(obj, "chain_includes")? }, &["chain_includes"])
+        } else if obj.contains_key("signed_by") {
+            let keys: BTreeMap<String, String> = obj_field(obj, "signed_by")?;
+            let mut trust = TrustStore::new();
+            for (key, name) in keys {
+                let public_key = key.parse().map_err(|e: String| FromCanonError::new(e).at("signed_by"))?;
+                trust.add(&name, public_key);
+            }
+            let threshold: u64 = obj_field(obj, "threshold")?;
+            let check = RuleCheck::SignedBy { kernel: obj_field(obj, "kernel")?, trust, threshold: threshold as usize };
+            (check, &["kernel", "signed_by", "threshold"])
+        } else {
+            let conds: Vec<&str> = Condition::KEYS.into_iter().filter(|k| obj.contains_key(*k)).collect();
+            let [key] = conds[..] else {
+                return Err(FromCanonError::new("expected exactly one of eq, in, min, max"));
+            };
+            let text: String = obj_field(obj, "path")?;
+            let path = PayloadPath::parse(&text).map_err(|e| FromCanonError::new(e).at("path"))?;
+            let condition = Condition::from_key(key, &obj[key])?;
+            (RuleCheck::Payload { kernel: obj_field(obj, "kernel")?, path, condition }, &["kernel", "path", key])
+        };
+        if let Some(k) = obj.keys().find(|k| *k != "id" && !allowed.contains(&k.as_str())) {
+            return Err(FromCanonError::new(format!("unexpected key {k:?} for this rule")));
+        }
+        Ok(Self { id, check })
+    }
+}
+
+/// Rules a bundle must satisfy beyond verifying. Rules keep their order, and
+/// ids are unique so a report can name the rule that failed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Policy {
+    rules: Vec<PolicyRule>,
+}
+
+impl Policy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fails if a rule with this `id` already exists.
+    pub fn rule(&mut self, id: &str, check: RuleCheck) -> Result<&mut Self, FromCanonError> {
+        if self.rules.iter().any(|r| r.id == id) {
+            return Err(FromCanonError::new(format!("duplicate rule id {id:?}")));
+        }
+        self.rules.push(PolicyRule { id: id.to_string(), check });
+        Ok(self)
+    }
+
+    pub fn rules(&self) -> &[PolicyRule] {
+        &self.rules
+    }
+
+    pub fn to_canon(&self) -> Canon {
+        let mut obj = BTreeMap::new();
+        obj.insert("format".to_string(), Canon::Str(POLICY_FORMAT_V1.to_string()));
+        obj.insert("rules".to_string(), Canon::Arr(self.rules.iter().map(PolicyRule::to_canon).collect()));
+        Canon::Obj(obj)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        canon_bytes(&self.to_canon())
+    }
+
+    /// Rejects unknown formats and duplicate rule ids.
+    pub fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        let Canon::Obj(obj) = v else {
+            return Err(FromCanonError::expected("object", v));
+        };
+        let format: String = obj_field(obj, "format")?;
+        if format != POLICY_FORMAT_V1 {
+            return Err(FromCanonError::new(format!("unsupported policy format {format:?}")).at("format"));
+        }
+        let raw: Vec<Canon> = obj_field(obj, "rules")?;
+        let mut ids = BTreeSet::new();
+        let mut rules = Vec::with_capacity(raw.len());
+        for (i, v) in raw.iter().enumerate() {
+            let r = PolicyRule::from_canon(v).map_err(|e| e.at(&format!("[{i}]")).at("rules"))?;
+            if !ids.insert(r.id.clone()) {
+                let msg = format!("duplicate rule id {:?}", r.id);
+                return Err(FromCanonError::new(msg).at(&format!("[{i}]")).at("rules"));
+            }
+            rules.push(r);
+        }
+        Ok(Self { rules })
+    }
+
+    /// Any JSON layout: policies are written by hand, so only the parsed
+    /// values need to be canonical.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PolicyLoadError> {
+        let json: serde_json::Value =
+            serde_json::from_slice(bytes).map_err(|e| PolicyLoadError::Parse(e.to_string()))?;
+        let canon = to_canon_value(&json).map_err(|e| PolicyLoadError::Parse(e.to_string()))?;
+        Ok(Self::from_canon(&canon)?)
+    }
+
+    /// `bundle.verify()`, then every rule against its payloads, chain and
+    /// signatures. Rules are only evaluated over a bundle that verifies.
+    pub fn evaluate(&self, bundle: &CertBundle) -> Result<PolicyReport, BundleError> {
+        bundle.verify()?;
+        let rules = self
+            .rules
+            .iter()
+            .map(|r| RuleOutcome { id: r.id.clone(), violations: evaluate_rule(&r.check, bundle) })
+            .collect();
+        Ok(PolicyReport { rules })
+    }
+}
+
+fn evaluate_rule(check: &RuleCheck, bundle: &CertBundle) -> Vec<PolicyViolation> {
+    let violation = |kernel: Option<&String>, path: &str, reason: String| PolicyViolation {
+        kernel: kernel.cloned(),
+        path: path.to_string(),
+        reason,
+    };
+    match check {
+        RuleCheck::Payload { kernel, path, condition } => {
+            let certs: Vec<_> = bundle.certs.iter().filter(|c| c.kernel_name == *kernel).collect();
+            if certs.is_empty() {
+                return vec![violation(Some(kernel), "", format!("no {kernel} cert in the bundle"))];
+            }
+            let mut out = Vec::new();
+            for c in certs {
+                for (p, v) in path.resolve(&c.payload) {
+                    let reason = match v {
+                        None => Some("missing".to_string()),
+                        Some(v) => condition.check(v),
+                    };
+                    out.extend(reason.map(|r| violation(Some(kernel), &p, r)));
+                }
+            }
+            out
+        }
+        RuleCheck::SignedBy { kernel, trust, threshold } => {
+            let subject = match kernel {
+                None => SignedSubject::of_chain(&bundle.chain, &bundle.certs),
+                Some(k) => match bundle.cert(k) {
+                    Some(c) => SignedSubject::of_cert(c),
+                    None => return vec![violation(Some(k), "", format!("no {k} cert in the bundle"))],
+                },
+            };
+            let what = kernel.as_deref().unwrap_or("chain root");
+            match trust.check(&subject, &bundle.signatures, *threshold) {
+                Ok(_) => Vec::new(),
+                Err(SignatureError::BelowThreshold { trusted, .. }) => {
+                    let n = trusted.len();
+                    let reason = format!("{what} is signed by {n} of the {threshold} trusted keys required");
+                    vec![violation(kernel.as_ref(), "", reason)]
+                }
+                Err(e) => vec![violation(kernel.as_ref(), "", format!("{what}: {e}"))],
+            }
+        }
+        RuleCheck::ChainIncludes { kernel } => {
+            if bundle.chain.items.iter().any(|it| it.name == *kernel) {
+                return Vec::new();
+            }
+            vec![violation(Some(kernel), "", format!("chain has no {kernel} item"))]
+        }
+    }
+}
+
+/// One place a rule failed. `path` is empty for rules about the bundle as a
+/// whole (a missing cert, a missing signature).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyViolation {
+    pub kernel: Option<String>,
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleOutcome {
+    pub id: String,
+    pub violations: Vec<PolicyViolation>,
+}
+
+impl RuleOutcome {
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Outcome of every rule, in policy order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyReport {
+    pub rules: Vec<RuleOutcome>,
+}
+
+impl PolicyReport {
+    pub fn passed(&self) -> bool {
+        self.rules.iter().all(RuleOutcome::passed)
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &RuleOutcome> {
+        self.rules.iter().filter(|r| !r.passed())
+    }
+
+    /// `{passed, rules: [{id, passed, violations: [{kernel?, path, reason}]}]}`.
+    pub fn to_canon(&self) -> Canon {
+        let rules = self
+            .rules
+            .iter()
+            .map(|r| {
+                let violations = r
+                    .violations
+                    .iter()
+                    .map(|v| {
+                        let mut obj = BTreeMap::new();
+                        if let Some(k) = &v.kernel {
+                            obj.insert("kernel".to_string(), Canon::Str(k.clone()));
+                        }
+                        obj.insert("path".to_string(), Canon::Str(v.path.clone()));
+                        obj.insert("reason".to_string(), Canon::Str(v.reason.clone()));
+                        Canon::Obj(obj)
+                    })
+                    .collect();
+                let mut obj = BTreeMap::new();
+                obj.insert("id".to_string(), Canon::Str(r.id.clone()));
+                obj.insert("passed".to_string(), Canon::Bool(r.passed()));
+                obj.insert("violations".to_string(), Canon::Arr(violations));
+                Canon::Obj(obj)
+            })
+            .collect();
+        let mut obj = BTreeMap::new();
+        obj.insert("passed".to_string(), Canon::Bool(self.passed()));
+        obj.insert("rules".to_string(), Canon::Arr(rules));
+        Canon::Obj(obj)
+    }
+}
+
+/// One line per rule, then one indented line per violation.
+impl fmt::Display for PolicyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for r in &self.rules {
+            writeln!(f, "{} {}", if r.passed() { "pass" } else { "FAIL" }, r.id)?;
+            for v in &r.violations {
+                let path = if v.path.is_empty() { "$" } else { &v.path };
+                match &v.kernel {
+                    Some(k) => writeln!(f, "    {k} {path}: {}", v.reason)?,
+                    None => writeln!(f, "    {path}: {}", v.reason)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why `Policy::from_bytes` failed.
+#[derive(Debug)]
+pub enum PolicyLoadError {
+    /// Not JSON, or JSON with a value `Canon` cannot hold (a float).
+    Parse(String),
+    Malformed(FromCanonError),
+}
+
+impl fmt::Display for PolicyLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyLoadError::Parse(e) => write!(f, "policy is not JSON: {e}"),
+            PolicyLoadError::Malformed(e) => write!(f, "malformed policy: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for PolicyLoadError {}
+
+impl From<FromCanonError> for PolicyLoadError {
+    fn from(e: FromCanonError) -> Self {
+        PolicyLoadError::Malformed(e)
+    }
+}
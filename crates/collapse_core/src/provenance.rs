@@ -0,0 +1,232 @@
+use std::collections::BTreeMap;
+
+use crate::bundle::{BundleError, CertBundle};
+use crate::canon::Canon;
+use crate::cert::{CertChain, CertItem, CertLoadError, KernelCert};
+use crate::convert::{FromCanonError, ToCanon, obj_field};
+use crate::diff::{CanonDiff, canon_diff};
+use crate::digest::{HashAlg, TaggedDigest};
+use crate::kernel::{Kernel, KernelError, KernelInputs};
+use crate::schema::{ObjectSchema, Schema, SchemaError};
+
+/// Kernel name of provenance certs, and of their `CertItem` in a chain.
+pub const PROVENANCE_KERNEL: &str = "provenance";
+pub const PROVENANCE_VERSION: &str = "1.0.0";
+
+/// The toolchain and dependency set a run was computed with. None of it is
+/// semantic, but any of it can change what the semantic kernels compute.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Provenance {
+    /// Every `(name, version)` in `Cargo.lock`, sorted; a crate can appear at several versions.
+    pub crates: Vec<(String, String)>,
+    pub cargo_lock_hash: TaggedDigest,
+    /// `rustc -V` output.
+    pub rustc_version: String,
+    pub target: String,
+    /// Cargo features enabled on `collapse_core`, sorted.
+    pub features: Vec<String>,
+}
+
+pub fn provenance_schema() -> Schema {
+    let krate = ObjectSchema::new().required("name", Schema::Str).required("version", Schema::Str).build();
+    ObjectSchema::new()
+        .required("cargo_lock_hash", Schema::Digest)
+        .required("crates", Schema::array_of(krate))
+        .required("features", Schema::array_of(Schema::Str))
+        .required("rustc_version", Schema::Str)
+        .required("target", Schema::Str)
+        .build()
+}
+
+/// `(name, version)` of every `[[package]]` in a `Cargo.lock`. Only those two
+/// keys are read, so no TOML parser is needed.
+fn lock_packages(lock: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    let mut name = None;
+    for line in lock.lines().map(str::trim) {
+        if line == "[[package]]" {
+            name = None;
+        } else if let Some(v) = line.strip_prefix("name = ") {
+            name = Some(v.trim_matches('"').to_string());
+        } else if let (Some(v), Some(n)) = (line.strip_prefix("version = "), &name) {
+            out.push((n.clone(), v.trim_matches('"').to_string()));
+            name = None;
+        }
+    }
+    out.sort();
+    out.dedup();
+    out
+}
+
+impl Provenance {
+    /// `lock` with the compiler, target and features this binary was built with.
+    /// Only the caller knows its own lock file, e.g. `include_bytes!("../../Cargo.lock")`.
+    pub fn current(lock: &[u8]) -> Self {
+        let features = env!("COLLAPSE_FEATURES").split(',').filter(|f| !f.is_empty()).map(str::to_string);
+        Self::from_lock(lock, env!("COLLAPSE_RUSTC_VERSION"), env!("COLLAPSE_TARGET"), features.collect())
+    }
+
+    pub fn from_lock(lock: &[u8], rustc_version: &str, target: &str, mut features: Vec<String>) -> Self {
+        features.sort();
+        features.dedup();
+        Self {
+            crates: lock_packages(&String::from_utf8_lossy(lock)),
+            cargo_lock_hash: HashAlg::Sha256.hash(lock),
+            rustc_version: rustc_version.to_string(),
+            target: target.to_string(),
+            features,
+        }
+    }
+
+    /// Version(s) of `name` in the lock file.
+    pub fn crate_versions(&self, name: &str) -> Vec<&str> {
+        self.crates.iter().filter(|(n, _)| n == name).map(|(_, v)| v.as_str()).collect()
+    }
+
+    pub fn to_canon(&self) -> Canon {
+        let crates = self
+            .crates
+            .iter()
+            .map(|(n, v)| {
+                let mut obj = BTreeMap::new();
+                obj.insert("name".to_string(), Canon::Str(n.clone()));
+                obj.insert("version".to_string(), Canon::Str(v.clone()));
+                Canon::Obj(obj)
+            })
+            .collect();
+        let mut obj = BTreeMap::new();
+        obj.insert("cargo_lock_hash".to_string(), self.cargo_lock_hash.to_canon());
+        obj.insert("crates".to_string(), Canon::Arr(crates));
+        obj.insert("features".to_string(), self.features.to_canon());
+        obj.insert("rustc_version".to_string(), Canon::Str(self.rustc_version.clone()));
+        obj.insert("target".to_string(), Canon::Str(self.target.clone()));
+        Canon::Obj(obj)
+    }
+
+    pub fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        let Canon::Obj(obj) = v else {
+            return Err(FromCanonError::expected("object", v));
+        };
+        let raw: Vec<BTreeMap<String, String>> = obj_field(obj, "crates")?;
+        let crates = raw
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut c)| match (c.remove("name"), c.remove("version")) {
+                (Some(n), Some(v)) => Ok((n, v)),
+                _ => Err(FromCanonError::new("expected name and version").at(&format!("[{i}]")).at("crates")),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            crates,
+            cargo_lock_hash: obj_field(obj, "cargo_lock_hash")?,
+            rustc_version: obj_field(obj, "rustc_version")?,
+            target: obj_field(obj, "target")?,
+            features: obj_field(obj, "features")?,
+        })
+    }
+
+    /// `provenance@1.0.0`.
+    pub fn to_kernel_cert(&self) -> Result<KernelCert, Vec<SchemaError>> {
+        self.cert()
+    }
+
+    /// What changed from `self` to `other`, e.g. `crates[17].version` or `features[0]`.
+    pub fn diff(&self, other: &Provenance) -> CanonDiff {
+        canon_diff(&self.to_canon(), &other.to_canon())
+    }
+}
+
+impl Kernel for Provenance {
+    const NAME: &'static str = PROVENANCE_KERNEL;
+    const VERSION: &'static str = PROVENANCE_VERSION;
+
+    fn payload(&self) -> Canon {
+        self.to_canon()
+    }
+
+    fn schema() -> Option<Schema> {
+        Some(provenance_schema())
+    }
+
+    /// Only the shape can be checked: the build it describes is not at hand.
+    fn verify(payload: &Canon, _inputs: &KernelInputs<'_>) -> Result<(), KernelError> {
+        let p = Provenance::from_canon(payload)?;
+        if !p.crates.windows(2).all(|w| w[0] < w[1]) {
+            return Err(KernelError::invalid("crates", "not sorted and distinct"));
+        }
+        if !p.features.windows(2).all(|w| w[0] < w[1]) {
+            return Err(KernelError::invalid("features", "not sorted and distinct"));
+        }
+        Ok(())
+    }
+}
+
+/// One chain item that differs between an expected and a recomputed chain.
+/// `None` means the item is missing on that side.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ItemDrift {
+    pub name: String,
+    pub expected: Option<TaggedDigest>,
+    pub found: Option<TaggedDigest>,
+}
+
+/// Differences between two chains, split by whether they change what was
+/// computed (`semantic`) or only what it was computed with (`provenance`).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ChainDrift {
+    pub semantic: Vec<ItemDrift>,
+    pub provenance: Vec<ItemDrift>,
+}
+
+impl ChainDrift {
+    pub fn is_empty(&self) -> bool {
+        self.semantic.is_empty() && self.provenance.is_empty()
+    }
+}
+
+/// Compares items by name. The provenance item is optional: a `found` chain
+/// may carry one the `expected` chain does not pin, and that is not drift.
+pub fn chain_drift(expected: &CertChain, found: &CertChain) -> ChainDrift {
+    let lookup =
+        |chain: &CertChain, name: &str| chain.items.iter().find(|it| it.name == name).map(|it| it.hash.clone());
+    let mut names: Vec<&str> = expected.items.iter().map(|it| it.name.as_str()).collect();
+    for it in &found.items {
+        if !names.contains(&it.name.as_str()) && it.name != PROVENANCE_KERNEL {
+            names.push(&it.name);
+        }
+    }
+    let mut drift = ChainDrift::default();
+    for name in names {
+        let (e, f) = (lookup(expected, name), lookup(found, name));
+        if e != f {
+            let item = ItemDrift { name: name.to_string(), expected: e, found: f };
+            if name == PROVENANCE_KERNEL {
+                drift.provenance.push(item);
+            } else {
+                drift.semantic.push(item);
+            }
+        }
+    }
+    drift
+}
+
+impl CertBundle {
+    /// Append a provenance cert and its chain item, unless the bundle already has one.
+    pub fn with_provenance(mut self, provenance: &Provenance) -> Result<Self, BundleError> {
+        if self.cert(PROVENANCE_KERNEL).is_some() {
+            return Err(BundleError::DuplicateCert { name: PROVENANCE_KERNEL.to_string() });
+        }
+        let index = self.certs.len();
+        let cert = provenance
+            .to_kernel_cert()
+            .map_err(|errs| BundleError::Cert { index, error: CertLoadError::Schema(errs) })?;
+        self.chain.push(CertItem::from_cert(&cert));
+        self.certs.push(cert);
+        Ok(self)
+    }
+
+    /// The recorded provenance, if the bundle carries a readable one.
+    pub fn provenance(&self) -> Option<Provenance> {
+        self.cert(PROVENANCE_KERNEL).and_then(|c| Provenance::from_canon(&c.payload).ok())
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::canon::{Canon, canon_bytes};
+use crate::cert::{CertChain, KernelCert};
+use crate::convert::{FromCanonError, ToCanon, obj_field};
+use crate::digest::{HashAlg, TaggedDigest};
+use crate::parse::{CanonParseError, parse_canon};
+
+pub const REVOCATION_FORMAT_V1: &str = "collapse/revocations/v1";
+
+/// What a revocation names: a kernel and the payload it computed. Unlike
+/// `kernel_hash`, it does not change under `with_hash_alg`, `with_hash_scheme`
+/// or `to_v2`; the last bumps the version, so the version is left out too.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CertId {
+    pub kernel_name: String,
+    /// SHA-256 of the payload's v1 bytes.
+    pub payload_digest: TaggedDigest,
+}
+
+impl CertId {
+    pub fn of(cert: &KernelCert) -> Self {
+        let payload_digest = HashAlg::Sha256.hash(&canon_bytes(&cert.payload));
+        Self { kernel_name: cert.kernel_name.clone(), payload_digest }
+    }
+}
+
+/// What one revocation applies to.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RevocationTarget {
+    /// A cert under any algorithm, hash scheme or version.
+    Cert(CertId),
+    /// One exact `kernel_hash`. This is all a `CertChain` item records, so it
+    /// is the only kind of entry a bare chain can be checked against.
+    KernelHash(TaggedDigest),
+}
+
+impl fmt::Display for RevocationTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RevocationTarget::Cert(c) => write!(f, "{} {}", c.kernel_name, c.payload_digest),
+            RevocationTarget::KernelHash(h) => write!(f, "{h}"),
+        }
+    }
+}
+
+/// Why one cert is no longer trusted, and the hash of what replaces it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Revocation {
+    pub target: RevocationTarget,
+    pub reason: String,
+    pub superseded_by: Option<TaggedDigest>,
+}
+
+impl Revocation {
+    /// `{kernel_name, payload_digest, reason}` or `{kernel_hash, reason}`, plus
+    /// `superseded_by` when there is a replacement.
+    pub fn to_canon(&self) -> Canon {
+        let mut obj = BTreeMap::new();
+        match &self.target {
+            RevocationTarget::Cert(c) => {
+                obj.insert("kernel_name".to_string(), Canon::Str(c.kernel_name.clone()));
+                obj.insert("payload_digest".to_string(), c.payload_digest.to_canon());
+            }
+            RevocationTarget::KernelHash(h) => {
+                obj.insert("kernel_hash".to_string(), h.to_canon());
+            }
+        }
+        obj.insert("reason".to_string(), Canon::Str(self.reason.clone()));
+        if let Some(s) = &self.superseded_by {
+            obj.insert("superseded_by".to_string(), s.to_canon());
+        }
+        Canon::Obj(obj)
+    }
+
+    pub fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        let Canon::Obj(obj) = v else {
+            return Err(FromCanonError::expected("object", v));
+        };
+        let target = match obj_field::<Option<TaggedDigest>>(obj, "kernel_hash")? {
+            Some(_) if obj.contains_key("kernel_name") || obj.contains_key("payload_digest") => {
+                return Err(FromCanonError::new("names both a kernel_hash and a cert").at("kernel_hash"));
+            }
+            Some(h) => RevocationTarget::KernelHash(h),
+            None => RevocationTarget::Cert(CertId {
+                kernel_name: obj_field(obj, "kernel_name")?,
+                payload_digest: obj_field(obj, "payload_digest")?,
+            }),
+        };
+        Ok(Self {
+            target,
+            reason: obj_field(obj, "reason")?,
+            superseded_by: obj_field(obj, "superseded_by")?,
+        })
+    }
+}
+
+/// A bundle cert or chain item on a revocation list. `index` is its position
+/// in `certs` or `items`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RevokedItem {
+    pub index: usize,
+    pub name: String,
+    pub revocation: Revocation,
+}
+
+/// What bundle and chain verification do with a revoked cert.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RevocationPolicy {
+    /// Fail verification on the first revoked cert.
+    #[default]
+    Deny,
+    /// Verify as usual and report revoked certs to the caller.
+    Warn,
+}
+
+/// Revoked certs. Entries are keyed and written in `RevocationTarget` order
+/// (certs first, then kernel hashes), so the same set of revocations always has
+/// the same bytes and hash.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RevocationList {
+    entries: BTreeMap<RevocationTarget, Revocation>,
+}
+
+impl RevocationList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces the revocation for `cert`.
+    pub fn revoke(&mut self, cert: CertId, reason: &str, superseded_by: Option<TaggedDigest>) -> &mut Self {
+        self.insert(RevocationTarget::Cert(cert), reason, superseded_by)
+    }
+
+    /// Adds or replaces the revocation for one exact `kernel_hash`.
+    pub fn revoke_hash(
+        &mut self,
+        kernel_hash: TaggedDigest,
+        reason: &str,
+        superseded_by: Option<TaggedDigest>,
+    ) -> &mut Self {
+        self.insert(RevocationTarget::KernelHash(kernel_hash), reason, superseded_by)
+    }
+
+    fn insert(&mut self, target: RevocationTarget, reason: &str, superseded_by: Option<TaggedDigest>) -> &mut Self {
+        let r = Revocation { target: target.clone(), reason: reason.to_string(), superseded_by };
+        self.entries.insert(target, r);
+        self
+    }
+
+    pub fn get(&self, cert: &CertId) -> Option<&Revocation> {
+        self.entries.get(&RevocationTarget::Cert(cert.clone()))
+    }
+
+    pub fn get_hash(&self, kernel_hash: &TaggedDigest) -> Option<&Revocation> {
+        self.entries.get(&RevocationTarget::KernelHash(kernel_hash.clone()))
+    }
+
+    pub fn is_revoked(&self, cert: &CertId) -> bool {
+        self.get(cert).is_some()
+    }
+
+    pub fn is_hash_revoked(&self, kernel_hash: &TaggedDigest) -> bool {
+        self.get_hash(kernel_hash).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Revocation> {
+        self.entries.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The revoked ones among `certs`, by `CertId` or by `kernel_hash`, in order.
+    pub fn revoked_certs(&self, certs: &[KernelCert]) -> Vec<RevokedItem> {
+        certs
+            .iter()
+            .enumerate()
+            .filter_map(|(index, c)| {
+                let revocation = self.get(&CertId::of(c)).or_else(|| self.get_hash(&c.kernel_hash))?.clone();
+                Some(RevokedItem { index, name: c.kernel_name.clone(), revocation })
+            })
+            .collect()
+    }
+
+    /// The chain items whose hash is revoked, in order. Items carry no payload,
+    /// so `CertId` entries cannot match them.
+    pub fn revoked_items(&self, chain: &CertChain) -> Vec<RevokedItem> {
+        chain
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, it)| {
+                let revocation = self.get_hash(&it.hash)?.clone();
+                Some(RevokedItem { index, name: it.name.clone(), revocation })
+            })
+            .collect()
+    }
+
+    pub fn to_canon(&self) -> Canon {
+        let mut obj = BTreeMap::new();
+        obj.insert("format".to_string(), Canon::Str(REVOCATION_FORMAT_V1.to_string()));
+        obj.insert("revoked".to_string(), Canon::Arr(self.iter().map(Revocation::to_canon).collect()));
+        Canon::Obj(obj)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        canon_bytes(&self.to_canon())
+    }
+
+    /// `alg` over `to_bytes`; publish it so verifiers can pin the list they use.
+    pub fn digest(&self, alg: HashAlg) -> TaggedDigest {
+        alg.hash(&self.to_bytes())
+    }
+
+    /// Rejects unknown formats, and entries that are not in strictly increasing `RevocationTarget` order.
+    pub fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        let Canon::Obj(obj) = v else {
+            return Err(FromCanonError::expected("object", v));
+        };
+        let format: String = obj_field(obj, "format")?;
+        if format != REVOCATION_FORMAT_V1 {
+            return Err(FromCanonError::new(format!("unsupported revocation list format {format:?}")).at("format"));
+        }
+        let raw: Vec<Canon> = obj_field(obj, "revoked")?;
+        let mut list = Self::new();
+        for (i, v) in raw.iter().enumerate() {
+            let r = Revocation::from_canon(v).map_err(|e| e.at(&format!("[{i}]")).at("revoked"))?;
+            if list.entries.last_key_value().is_some_and(|(prev, _)| *prev >= r.target) {
+                let msg = format!("{} is duplicated or out of order", r.target);
+                return Err(FromCanonError::new(msg).at(&format!("[{i}]")).at("revoked"));
+            }
+            list.entries.insert(r.target.clone(), r);
+        }
+        Ok(list)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RevocationLoadError> {
+        Ok(Self::from_canon(&parse_canon(bytes)?)?)
+    }
+}
+
+/// Why `RevocationList::from_bytes` failed.
+#[derive(Debug)]
+pub enum RevocationLoadError {
+    Parse(CanonParseError),
+    Malformed(FromCanonError),
+}
+
+impl fmt::Display for RevocationLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RevocationLoadError::Parse(e) => write!(f, "revocation list is not canonical JSON: {e}"),
+            RevocationLoadError::Malformed(e) => write!(f, "malformed revocation list: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RevocationLoadError {}
+
+impl From<CanonParseError> for RevocationLoadError {
+    fn from(e: CanonParseError) -> Self {
+        RevocationLoadError::Parse(e)
+    }
+}
+
+impl From<FromCanonError> for RevocationLoadError {
+    fn from(e: FromCanonError) -> Self {
+        RevocationLoadError::Malformed(e)
+    }
+}
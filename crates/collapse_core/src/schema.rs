@@ -0,0 +1,354 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::canon::Canon;
+use crate::convert::canon_kind;
+use crate::digest::{HashAlg, TaggedDigest};
+use crate::exact::{Decimal, Rational};
+use crate::kernel::DepsFn;
+
+/// Shape of a `Canon` payload.
+///
+/// Integer schemas check range, not the `I64`/`U64` variant, since v1 bytes do not
+/// record width: `Schema::U64` accepts any non-negative integer, `Schema::I64` any
+/// integer that fits in `i64`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Schema {
+    Any,
+    Null,
+    Bool,
+    I64,
+    U64,
+    Str,
+    /// Lowercase hex string of exactly this many characters.
+    Hex(usize),
+    /// Bare SHA-256 hex or `alg:hex` for any `HashAlg` (see `TaggedDigest::parse_compat`).
+    Digest,
+    /// `Canon::Rat` or its `{den,num}` object form.
+    Rational,
+    /// `Canon::Dec` with exactly this scale, or its `{mantissa,scale}` object form.
+    Decimal(u8),
+    Nullable(Box<Schema>),
+    Array(Box<Schema>),
+    Object(ObjectSchema),
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ObjectSchema {
+    pub required: BTreeMap<String, Schema>,
+    pub optional: BTreeMap<String, Schema>,
+    pub allow_extra: bool,
+}
+
+impl ObjectSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn required(mut self, key: &str, s: Schema) -> Self {
+        self.required.insert(key.to_string(), s);
+        self
+    }
+
+    pub fn optional(mut self, key: &str, s: Schema) -> Self {
+        self.optional.insert(key.to_string(), s);
+        self
+    }
+
+    pub fn allow_extra(mut self) -> Self {
+        self.allow_extra = true;
+        self
+    }
+
+    pub fn build(self) -> Schema {
+        Schema::Object(self)
+    }
+}
+
+/// A payload location that failed validation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SchemaError {
+    pub path: String,
+    pub msg: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() { "$" } else { &self.path };
+        write!(f, "{path}: {}", self.msg)
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+fn join(base: &str, key: &str) -> String {
+    if base.is_empty() { key.to_string() } else { format!("{base}.{key}") }
+}
+
+impl Schema {
+    pub fn nullable(self) -> Schema {
+        Schema::Nullable(Box::new(self))
+    }
+
+    pub fn array_of(item: Schema) -> Schema {
+        Schema::Array(Box::new(item))
+    }
+
+    /// Checks `v` against this schema, collecting every violation (not just the first).
+    pub fn validate(&self, v: &Canon) -> Result<(), Vec<SchemaError>> {
+        let mut errs = Vec::new();
+        self.check(v, String::new(), &mut errs);
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+
+    fn check(&self, v: &Canon, path: String, errs: &mut Vec<SchemaError>) {
+        let mismatch = |errs: &mut Vec<SchemaError>, path: String, want: &str| {
+            errs.push(SchemaError { path, msg: format!("expected {want}, got {}", canon_kind(v)) });
+        };
+        match (self, v) {
+            (Schema::Any, _) | (Schema::Null, Canon::Null) | (Schema::Bool, Canon::Bool(_)) | (Schema::Str, Canon::Str(_)) => {}
+            (Schema::I64, Canon::I64(_)) | (Schema::U64, Canon::U64(_)) => {}
+            (Schema::I64, Canon::U64(n)) if *n <= i64::MAX as u64 => {}
+            (Schema::U64, Canon::I64(n)) if *n >= 0 => {}
+            (Schema::Hex(len), Canon::Str(s)) => {
+                if s.len() != *len || !s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+                    errs.push(SchemaError { path, msg: format!("expected {len} lowercase hex chars, got {s:?}") });
+                }
+            }
+            (Schema::Digest, Canon::Str(s)) => {
+                let lower = !s.bytes().any(|b| b.is_ascii_uppercase());
+                if !lower || TaggedDigest::parse_compat(s).is_err() {
+                    errs.push(SchemaError { path, msg: format!("expected digest (hex or alg:hex), got {s:?}") });
+                }
+            }
+            (Schema::Rational, Canon::Rat(_)) => {}
+            (Schema::Rational, Canon::Obj(_)) if Rational::from_obj(v).is_some() => {}
+            (Schema::Decimal(scale), Canon::Dec(_) | Canon::Obj(_)) => {
+                let d = match v {
+                    Canon::Dec(d) => Some(*d),
+                    _ => Decimal::from_obj(v),
+                };
+                match d {
+                    Some(d) if d.scale() == *scale => {}
+                    Some(d) => errs.push(SchemaError { path, msg: format!("expected decimal scale {scale}, got {}", d.scale()) }),
+                    None => mismatch(errs, path, "decimal"),
+                }
+            }
+            (Schema::Nullable(_), Canon::Null) => {}
+            (Schema::Nullable(inner), _) => inner.check(v, path, errs),
+            (Schema::Array(item), Canon::Arr(xs)) => {
+                for (i, x) in xs.iter().enumerate() {
+                    item.check(x, format!("{path}[{i}]"), errs);
+                }
+            }
+            (Schema::Object(o), Canon::Obj(map)) => {
+                for (k, s) in &o.required {
+                    match map.get(k) {
+                        Some(x) => s.check(x, join(&path, k), errs),
+                        None => errs.push(SchemaError { path: join(&path, k), msg: "missing required key".to_string() }),
+                    }
+                }
+                for (k, x) in map {
+                    if o.required.contains_key(k) {
+                        continue;
+                    }
+                    match o.optional.get(k) {
+                        Some(s) => s.check(x, join(&path, k), errs),
+                        None if o.allow_extra => {}
+                        None => errs.push(SchemaError { path: join(&path, k), msg: "unexpected key".to_string() }),
+                    }
+                }
+            }
+            (Schema::Null, _) => mismatch(errs, path, "null"),
+            (Schema::Bool, _) => mismatch(errs, path, "bool"),
+            (Schema::I64, _) => mismatch(errs, path, "i64 integer"),
+            (Schema::U64, _) => mismatch(errs, path, "non-negative integer"),
+            (Schema::Str, _) | (Schema::Hex(_), _) | (Schema::Digest, _) => mismatch(errs, path, "string"),
+            (Schema::Rational, _) => mismatch(errs, path, "rational"),
+            (Schema::Decimal(_), _) => mismatch(errs, path, "decimal"),
+            (Schema::Array(_), _) => mismatch(errs, path, "array"),
+            (Schema::Object(_), _) => mismatch(errs, path, "object"),
+        }
+    }
+
+    /// JSON Schema (draft 2020-12) for this shape, as a `Canon` value ready for `canon_bytes`.
+    pub fn to_json_schema(&self) -> Canon {
+        let ty = |t: &str| {
+            let mut o = BTreeMap::new();
+            o.insert("type".to_string(), Canon::Str(t.to_string()));
+            o
+        };
+        let obj = match self {
+            Schema::Any => BTreeMap::new(),
+            Schema::Null => ty("null"),
+            Schema::Bool => ty("boolean"),
+            Schema::I64 => {
+                let mut o = ty("integer");
+                o.insert("minimum".to_string(), Canon::I64(i64::MIN));
+                o.insert("maximum".to_string(), Canon::I64(i64::MAX));
+                o
+            }
+            Schema::U64 => {
+                let mut o = ty("integer");
+                o.insert("minimum".to_string(), Canon::U64(0));
+                o.insert("maximum".to_string(), Canon::U64(u64::MAX));
+                o
+            }
+            Schema::Str => ty("string"),
+            Schema::Hex(len) => {
+                let mut o = ty("string");
+                o.insert("pattern".to_string(), Canon::Str(format!("^[0-9a-f]{{{len}}}$")));
+                o
+            }
+            Schema::Digest => {
+                let mut alts = vec!["[0-9a-f]{64}".to_string()];
+                alts.extend(HashAlg::ALL.iter().map(|a| format!("{}:[0-9a-f]{{{}}}", a.name(), a.output_len() * 2)));
+                let mut o = ty("string");
+                o.insert("pattern".to_string(), Canon::Str(format!("^({})$", alts.join("|"))));
+                o
+            }
+            Schema::Rational => {
+                let mut den = ty("integer");
+                den.insert("minimum".to_string(), Canon::U64(1));
+                return exact_json_schema([("den", Canon::Obj(den)), ("num", Schema::I64.to_json_schema())]);
+            }
+            Schema::Decimal(scale) => {
+                let mut sc = ty("integer");
+                sc.insert("const".to_string(), Canon::U64(*scale as u64));
+                return exact_json_schema([("mantissa", Schema::I64.to_json_schema()), ("scale", Canon::Obj(sc))]);
+            }
+            Schema::Nullable(inner) => {
+                let mut o = BTreeMap::new();
+                o.insert("anyOf".to_string(), Canon::Arr(vec![inner.to_json_schema(), Canon::Obj(ty("null"))]));
+                o
+            }
+            Schema::Array(item) => {
+                let mut o = ty("array");
+                o.insert("items".to_string(), item.to_json_schema());
+                o
+            }
+            Schema::Object(os) => {
+                let mut o = ty("object");
+                let props = os.required.iter().chain(os.optional.iter()).map(|(k, s)| (k.clone(), s.to_json_schema()));
+                o.insert("properties".to_string(), Canon::Obj(props.collect()));
+                o.insert("required".to_string(), Canon::Arr(os.required.keys().map(|k| Canon::Str(k.clone())).collect()));
+                o.insert("additionalProperties".to_string(), Canon::Bool(os.allow_extra));
+                o
+            }
+        };
+        Canon::Obj(obj)
+    }
+}
+
+/// Closed two-field object, the JSON form of `Canon::Rat` / `Canon::Dec`.
+fn exact_json_schema(props: [(&str, Canon); 2]) -> Canon {
+    let mut o = BTreeMap::new();
+    o.insert("type".to_string(), Canon::Str("object".to_string()));
+    o.insert("required".to_string(), Canon::Arr(props.iter().map(|(k, _)| Canon::Str(k.to_string())).collect()));
+    o.insert("properties".to_string(), Canon::Obj(props.into_iter().map(|(k, v)| (k.to_string(), v)).collect()));
+    o.insert("additionalProperties".to_string(), Canon::Bool(false));
+    Canon::Obj(o)
+}
+
+/// `SchemaRegistry::register` was given a second, different schema for a kernel.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SchemaConflict {
+    pub kernel_name: String,
+    pub kernel_version: String,
+}
+
+impl fmt::Display for SchemaConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "conflicting schema registered for {}@{}", self.kernel_name, self.kernel_version)
+    }
+}
+
+impl std::error::Error for SchemaConflict {}
+
+/// Payload schemas keyed by `(kernel_name, kernel_version)`, passed explicitly
+/// to every loader that validates payloads, along with the kernels whose certs
+/// may load under `CertHashScheme::Legacy`.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaRegistry {
+    schemas: BTreeMap<(String, String), Schema>,
+    allow_unregistered: bool,
+    legacy: BTreeMap<(String, String), DepsFn>,
+}
+
+impl SchemaRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Let payloads of kernels with no registered schema pass unchecked.
+    /// Without this, `validate` rejects them.
+    pub fn allow_unregistered(mut self) -> Self {
+        self.allow_unregistered = true;
+        self
+    }
+
+    /// Re-registering the same schema is a no-op.
+    pub fn register(
+        &mut self,
+        kernel_name: &str,
+        kernel_version: &str,
+        schema: Schema,
+    ) -> Result<&mut Self, SchemaConflict> {
+        let key = (kernel_name.to_string(), kernel_version.to_string());
+        match self.schemas.get(&key) {
+            Some(existing) if *existing != schema => {
+                return Err(SchemaConflict { kernel_name: key.0, kernel_version: key.1 });
+            }
+            Some(_) => {}
+            None => {
+                self.schemas.insert(key, schema);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Let certs of this kernel load under `CertHashScheme::Legacy`, which does not
+    /// commit to name, version or deps. Loaders then accept only the deps `deps_of`
+    /// reads from the payload, and reject `legacy` certs of any other kernel.
+    pub fn allow_legacy(&mut self, kernel_name: &str, kernel_version: &str, deps_of: DepsFn) -> &mut Self {
+        self.legacy.insert((kernel_name.to_string(), kernel_version.to_string()), deps_of);
+        self
+    }
+
+    /// How to read a `Legacy` cert's deps, if this kernel may have such certs.
+    pub fn legacy_deps_of(&self, kernel_name: &str, kernel_version: &str) -> Option<DepsFn> {
+        self.legacy.get(&(kernel_name.to_string(), kernel_version.to_string())).copied()
+    }
+
+    pub fn get(&self, kernel_name: &str, kernel_version: &str) -> Option<&Schema> {
+        self.schemas.get(&(kernel_name.to_string(), kernel_version.to_string()))
+    }
+
+    /// Validate a payload against its kernel's schema. A kernel with no
+    /// registered schema is a root-level error unless `allow_unregistered` was set.
+    pub fn validate(&self, kernel_name: &str, kernel_version: &str, payload: &Canon) -> Result<(), Vec<SchemaError>> {
+        match self.get(kernel_name, kernel_version) {
+            Some(s) => s.validate(payload),
+            None if self.allow_unregistered => Ok(()),
+            None => Err(vec![SchemaError {
+                path: String::new(),
+                msg: format!("no schema registered for {kernel_name}@{kernel_version}"),
+            }]),
+        }
+    }
+
+    /// Every schema as JSON Schema, keyed `kernel_name@kernel_version`.
+    pub fn to_json_schemas(&self) -> Canon {
+        let mut out = BTreeMap::new();
+        for ((name, version), s) in self.schemas.iter() {
+            let mut js = match s.to_json_schema() {
+                Canon::Obj(o) => o,
+                _ => unreachable!(),
+            };
+            js.insert("$schema".to_string(), Canon::Str("https://json-schema.org/draft/2020-12/schema".to_string()));
+            js.insert("title".to_string(), Canon::Str(format!("{name}@{version} payload")));
+            out.insert(format!("{name}@{version}"), Canon::Obj(js));
+        }
+        Canon::Obj(out)
+    }
+}
@@ -0,0 +1,400 @@
+//! serde data format targeting `Canon`.
+//!
+//! Mapping follows serde_json: structs and maps become `Obj`, sequences and tuples
+//! become `Arr`, `None`/unit become `Null`, enum variants are externally tagged.
+//! Signed integers map to `I64`, unsigned to `U64`. Floats are rejected outright,
+//! and map keys must be strings (or chars/integers, which are stringified), so the
+//! result sorts exactly like a hand-built `BTreeMap<String, Canon>`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::ser::{self, Serialize};
+
+use crate::canon::Canon;
+
+/// Why a value could not be converted to or from `Canon`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CanonSerdeError(pub String);
+
+impl fmt::Display for CanonSerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for CanonSerdeError {}
+
+impl ser::Error for CanonSerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        CanonSerdeError(msg.to_string())
+    }
+}
+
+impl de::Error for CanonSerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        CanonSerdeError(msg.to_string())
+    }
+}
+
+fn float_error() -> CanonSerdeError {
+    CanonSerdeError("floats are not allowed in canonical payloads; store a scaled integer".to_string())
+}
+
+pub fn to_canon_value<T: Serialize + ?Sized>(value: &T) -> Result<Canon, CanonSerdeError> {
+    value.serialize(CanonSerializer)
+}
+
+pub fn from_canon_value<T: DeserializeOwned>(value: Canon) -> Result<T, CanonSerdeError> {
+    T::deserialize(value)
+}
+
+pub struct CanonSerializer;
+
+fn single_key(key: &str, v: Canon) -> Canon {
+    let mut obj = BTreeMap::new();
+    obj.insert(key.to_string(), v);
+    Canon::Obj(obj)
+}
+
+impl ser::Serializer for CanonSerializer {
+    type Ok = Canon;
+    type Error = CanonSerdeError;
+    type SerializeSeq = SeqBuilder;
+    type SerializeTuple = SeqBuilder;
+    type SerializeTupleStruct = SeqBuilder;
+    type SerializeTupleVariant = SeqBuilder;
+    type SerializeMap = MapBuilder;
+    type SerializeStruct = MapBuilder;
+    type SerializeStructVariant = MapBuilder;
+
+    fn serialize_bool(self, v: bool) -> Result<Canon, CanonSerdeError> { Ok(Canon::Bool(v)) }
+    fn serialize_i8(self, v: i8) -> Result<Canon, CanonSerdeError> { Ok(Canon::I64(v as i64)) }
+    fn serialize_i16(self, v: i16) -> Result<Canon, CanonSerdeError> { Ok(Canon::I64(v as i64)) }
+    fn serialize_i32(self, v: i32) -> Result<Canon, CanonSerdeError> { Ok(Canon::I64(v as i64)) }
+    fn serialize_i64(self, v: i64) -> Result<Canon, CanonSerdeError> { Ok(Canon::I64(v)) }
+    fn serialize_u8(self, v: u8) -> Result<Canon, CanonSerdeError> { Ok(Canon::U64(v as u64)) }
+    fn serialize_u16(self, v: u16) -> Result<Canon, CanonSerdeError> { Ok(Canon::U64(v as u64)) }
+    fn serialize_u32(self, v: u32) -> Result<Canon, CanonSerdeError> { Ok(Canon::U64(v as u64)) }
+    fn serialize_u64(self, v: u64) -> Result<Canon, CanonSerdeError> { Ok(Canon::U64(v)) }
+
+    fn serialize_i128(self, v: i128) -> Result<Canon, CanonSerdeError> {
+        i64::try_from(v).map(Canon::I64).map_err(|_| CanonSerdeError(format!("i128 {v} does not fit in i64")))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Canon, CanonSerdeError> {
+        u64::try_from(v).map(Canon::U64).map_err(|_| CanonSerdeError(format!("u128 {v} does not fit in u64")))
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Canon, CanonSerdeError> { Err(float_error()) }
+    fn serialize_f64(self, _v: f64) -> Result<Canon, CanonSerdeError> { Err(float_error()) }
+
+    fn serialize_char(self, v: char) -> Result<Canon, CanonSerdeError> { Ok(Canon::Str(v.to_string())) }
+    fn serialize_str(self, v: &str) -> Result<Canon, CanonSerdeError> { Ok(Canon::Str(v.to_string())) }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Canon, CanonSerdeError> {
+        Ok(Canon::Arr(v.iter().map(|b| Canon::U64(*b as u64)).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Canon, CanonSerdeError> { Ok(Canon::Null) }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Canon, CanonSerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Canon, CanonSerdeError> { Ok(Canon::Null) }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Canon, CanonSerdeError> { Ok(Canon::Null) }
+
+    fn serialize_unit_variant(self, _name: &'static str, _idx: u32, variant: &'static str) -> Result<Canon, CanonSerdeError> {
+        Ok(Canon::Str(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Canon, CanonSerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _idx: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Canon, CanonSerdeError> {
+        Ok(single_key(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqBuilder, CanonSerdeError> {
+        Ok(SeqBuilder { variant: None, items: Vec::with_capacity(len.unwrap_or(0)) })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqBuilder, CanonSerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SeqBuilder, CanonSerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _idx: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqBuilder, CanonSerdeError> {
+        Ok(SeqBuilder { variant: Some(variant), items: Vec::with_capacity(len) })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapBuilder, CanonSerdeError> {
+        Ok(MapBuilder { variant: None, map: BTreeMap::new(), next_key: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<MapBuilder, CanonSerdeError> {
+        self.serialize_map(None)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _idx: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<MapBuilder, CanonSerdeError> {
+        Ok(MapBuilder { variant: Some(variant), map: BTreeMap::new(), next_key: None })
+    }
+}
+
+pub struct SeqBuilder {
+    variant: Option<&'static str>,
+    items: Vec<Canon>,
+}
+
+impl SeqBuilder {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CanonSerdeError> {
+        self.items.push(value.serialize(CanonSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Canon, CanonSerdeError> {
+        let arr = Canon::Arr(self.items);
+        Ok(match self.variant {
+            Some(v) => single_key(v, arr),
+            None => arr,
+        })
+    }
+}
+
+impl ser::SerializeSeq for SeqBuilder {
+    type Ok = Canon;
+    type Error = CanonSerdeError;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CanonSerdeError> { self.push(value) }
+    fn end(self) -> Result<Canon, CanonSerdeError> { self.finish() }
+}
+
+impl ser::SerializeTuple for SeqBuilder {
+    type Ok = Canon;
+    type Error = CanonSerdeError;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CanonSerdeError> { self.push(value) }
+    fn end(self) -> Result<Canon, CanonSerdeError> { self.finish() }
+}
+
+impl ser::SerializeTupleStruct for SeqBuilder {
+    type Ok = Canon;
+    type Error = CanonSerdeError;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CanonSerdeError> { self.push(value) }
+    fn end(self) -> Result<Canon, CanonSerdeError> { self.finish() }
+}
+
+impl ser::SerializeTupleVariant for SeqBuilder {
+    type Ok = Canon;
+    type Error = CanonSerdeError;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CanonSerdeError> { self.push(value) }
+    fn end(self) -> Result<Canon, CanonSerdeError> { self.finish() }
+}
+
+pub struct MapBuilder {
+    variant: Option<&'static str>,
+    map: BTreeMap<String, Canon>,
+    next_key: Option<String>,
+}
+
+impl MapBuilder {
+    fn insert(&mut self, key: String, value: Canon) -> Result<(), CanonSerdeError> {
+        if self.map.contains_key(&key) {
+            return Err(CanonSerdeError(format!("duplicate map key {key:?}")));
+        }
+        self.map.insert(key, value);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Canon, CanonSerdeError> {
+        let obj = Canon::Obj(self.map);
+        Ok(match self.variant {
+            Some(v) => single_key(v, obj),
+            None => obj,
+        })
+    }
+}
+
+impl ser::SerializeMap for MapBuilder {
+    type Ok = Canon;
+    type Error = CanonSerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), CanonSerdeError> {
+        let k = match key.serialize(CanonSerializer)? {
+            Canon::Str(s) => s,
+            Canon::I64(n) => n.to_string(),
+            Canon::U64(n) => n.to_string(),
+            other => return Err(CanonSerdeError(format!("map key must be a string, got {other:?}"))),
+        };
+        self.next_key = Some(k);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CanonSerdeError> {
+        let k = self.next_key.take().ok_or_else(|| CanonSerdeError("serialize_value before serialize_key".to_string()))?;
+        let v = value.serialize(CanonSerializer)?;
+        self.insert(k, v)
+    }
+
+    fn end(self) -> Result<Canon, CanonSerdeError> { self.finish() }
+}
+
+impl ser::SerializeStruct for MapBuilder {
+    type Ok = Canon;
+    type Error = CanonSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), CanonSerdeError> {
+        let v = value.serialize(CanonSerializer)?;
+        self.insert(key.to_string(), v)
+    }
+
+    fn end(self) -> Result<Canon, CanonSerdeError> { self.finish() }
+}
+
+impl ser::SerializeStructVariant for MapBuilder {
+    type Ok = Canon;
+    type Error = CanonSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), CanonSerdeError> {
+        let v = value.serialize(CanonSerializer)?;
+        self.insert(key.to_string(), v)
+    }
+
+    fn end(self) -> Result<Canon, CanonSerdeError> { self.finish() }
+}
+
+fn unexpected(v: &Canon) -> de::Unexpected<'_> {
+    match v {
+        Canon::Null => de::Unexpected::Unit,
+        Canon::Bool(b) => de::Unexpected::Bool(*b),
+        Canon::I64(n) => de::Unexpected::Signed(*n),
+        Canon::U64(n) => de::Unexpected::Unsigned(*n),
+        Canon::Str(s) => de::Unexpected::Str(s),
+        Canon::Arr(_) => de::Unexpected::Seq,
+        Canon::Obj(_) | Canon::Rat(_) | Canon::Dec(_) => de::Unexpected::Map,
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Canon {
+    type Error = CanonSerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CanonSerdeError> {
+        match self {
+            Canon::Null => visitor.visit_unit(),
+            Canon::Bool(b) => visitor.visit_bool(b),
+            Canon::I64(n) => visitor.visit_i64(n),
+            Canon::U64(n) => visitor.visit_u64(n),
+            Canon::Str(s) => visitor.visit_string(s),
+            Canon::Arr(xs) => visitor.visit_seq(de::value::SeqDeserializer::new(xs.into_iter())),
+            Canon::Obj(map) => visitor.visit_map(de::value::MapDeserializer::new(map.into_iter())),
+            Canon::Rat(r) => r.to_obj().deserialize_any(visitor),
+            Canon::Dec(d) => d.to_obj().deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, CanonSerdeError> { Err(float_error()) }
+    fn deserialize_f64<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, CanonSerdeError> { Err(float_error()) }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CanonSerdeError> {
+        match self {
+            Canon::Null => visitor.visit_none(),
+            other => visitor.visit_some(other),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, CanonSerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, CanonSerdeError> {
+        match self {
+            Canon::Str(s) => visitor.visit_enum(s.into_deserializer()),
+            Canon::Obj(map) if map.len() == 1 => {
+                let (variant, value) = map.into_iter().next().unwrap();
+                visitor.visit_enum(EnumAccess { variant, value })
+            }
+            other => Err(de::Error::invalid_type(unexpected(&other), &"string or single-key object")),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, CanonSerdeError> for Canon {
+    type Deserializer = Canon;
+    fn into_deserializer(self) -> Canon {
+        self
+    }
+}
+
+struct EnumAccess {
+    variant: String,
+    value: Canon,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+    type Error = CanonSerdeError;
+    type Variant = VariantAccess;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, VariantAccess), CanonSerdeError> {
+        let v = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((v, VariantAccess { value: self.value }))
+    }
+}
+
+struct VariantAccess {
+    value: Canon,
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess {
+    type Error = CanonSerdeError;
+
+    fn unit_variant(self) -> Result<(), CanonSerdeError> {
+        match self.value {
+            Canon::Null => Ok(()),
+            other => Err(de::Error::invalid_type(unexpected(&other), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, CanonSerdeError> {
+        seed.deserialize(self.value)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, CanonSerdeError> {
+        de::Deserializer::deserialize_any(self.value, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, CanonSerdeError> {
+        de::Deserializer::deserialize_any(self.value, visitor)
+    }
+}
@@ -0,0 +1,356 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
+
+use crate::canon::{Canon, CanonMode, write_canon_with};
+use crate::cert::{CertChain, CertHashScheme, CertItem, ChainMode, KernelCert, deps_to_canon};
+use crate::convert::{FromCanon, FromCanonError, ToCanon, obj_field};
+use crate::digest::TaggedDigest;
+
+/// Prefix of every signed message.
+pub const SIGNATURE_DOMAIN_V1: &str = "collapse/signature/v1";
+
+/// An Ed25519 public key. Displays and parses as 64 hex chars.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct PublicKey(pub [u8; 32]);
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s).map_err(|_| format!("public key {s:?} is not hex"))?;
+        let key: [u8; 32] = bytes.try_into().map_err(|_| format!("public key {s:?} is not 32 bytes"))?;
+        Ok(PublicKey(key))
+    }
+}
+
+impl PublicKey {
+    /// Raw Ed25519 check of `signature` over `msg`, for formats that do their
+    /// own framing (e.g. DSSE). `CertSignature::is_valid` covers `SignedSubject`s.
+    pub fn verify_bytes(&self, msg: &[u8], signature: &[u8; 64]) -> bool {
+        let Ok(key) = VerifyingKey::from_bytes(&self.0) else { return false };
+        key.verify_strict(msg, &ed25519_dalek::Signature::from_bytes(signature)).is_ok()
+    }
+}
+
+/// Everything about a cert but its payload, which `kernel_hash` stands for.
+/// A `Legacy` hash covers the payload alone, so a signature binds the rest here.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CertHeader {
+    pub kernel_name: String,
+    pub kernel_version: String,
+    pub canon_mode: CanonMode,
+    pub hash_scheme: CertHashScheme,
+    pub deps: Vec<CertItem>,
+    pub kernel_hash: TaggedDigest,
+}
+
+impl CertHeader {
+    pub fn of(cert: &KernelCert) -> Self {
+        Self {
+            kernel_name: cert.kernel_name.clone(),
+            kernel_version: cert.kernel_version.clone(),
+            canon_mode: cert.canon_mode,
+            hash_scheme: cert.hash_scheme,
+            deps: cert.deps.clone(),
+            kernel_hash: cert.kernel_hash.clone(),
+        }
+    }
+
+    fn write_fields(&self, obj: &mut BTreeMap<String, Canon>) {
+        obj.insert("canon_mode".to_string(), Canon::Str(self.canon_mode.tag().to_string()));
+        obj.insert("hash_scheme".to_string(), Canon::Str(self.hash_scheme.tag().to_string()));
+        obj.insert("kernel_hash".to_string(), self.kernel_hash.to_canon());
+        obj.insert("kernel_name".to_string(), Canon::Str(self.kernel_name.clone()));
+        obj.insert("kernel_version".to_string(), Canon::Str(self.kernel_version.clone()));
+        if !self.deps.is_empty() {
+            obj.insert("deps".to_string(), deps_to_canon(&self.deps));
+        }
+    }
+
+    fn read_fields(obj: &BTreeMap<String, Canon>) -> Result<Self, FromCanonError> {
+        let tag: String = obj_field(obj, "canon_mode")?;
+        let canon_mode = CanonMode::from_tag(&tag)
+            .ok_or_else(|| FromCanonError::new(format!("unknown canon_mode {tag:?}")).at("canon_mode"))?;
+        let tag: String = obj_field(obj, "hash_scheme")?;
+        let hash_scheme = CertHashScheme::from_tag(&tag)
+            .ok_or_else(|| FromCanonError::new(format!("unknown hash_scheme {tag:?}")).at("hash_scheme"))?;
+        Ok(Self {
+            kernel_name: obj_field(obj, "kernel_name")?,
+            kernel_version: obj_field(obj, "kernel_version")?,
+            canon_mode,
+            hash_scheme,
+            deps: obj_field::<Option<Vec<CertItem>>>(obj, "deps")?.unwrap_or_default(),
+            kernel_hash: obj_field(obj, "kernel_hash")?,
+        })
+    }
+}
+
+impl ToCanon for CertHeader {
+    fn to_canon(&self) -> Canon {
+        let mut obj = BTreeMap::new();
+        self.write_fields(&mut obj);
+        Canon::Obj(obj)
+    }
+}
+
+impl FromCanon for CertHeader {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        let Canon::Obj(obj) = v else {
+            return Err(FromCanonError::expected("object", v));
+        };
+        Self::read_fields(obj)
+    }
+}
+
+/// What a signature vouches for.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SignedSubject {
+    Cert(CertHeader),
+    /// The chain root together with the headers of the certs under it, sorted by
+    /// name and hash: chain items alone name only kernel names and hashes.
+    ChainRoot { mode: ChainMode, chain_hash: TaggedDigest, certs: Vec<CertHeader> },
+    /// A transparency log's Merkle root at `tree_size` entries.
+    TreeHead { tree_size: u64, root_hash: TaggedDigest },
+}
+
+impl SignedSubject {
+    pub fn of_cert(cert: &KernelCert) -> Self {
+        SignedSubject::Cert(CertHeader::of(cert))
+    }
+
+    /// `certs` are the certs the chain was built over, in any order.
+    pub fn of_chain(chain: &CertChain, certs: &[KernelCert]) -> Self {
+        let mut certs: Vec<CertHeader> = certs.iter().map(CertHeader::of).collect();
+        certs.sort_by(|a, b| {
+            a.kernel_name.cmp(&b.kernel_name).then_with(|| a.kernel_hash.as_bytes().cmp(b.kernel_hash.as_bytes()))
+        });
+        SignedSubject::ChainRoot { mode: chain.mode, chain_hash: chain.chain_hash.clone(), certs }
+    }
+
+    pub fn to_canon(&self) -> Canon {
+        let mut obj = BTreeMap::new();
+        match self {
+            SignedSubject::Cert(header) => {
+                header.write_fields(&mut obj);
+                obj.insert("type".to_string(), Canon::Str("kernel_cert".to_string()));
+            }
+            SignedSubject::ChainRoot { mode, chain_hash, certs } => {
+                obj.insert("certs".to_string(), certs.to_canon());
+                obj.insert("chain_hash".to_string(), chain_hash.to_canon());
+                obj.insert("mode".to_string(), Canon::Str(mode.tag().to_string()));
+                obj.insert("type".to_string(), Canon::Str("chain_root".to_string()));
+            }
+            SignedSubject::TreeHead { tree_size, root_hash } => {
+                obj.insert("root_hash".to_string(), root_hash.to_canon());
+                obj.insert("tree_size".to_string(), Canon::U64(*tree_size));
+                obj.insert("type".to_string(), Canon::Str("tree_head".to_string()));
+            }
+        }
+        Canon::Obj(obj)
+    }
+
+    pub fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        let Canon::Obj(obj) = v else {
+            return Err(FromCanonError::expected("object", v));
+        };
+        let ty: String = obj_field(obj, "type")?;
+        match ty.as_str() {
+            "kernel_cert" => Ok(SignedSubject::Cert(CertHeader::read_fields(obj)?)),
+            "chain_root" => {
+                let tag: String = obj_field(obj, "mode")?;
+                let mode = ChainMode::from_tag(&tag)
+                    .ok_or_else(|| FromCanonError::new(format!("unknown chain mode {tag:?}")).at("mode"))?;
+                let (chain_hash, certs) = (obj_field(obj, "chain_hash")?, obj_field(obj, "certs")?);
+                Ok(SignedSubject::ChainRoot { mode, chain_hash, certs })
+            }
+            "tree_head" => Ok(SignedSubject::TreeHead {
+                tree_size: obj_field(obj, "tree_size")?,
+                root_hash: obj_field(obj, "root_hash")?,
+            }),
+            _ => Err(FromCanonError::new(format!("unknown subject type {ty:?}")).at("type")),
+        }
+    }
+
+    /// `SIGNATURE_DOMAIN_V1 || 0x00 || canon(subject)`.
+    pub fn message(&self) -> Vec<u8> {
+        let mut out = SIGNATURE_DOMAIN_V1.as_bytes().to_vec();
+        out.push(0);
+        write_canon_with(&mut out, &self.to_canon(), CanonMode::V1);
+        out
+    }
+}
+
+/// A detached Ed25519 signature over a `SignedSubject`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CertSignature {
+    pub subject: SignedSubject,
+    pub public_key: PublicKey,
+    pub signature: [u8; 64],
+}
+
+impl CertSignature {
+    /// Cryptographic check only; whether the key is trusted is up to `TrustStore`.
+    pub fn is_valid(&self) -> bool {
+        self.public_key.verify_bytes(&self.subject.message(), &self.signature)
+    }
+}
+
+impl ToCanon for CertSignature {
+    fn to_canon(&self) -> Canon {
+        let mut obj = BTreeMap::new();
+        obj.insert("public_key".to_string(), Canon::Str(self.public_key.to_string()));
+        obj.insert("signature".to_string(), Canon::Str(hex::encode(self.signature)));
+        obj.insert("subject".to_string(), self.subject.to_canon());
+        Canon::Obj(obj)
+    }
+}
+
+impl FromCanon for CertSignature {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        let Canon::Obj(obj) = v else {
+            return Err(FromCanonError::expected("object", v));
+        };
+        let key: String = obj_field(obj, "public_key")?;
+        let public_key = key.parse().map_err(|e: String| FromCanonError::new(e).at("public_key"))?;
+        let sig: String = obj_field(obj, "signature")?;
+        let signature = hex::decode(&sig)
+            .ok()
+            .and_then(|b| <[u8; 64]>::try_from(b).ok())
+            .ok_or_else(|| FromCanonError::new("signature is not 64 hex-encoded bytes").at("signature"))?;
+        let subject =
+            SignedSubject::from_canon(obj.get("subject").unwrap_or(&Canon::Null)).map_err(|e| e.at("subject"))?;
+        Ok(Self { subject, public_key, signature })
+    }
+}
+
+/// An Ed25519 signing key. Keys come from a 32-byte seed; generating and storing
+/// seeds is left to the caller.
+pub struct CertSigner {
+    key: SigningKey,
+}
+
+impl CertSigner {
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self { key: SigningKey::from_bytes(&seed) }
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(self.key.verifying_key().to_bytes())
+    }
+
+    pub fn sign(&self, subject: SignedSubject) -> CertSignature {
+        let signature = self.sign_bytes(&subject.message());
+        CertSignature { subject, public_key: self.public_key(), signature }
+    }
+
+    /// Raw Ed25519 over `msg`; the counterpart of `PublicKey::verify_bytes`.
+    pub fn sign_bytes(&self, msg: &[u8]) -> [u8; 64] {
+        self.key.sign(msg).to_bytes()
+    }
+
+    pub fn sign_cert(&self, cert: &KernelCert) -> CertSignature {
+        self.sign(SignedSubject::of_cert(cert))
+    }
+
+    pub fn sign_chain(&self, chain: &CertChain, certs: &[KernelCert]) -> CertSignature {
+        self.sign(SignedSubject::of_chain(chain, certs))
+    }
+}
+
+/// Why `TrustStore::check` rejected a set of signatures.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SignatureError {
+    /// A signature over the subject that does not verify under its own key.
+    Invalid { public_key: PublicKey },
+    /// Fewer distinct trusted signers than the policy requires.
+    BelowThreshold { required: usize, trusted: Vec<String> },
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::Invalid { public_key } => write!(f, "invalid signature from key {public_key}"),
+            SignatureError::BelowThreshold { required, trusted } => {
+                write!(f, "need {required} trusted signers, got {} ({})", trusted.len(), trusted.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// Named public keys a verifier accepts.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TrustStore {
+    keys: BTreeMap<PublicKey, String>,
+}
+
+impl TrustStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: &str, key: PublicKey) -> &mut Self {
+        self.keys.insert(key, name.to_string());
+        self
+    }
+
+    pub fn name_of(&self, key: &PublicKey) -> Option<&str> {
+        self.keys.get(key).map(String::as_str)
+    }
+
+    /// Keys in order, with their names.
+    pub fn iter(&self) -> impl Iterator<Item = (&PublicKey, &str)> {
+        self.keys.iter().map(|(k, n)| (k, n.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Threshold check for `subject`: at least `threshold` distinct trusted keys
+    /// must have signed it (e.g. 2 of a 3-key store). Signatures over other
+    /// subjects are ignored; an invalid one over this subject is an error.
+    /// Returns the names of the trusted signers, sorted.
+    pub fn check(
+        &self,
+        subject: &SignedSubject,
+        signatures: &[CertSignature],
+        threshold: usize,
+    ) -> Result<Vec<String>, SignatureError> {
+        let mut signers = Vec::new();
+        for sig in signatures.iter().filter(|s| s.subject == *subject) {
+            if !sig.is_valid() {
+                return Err(SignatureError::Invalid { public_key: sig.public_key });
+            }
+            signers.push(sig.public_key);
+        }
+        self.check_signers(&signers, threshold)
+    }
+
+    /// Threshold check over keys whose signatures the caller has already
+    /// verified. Each trusted key counts once, whatever its name; untrusted keys
+    /// are ignored. Returns the name of each trusted key, sorted.
+    pub fn check_signers(&self, signers: &[PublicKey], threshold: usize) -> Result<Vec<String>, SignatureError> {
+        let keys: BTreeSet<&PublicKey> = signers.iter().collect();
+        let mut trusted: Vec<String> = keys.into_iter().filter_map(|k| self.name_of(k)).map(str::to_string).collect();
+        trusted.sort();
+        if trusted.len() < threshold {
+            return Err(SignatureError::BelowThreshold { required: threshold, trusted });
+        }
+        Ok(trusted)
+    }
+}
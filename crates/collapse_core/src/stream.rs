@@ -0,0 +1,143 @@
+use sha2::{Digest, Sha256};
+
+use crate::canon::{Canon, CanonMode, write_canon, write_canon_str, write_canon_with};
+use crate::digest::{HashAlg, Sha256Digest, TaggedDigest};
+
+/// Destination for canonical bytes. Writers stream into a sink instead of
+/// building a `Vec<u8>`, so large values can be hashed without materializing them.
+pub trait CanonSink {
+    fn write_bytes(&mut self, bytes: &[u8]);
+
+    fn write_byte(&mut self, b: u8) {
+        self.write_bytes(&[b]);
+    }
+}
+
+impl CanonSink for Vec<u8> {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+
+    fn write_byte(&mut self, b: u8) {
+        self.push(b);
+    }
+}
+
+/// SHA-256 over streamed canonical bytes.
+#[derive(Clone, Default)]
+pub struct HashSink(Sha256);
+
+impl HashSink {
+    pub fn new() -> Self {
+        Self(Sha256::new())
+    }
+
+    pub fn finish(self) -> Sha256Digest {
+        Sha256Digest(self.0.finalize().into())
+    }
+}
+
+impl CanonSink for HashSink {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+}
+
+/// `sha256(canon_bytes(v))` without allocating the bytes.
+pub fn canon_sha256(v: &Canon) -> Sha256Digest {
+    canon_sha256_with(v, CanonMode::V1)
+}
+
+/// `sha256(canon_bytes_with(v, mode))` without allocating the bytes.
+pub fn canon_sha256_with(v: &Canon, mode: CanonMode) -> Sha256Digest {
+    let mut h = HashSink::new();
+    write_canon_with(&mut h, v, mode);
+    h.finish()
+}
+
+/// `alg(canon_bytes_with(v, mode))`, streamed.
+pub fn canon_digest_with(v: &Canon, mode: CanonMode, alg: HashAlg) -> TaggedDigest {
+    let mut h = alg.hasher();
+    write_canon_with(&mut h, v, mode);
+    h.finish()
+}
+
+/// Write a v1 array element by element. The closure receives the writer; the
+/// closing bracket is emitted when it returns.
+pub fn write_array<S: CanonSink + ?Sized>(sink: &mut S, f: impl FnOnce(&mut ArrayWriter<'_, S>)) {
+    sink.write_byte(b'[');
+    let mut w = ArrayWriter { sink, first: true };
+    f(&mut w);
+    w.sink.write_byte(b']');
+}
+
+/// Write a v1 object field by field. Keys must arrive in strictly increasing
+/// (`BTreeMap`) order; anything else would hash differently from the equivalent
+/// `Canon::Obj`, so it panics.
+pub fn write_object<S: CanonSink + ?Sized>(sink: &mut S, f: impl FnOnce(&mut ObjectWriter<'_, S>)) {
+    sink.write_byte(b'{');
+    let mut w = ObjectWriter { sink, last_key: None };
+    f(&mut w);
+    w.sink.write_byte(b'}');
+}
+
+pub struct ArrayWriter<'a, S: CanonSink + ?Sized> {
+    sink: &'a mut S,
+    first: bool,
+}
+
+impl<S: CanonSink + ?Sized> ArrayWriter<'_, S> {
+    fn sep(&mut self) {
+        if !self.first {
+            self.sink.write_byte(b',');
+        }
+        self.first = false;
+    }
+
+    pub fn item(&mut self, v: &Canon) {
+        self.sep();
+        write_canon(self.sink, v);
+    }
+
+    pub fn item_array(&mut self, f: impl FnOnce(&mut ArrayWriter<'_, S>)) {
+        self.sep();
+        write_array(self.sink, f);
+    }
+
+    pub fn item_object(&mut self, f: impl FnOnce(&mut ObjectWriter<'_, S>)) {
+        self.sep();
+        write_object(self.sink, f);
+    }
+}
+
+pub struct ObjectWriter<'a, S: CanonSink + ?Sized> {
+    sink: &'a mut S,
+    last_key: Option<String>,
+}
+
+impl<S: CanonSink + ?Sized> ObjectWriter<'_, S> {
+    fn key(&mut self, key: &str) {
+        if let Some(prev) = &self.last_key {
+            assert!(prev.as_str() < key, "ObjectWriter: key {key:?} after {prev:?} breaks canonical order");
+            self.sink.write_byte(b',');
+        }
+        self.last_key = Some(key.to_string());
+        write_canon_str(self.sink, key);
+        self.sink.write_byte(b':');
+    }
+
+    pub fn field(&mut self, key: &str, v: &Canon) {
+        self.key(key);
+        write_canon(self.sink, v);
+    }
+
+    pub fn field_array(&mut self, key: &str, f: impl FnOnce(&mut ArrayWriter<'_, S>)) {
+        self.key(key);
+        write_array(self.sink, f);
+    }
+
+    pub fn field_object(&mut self, key: &str, f: impl FnOnce(&mut ObjectWriter<'_, S>)) {
+        self.key(key);
+        write_object(self.sink, f);
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::canon::{Canon, canon_bytes};
+use crate::cert::{CertChain, ChainMode};
+use crate::convert::{FromCanonError, ToCanon, obj_field};
+use crate::digest::{HashAlg, TaggedDigest};
+use crate::merkle::{ConsistencyProof, InclusionProof, consistency_proof, inclusion_proof, merkle_leaf_hash, merkle_root};
+use crate::parse::parse_canon;
+use crate::sign::{CertSignature, CertSigner, SignatureError, SignedSubject, TrustStore};
+
+/// The log tree is always SHA-256, as in RFC 6962; the chain roots it records may use any algorithm.
+const LOG_HASH_ALG: HashAlg = HashAlg::Sha256;
+
+/// One logged chain root. Its leaf data is its canonical JSON (v1) line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogEntry {
+    pub mode: ChainMode,
+    pub chain_hash: TaggedDigest,
+}
+
+impl LogEntry {
+    pub fn of_chain(chain: &CertChain) -> Self {
+        Self { mode: chain.mode, chain_hash: chain.chain_hash.clone() }
+    }
+
+    pub fn to_canon(&self) -> Canon {
+        let mut obj = BTreeMap::new();
+        obj.insert("chain_hash".to_string(), self.chain_hash.to_canon());
+        obj.insert("mode".to_string(), Canon::Str(self.mode.tag().to_string()));
+        Canon::Obj(obj)
+    }
+
+    pub fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        let Canon::Obj(obj) = v else {
+            return Err(FromCanonError::expected("object", v));
+        };
+        let tag: String = obj_field(obj, "mode")?;
+        let mode = ChainMode::from_tag(&tag)
+            .ok_or_else(|| FromCanonError::new(format!("unknown chain mode {tag:?}")).at("mode"))?;
+        Ok(Self { mode, chain_hash: obj_field(obj, "chain_hash")? })
+    }
+
+    pub fn leaf_hash(&self) -> TaggedDigest {
+        merkle_leaf_hash(LOG_HASH_ALG, &canon_bytes(&self.to_canon()))
+    }
+}
+
+/// The log's root at a given size. Signed as `SignedSubject::TreeHead`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeHead {
+    pub tree_size: u64,
+    pub root_hash: TaggedDigest,
+}
+
+impl TreeHead {
+    pub fn subject(&self) -> SignedSubject {
+        SignedSubject::TreeHead { tree_size: self.tree_size, root_hash: self.root_hash.clone() }
+    }
+
+    pub fn from_subject(subject: &SignedSubject) -> Option<Self> {
+        match subject {
+            SignedSubject::TreeHead { tree_size, root_hash } => {
+                Some(Self { tree_size: *tree_size, root_hash: root_hash.clone() })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Why the log failed to load, or a verifier rejected what the log served.
+#[derive(Debug)]
+pub enum LogError {
+    Io(std::io::Error),
+    /// Line `line` (1-based) is not a canonical log entry, e.g. after a torn write.
+    Malformed { line: usize, reason: String },
+    /// The log holds fewer entries than a head it is checked against.
+    Truncated { tree_size: u64, entries: u64 },
+    /// The entries no longer hash to a previously seen root: history was rewritten.
+    RootMismatch { tree_size: u64, expected: TaggedDigest, found: TaggedDigest },
+    /// A new head that does not extend the current one.
+    Inconsistent { old_size: u64, new_size: u64 },
+    NotIncluded { leaf_index: u64, tree_size: u64 },
+    /// `LogVerifier::verify_inclusion` before any head was accepted.
+    NoHead,
+    /// Another writer appended since this log was opened; reopen it.
+    Stale { expected_len: u64, found_len: u64 },
+    Signature(SignatureError),
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogError::Io(e) => write!(f, "log i/o: {e}"),
+            LogError::Malformed { line, reason } => write!(f, "log line {line}: {reason}"),
+            LogError::Truncated { tree_size, entries } => {
+                write!(f, "log has {entries} entries, head claims {tree_size}")
+            }
+            LogError::RootMismatch { tree_size, expected, found } => {
+                write!(f, "log root at size {tree_size} is {found}, expected {expected}")
+            }
+            LogError::Inconsistent { old_size, new_size } => {
+                write!(f, "tree head at size {new_size} is not consistent with size {old_size}")
+            }
+            LogError::NotIncluded { leaf_index, tree_size } => {
+                write!(f, "entry {leaf_index} is not included in the tree of size {tree_size}")
+            }
+            LogError::NoHead => write!(f, "no tree head accepted yet"),
+            LogError::Stale { expected_len, found_len } => {
+                write!(f, "log file is {found_len} bytes, expected {expected_len}: it changed since it was opened")
+            }
+            LogError::Signature(e) => write!(f, "tree head: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for LogError {}
+
+impl From<std::io::Error> for LogError {
+    fn from(e: std::io::Error) -> Self {
+        LogError::Io(e)
+    }
+}
+
+/// Append-only log of chain roots, one canonical JSON entry per line. Entries
+/// are leaves of an RFC 6962 Merkle tree, so a published head can later be
+/// proven to contain an entry, or to be a prefix of a newer head.
+///
+/// `append` holds an exclusive file lock while writing and fails with
+/// `LogError::Stale` if another writer got there first.
+#[derive(Clone, Debug)]
+pub struct TransparencyLog {
+    path: PathBuf,
+    entries: Vec<LogEntry>,
+    leaves: Vec<TaggedDigest>,
+    /// File length the entries were read from or written to.
+    len: u64,
+}
+
+impl TransparencyLog {
+    /// Loads the log at `path`; a missing file is an empty log, created on first append.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, LogError> {
+        let path = path.as_ref().to_path_buf();
+        let bytes = match std::fs::read(&path) {
+            Ok(b) => b,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut entries = Vec::new();
+        if let Some(body) = bytes.strip_suffix(b"\n") {
+            for (i, raw) in body.split(|&b| b == b'\n').enumerate() {
+                let bad = |reason: String| LogError::Malformed { line: i + 1, reason };
+                let v = parse_canon(raw).map_err(|e| bad(e.to_string()))?;
+                let entry = LogEntry::from_canon(&v).map_err(|e| bad(e.to_string()))?;
+                if canon_bytes(&entry.to_canon()) != raw {
+                    return Err(bad("entry is not in canonical form".to_string()));
+                }
+                entries.push(entry);
+            }
+        } else if !bytes.is_empty() {
+            let line = bytes.split(|&b| b == b'\n').count();
+            return Err(LogError::Malformed { line, reason: "unterminated line".to_string() });
+        }
+        let leaves = entries.iter().map(LogEntry::leaf_hash).collect();
+        Ok(Self { path, entries, leaves, len: bytes.len() as u64 })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn entries(&self) -> &[LogEntry] {
+        &self.entries
+    }
+
+    pub fn size(&self) -> u64 {
+        self.entries.len() as u64
+    }
+
+    /// Appends `chain`'s root and syncs the file. Returns the new entry's index.
+    pub fn append(&mut self, chain: &CertChain) -> Result<u64, LogError> {
+        let entry = LogEntry::of_chain(chain);
+        let mut line = canon_bytes(&entry.to_canon());
+        line.push(b'\n');
+        let mut f = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        f.lock()?;
+        let found_len = f.metadata()?.len();
+        if found_len != self.len {
+            return Err(LogError::Stale { expected_len: self.len, found_len });
+        }
+        f.write_all(&line)?;
+        f.sync_data()?;
+        self.len += line.len() as u64;
+        self.leaves.push(entry.leaf_hash());
+        self.entries.push(entry);
+        Ok(self.size() - 1)
+    }
+
+    pub fn tree_head(&self) -> TreeHead {
+        self.head_over(self.size())
+    }
+
+    /// Head over the first `tree_size` entries. `None` if the log is smaller.
+    pub fn tree_head_at(&self, tree_size: u64) -> Option<TreeHead> {
+        (tree_size <= self.size()).then(|| self.head_over(tree_size))
+    }
+
+    fn head_over(&self, tree_size: u64) -> TreeHead {
+        TreeHead { tree_size, root_hash: merkle_root(LOG_HASH_ALG, &self.leaves[..tree_size as usize]) }
+    }
+
+    pub fn sign_tree_head(&self, signer: &CertSigner) -> CertSignature {
+        signer.sign(self.tree_head().subject())
+    }
+
+    /// `None` unless `leaf_index < tree_size <= size()`.
+    pub fn inclusion_proof(&self, leaf_index: u64, tree_size: u64) -> Option<InclusionProof> {
+        if tree_size > self.size() {
+            return None;
+        }
+        inclusion_proof(LOG_HASH_ALG, &self.leaves[..tree_size as usize], usize::try_from(leaf_index).ok()?)
+    }
+
+    /// `None` unless `old_size <= new_size <= size()`.
+    pub fn consistency_proof(&self, old_size: u64, new_size: u64) -> Option<ConsistencyProof> {
+        if new_size > self.size() {
+            return None;
+        }
+        consistency_proof(LOG_HASH_ALG, &self.leaves[..new_size as usize], usize::try_from(old_size).ok()?)
+    }
+
+    /// The entries on disk still hash to `head`.
+    pub fn check_head(&self, head: &TreeHead) -> Result<(), LogError> {
+        if head.tree_size > self.size() {
+            return Err(LogError::Truncated { tree_size: head.tree_size, entries: self.size() });
+        }
+        let found = self.head_over(head.tree_size).root_hash;
+        if found != head.root_hash {
+            return Err(LogError::RootMismatch { tree_size: head.tree_size, expected: head.root_hash.clone(), found });
+        }
+        Ok(())
+    }
+}
+
+/// A log client. It holds the latest tree head it has accepted and only moves
+/// forward to heads that are signed by enough trusted keys and provably extend it.
+#[derive(Clone, Debug)]
+pub struct LogVerifier {
+    trust: TrustStore,
+    threshold: usize,
+    head: Option<TreeHead>,
+}
+
+impl LogVerifier {
+    pub fn new(trust: TrustStore, threshold: usize) -> Self {
+        Self { trust, threshold, head: None }
+    }
+
+    pub fn head(&self) -> Option<&TreeHead> {
+        self.head.as_ref()
+    }
+
+    /// Accept `head` if `signatures` meet the threshold and `proof` shows it
+    /// extends the current head. The first head needs no proof (pass the empty
+    /// one from `consistency_proof(0, n)`).
+    pub fn update(
+        &mut self,
+        head: &TreeHead,
+        signatures: &[CertSignature],
+        proof: &ConsistencyProof,
+    ) -> Result<(), LogError> {
+        self.trust.check(&head.subject(), signatures, self.threshold).map_err(LogError::Signature)?;
+        if let Some(old) = &self.head {
+            let consistent = proof.old_size == old.tree_size
+                && proof.new_size == head.tree_size
+                && proof.verify(&old.root_hash, &head.root_hash);
+            if !consistent {
+                return Err(LogError::Inconsistent { old_size: old.tree_size, new_size: head.tree_size });
+            }
+        }
+        self.head = Some(head.clone());
+        Ok(())
+    }
+
+    /// `entry` is in the log under the accepted head.
+    pub fn verify_inclusion(&self, entry: &LogEntry, proof: &InclusionProof) -> Result<(), LogError> {
+        let head = self.head.as_ref().ok_or(LogError::NoHead)?;
+        if proof.tree_size != head.tree_size || !proof.verify(&entry.leaf_hash(), &head.root_hash) {
+            return Err(LogError::NotIncluded { leaf_index: proof.leaf_index, tree_size: head.tree_size });
+        }
+        Ok(())
+    }
+}
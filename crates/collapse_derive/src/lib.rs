@@ -0,0 +1,357 @@
+//! `#[derive(ToCanon, FromCanon)]` for cert payload types.
+//!
+//! Structs with named fields become `Canon::Obj` keyed by field name; newtype structs
+//! are transparent; tuple structs become `Canon::Arr`; unit structs become `Canon::Null`.
+//! Enums are externally tagged: unit variants as `Str(name)`, others as `{name: body}`.
+//!
+//! Field / variant attributes, all under `#[canon(...)]`:
+//! - `rename = "key"`: object key (or variant tag) to use instead of the Rust name.
+//! - `skip`: leave the field out; `from_canon` fills it with `Default::default()`.
+//! - `skip_if_none`: omit the key when an `Option` field is `None` (default is `null`).
+//! - `scale = N`: store an `f64`/`f32` field as the fixed-point integer `round(x * N)`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Fields, Generics, Ident, LitStr};
+
+#[proc_macro_derive(ToCanon, attributes(canon))]
+pub fn derive_to_canon(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_to_canon(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+#[proc_macro_derive(FromCanon, attributes(canon))]
+pub fn derive_from_canon(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_canon(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+#[derive(Default)]
+struct CanonAttrs {
+    rename: Option<String>,
+    skip: bool,
+    skip_if_none: bool,
+    scale: Option<syn::Expr>,
+}
+
+fn parse_attrs(attrs: &[syn::Attribute]) -> syn::Result<CanonAttrs> {
+    let mut out = CanonAttrs::default();
+    for attr in attrs {
+        if !attr.path().is_ident("canon") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let s: LitStr = meta.value()?.parse()?;
+                out.rename = Some(s.value());
+            } else if meta.path.is_ident("skip") {
+                out.skip = true;
+            } else if meta.path.is_ident("skip_if_none") {
+                out.skip_if_none = true;
+            } else if meta.path.is_ident("scale") {
+                out.scale = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("unknown canon attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(out)
+}
+
+fn add_bound(generics: &Generics, bound: syn::Path) -> Generics {
+    let mut g = generics.clone();
+    for tp in g.type_params_mut() {
+        tp.bounds.push(parse_quote!(#bound));
+    }
+    g
+}
+
+struct NamedField {
+    ident: Ident,
+    key: String,
+    attrs: CanonAttrs,
+}
+
+fn named_fields(fields: &syn::FieldsNamed) -> syn::Result<Vec<NamedField>> {
+    fields
+        .named
+        .iter()
+        .map(|f| {
+            let ident = f.ident.clone().unwrap();
+            let attrs = parse_attrs(&f.attrs)?;
+            if attrs.skip && (attrs.scale.is_some() || attrs.skip_if_none) {
+                return Err(syn::Error::new_spanned(f, "`skip` cannot be combined with other canon attributes"));
+            }
+            let key = attrs.rename.clone().unwrap_or_else(|| ident.to_string());
+            Ok(NamedField { ident, key, attrs })
+        })
+        .collect()
+}
+
+fn reject_field_attrs(fields: &Fields) -> syn::Result<()> {
+    if let Fields::Unnamed(u) = fields {
+        for f in &u.unnamed {
+            let a = parse_attrs(&f.attrs)?;
+            if a.rename.is_some() || a.skip || a.skip_if_none || a.scale.is_some() {
+                return Err(syn::Error::new_spanned(f, "canon attributes are only supported on named fields"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Builds a `Canon::Obj` from named fields reachable through `access(ident)`.
+fn to_obj(fields: &[NamedField], access: impl Fn(&Ident) -> TokenStream2) -> TokenStream2 {
+    let inserts = fields.iter().filter(|f| !f.attrs.skip).map(|f| {
+        let key = &f.key;
+        let v = access(&f.ident);
+        if let Some(scale) = &f.attrs.scale {
+            quote! { obj.insert(#key.to_string(), ::collapse_core::convert::scaled_to_canon(*#v as f64, #scale)); }
+        } else if f.attrs.skip_if_none {
+            quote! {
+                if let ::std::option::Option::Some(x) = #v {
+                    obj.insert(#key.to_string(), ::collapse_core::ToCanon::to_canon(x));
+                }
+            }
+        } else {
+            quote! { obj.insert(#key.to_string(), ::collapse_core::ToCanon::to_canon(#v)); }
+        }
+    });
+    quote! {{
+        let mut obj = ::std::collections::BTreeMap::new();
+        #(#inserts)*
+        ::collapse_core::Canon::Obj(obj)
+    }}
+}
+
+/// Decodes named fields from `obj`, rejecting keys the type does not declare.
+fn from_obj(fields: &[NamedField], ctor: TokenStream2) -> TokenStream2 {
+    let keys: Vec<&String> = fields.iter().filter(|f| !f.attrs.skip).map(|f| &f.key).collect();
+    let inits = fields.iter().map(|f| {
+        let ident = &f.ident;
+        let key = &f.key;
+        if f.attrs.skip {
+            quote! { #ident: ::std::default::Default::default() }
+        } else if let Some(scale) = &f.attrs.scale {
+            quote! {
+                #ident: ::collapse_core::convert::scaled_from_canon(
+                    obj.get(#key).unwrap_or(&::collapse_core::Canon::Null), #scale,
+                ).map_err(|e| e.at(#key))? as _
+            }
+        } else {
+            quote! { #ident: ::collapse_core::convert::obj_field(obj, #key)? }
+        }
+    });
+    quote! {{
+        const KEYS: &[&str] = &[#(#keys),*];
+        for k in obj.keys() {
+            if !KEYS.contains(&k.as_str()) {
+                return ::std::result::Result::Err(
+                    ::collapse_core::FromCanonError::new(::std::format!("unknown key {:?}", k)),
+                );
+            }
+        }
+        #ctor { #(#inits),* }
+    }}
+}
+
+fn expand_to_canon(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let generics = add_bound(&input.generics, parse_quote!(::collapse_core::ToCanon));
+    let (impl_g, ty_g, where_g) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(named) => {
+                let fields = named_fields(named)?;
+                to_obj(&fields, |id| quote!(&self.#id))
+            }
+            Fields::Unnamed(u) if u.unnamed.len() == 1 => {
+                reject_field_attrs(&s.fields)?;
+                quote!(::collapse_core::ToCanon::to_canon(&self.0))
+            }
+            Fields::Unnamed(u) => {
+                reject_field_attrs(&s.fields)?;
+                let idx = (0..u.unnamed.len()).map(syn::Index::from);
+                quote!(::collapse_core::Canon::Arr(vec![#(::collapse_core::ToCanon::to_canon(&self.#idx)),*]))
+            }
+            Fields::Unit => quote!(::collapse_core::Canon::Null),
+        },
+        Data::Enum(e) => {
+            let arms = e
+                .variants
+                .iter()
+                .map(|v| {
+                    let vident = &v.ident;
+                    let attrs = parse_attrs(&v.attrs)?;
+                    let tag = attrs.rename.unwrap_or_else(|| vident.to_string());
+                    let wrap = |inner: TokenStream2| {
+                        quote! {{
+                            let mut obj = ::std::collections::BTreeMap::new();
+                            obj.insert(#tag.to_string(), #inner);
+                            ::collapse_core::Canon::Obj(obj)
+                        }}
+                    };
+                    Ok(match &v.fields {
+                        Fields::Unit => quote!(Self::#vident => ::collapse_core::Canon::Str(#tag.to_string())),
+                        Fields::Unnamed(u) => {
+                            reject_field_attrs(&v.fields)?;
+                            let binds: Vec<Ident> = (0..u.unnamed.len()).map(|i| format_ident!("f{i}")).collect();
+                            let inner = if binds.len() == 1 {
+                                quote!(::collapse_core::ToCanon::to_canon(f0))
+                            } else {
+                                quote!(::collapse_core::Canon::Arr(vec![#(::collapse_core::ToCanon::to_canon(#binds)),*]))
+                            };
+                            let body = wrap(inner);
+                            quote!(Self::#vident(#(#binds),*) => #body)
+                        }
+                        Fields::Named(named) => {
+                            let fields = named_fields(named)?;
+                            let binds = fields.iter().map(|f| {
+                                let id = &f.ident;
+                                if f.attrs.skip { quote!(#id: _) } else { quote!(#id) }
+                            });
+                            let body = wrap(to_obj(&fields, |id| quote!(#id)));
+                            quote!(Self::#vident { #(#binds),* } => #body)
+                        }
+                    })
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote!(match self { #(#arms,)* })
+        }
+        Data::Union(_) => return Err(syn::Error::new_spanned(&input, "ToCanon cannot be derived for unions")),
+    };
+
+    Ok(quote! {
+        impl #impl_g ::collapse_core::ToCanon for #name #ty_g #where_g {
+            fn to_canon(&self) -> ::collapse_core::Canon {
+                #body
+            }
+        }
+    })
+}
+
+fn expand_from_canon(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let generics = add_bound(&input.generics, parse_quote!(::collapse_core::FromCanon));
+    let (impl_g, ty_g, where_g) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(named) => {
+                let fields = named_fields(named)?;
+                let build = from_obj(&fields, quote!(Self));
+                quote! {
+                    match v {
+                        ::collapse_core::Canon::Obj(obj) => ::std::result::Result::Ok(#build),
+                        _ => ::std::result::Result::Err(::collapse_core::FromCanonError::expected("object", v)),
+                    }
+                }
+            }
+            Fields::Unnamed(u) if u.unnamed.len() == 1 => {
+                reject_field_attrs(&s.fields)?;
+                quote!(::std::result::Result::Ok(Self(::collapse_core::FromCanon::from_canon(v)?)))
+            }
+            Fields::Unnamed(u) => {
+                reject_field_attrs(&s.fields)?;
+                let n = u.unnamed.len();
+                let expected = format!("{n}-element array");
+                let items = (0..n).map(|i| {
+                    let seg = format!("[{i}]");
+                    quote!(::collapse_core::FromCanon::from_canon(&xs[#i]).map_err(|e| e.at(#seg))?)
+                });
+                quote! {
+                    match v {
+                        ::collapse_core::Canon::Arr(xs) if xs.len() == #n => ::std::result::Result::Ok(Self(#(#items),*)),
+                        _ => ::std::result::Result::Err(::collapse_core::FromCanonError::expected(#expected, v)),
+                    }
+                }
+            }
+            Fields::Unit => quote! {
+                match v {
+                    ::collapse_core::Canon::Null => ::std::result::Result::Ok(Self),
+                    _ => ::std::result::Result::Err(::collapse_core::FromCanonError::expected("null", v)),
+                }
+            },
+        },
+        Data::Enum(e) => {
+            let mut unit_arms = Vec::new();
+            let mut obj_arms = Vec::new();
+            for var in &e.variants {
+                let vident = &var.ident;
+                let attrs = parse_attrs(&var.attrs)?;
+                let tag = attrs.rename.unwrap_or_else(|| vident.to_string());
+                match &var.fields {
+                    Fields::Unit => unit_arms.push(quote!(#tag => ::std::result::Result::Ok(Self::#vident))),
+                    Fields::Unnamed(u) if u.unnamed.len() == 1 => {
+                        reject_field_attrs(&var.fields)?;
+                        obj_arms.push(quote! {
+                            #tag => ::std::result::Result::Ok(Self::#vident(
+                                ::collapse_core::FromCanon::from_canon(inner).map_err(|e| e.at(#tag))?,
+                            ))
+                        });
+                    }
+                    Fields::Unnamed(u) => {
+                        reject_field_attrs(&var.fields)?;
+                        let n = u.unnamed.len();
+                        let expected = format!("{n}-element array");
+                        let items = (0..n).map(|i| {
+                            let seg = format!("{tag}[{i}]");
+                            quote!(::collapse_core::FromCanon::from_canon(&xs[#i]).map_err(|e| e.at(#seg))?)
+                        });
+                        obj_arms.push(quote! {
+                            #tag => match inner {
+                                ::collapse_core::Canon::Arr(xs) if xs.len() == #n => ::std::result::Result::Ok(Self::#vident(#(#items),*)),
+                                _ => ::std::result::Result::Err(
+                                    ::collapse_core::FromCanonError::expected(#expected, inner).at(#tag),
+                                ),
+                            }
+                        });
+                    }
+                    Fields::Named(named) => {
+                        let fields = named_fields(named)?;
+                        let build = from_obj(&fields, quote!(Self::#vident));
+                        obj_arms.push(quote! {
+                            #tag => match inner {
+                                ::collapse_core::Canon::Obj(obj) => (|| -> ::std::result::Result<Self, ::collapse_core::FromCanonError> {
+                                    ::std::result::Result::Ok(#build)
+                                })().map_err(
+                                    |e: ::collapse_core::FromCanonError| e.at(#tag),
+                                ),
+                                _ => ::std::result::Result::Err(::collapse_core::FromCanonError::expected("object", inner).at(#tag)),
+                            }
+                        });
+                    }
+                }
+            }
+            quote! {
+                let unknown = |t: &str| ::collapse_core::FromCanonError::new(::std::format!("unknown variant {:?}", t));
+                match v {
+                    ::collapse_core::Canon::Str(t) => match t.as_str() {
+                        #(#unit_arms,)*
+                        _ => ::std::result::Result::Err(unknown(t)),
+                    },
+                    ::collapse_core::Canon::Obj(m) if m.len() == 1 => {
+                        let (t, inner) = m.iter().next().unwrap();
+                        match t.as_str() {
+                            #(#obj_arms,)*
+                            _ => ::std::result::Result::Err(unknown(t)),
+                        }
+                    }
+                    _ => ::std::result::Result::Err(::collapse_core::FromCanonError::expected("string or single-key object", v)),
+                }
+            }
+        }
+        Data::Union(_) => return Err(syn::Error::new_spanned(&input, "FromCanon cannot be derived for unions")),
+    };
+
+    Ok(quote! {
+        impl #impl_g ::collapse_core::FromCanon for #name #ty_g #where_g {
+            fn from_canon(v: &::collapse_core::Canon) -> ::std::result::Result<Self, ::collapse_core::FromCanonError> {
+                #body
+            }
+        }
+    })
+}
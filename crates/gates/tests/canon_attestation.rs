@@ -0,0 +1,161 @@
+mod common;
+
+use collapse_core::{
+    AttestationError, BundleError, CHAIN_SUBJECT_NAME, Canon, CertBundle, CertSigner, ChainMode, DsseEnvelope,
+    DsseSignature, HashAlg, IN_TOTO_PAYLOAD_TYPE, KernelCert, SchemaRegistry, SignatureError, TrustStore, canon_bytes,
+    dsse_pae, export_attestation, import_attestation, in_toto_statement, parse_canon,
+};
+
+use common::kernels;
+
+fn bundle() -> CertBundle {
+    common::bundle().with_meta("generator", "gate")
+}
+
+fn trust() -> TrustStore {
+    let mut t = TrustStore::new();
+    t.add("ci", CertSigner::from_seed([1; 32]).public_key());
+    t
+}
+
+/// Re-sign an envelope whose statement was edited, so only the content checks can catch it.
+fn resigned(statement: &Canon) -> DsseEnvelope {
+    let mut env = DsseEnvelope {
+        payload_type: IN_TOTO_PAYLOAD_TYPE.to_string(),
+        payload: canon_bytes(statement),
+        signatures: Vec::new(),
+    };
+    env.sign(&CertSigner::from_seed([1; 32]));
+    env
+}
+
+#[test]
+fn gate_attestation_pae_matches_dsse_spec() {
+    let pae = dsse_pae("http://example.com/HelloWorld", b"hello world");
+    assert_eq!(pae, b"DSSEv1 29 http://example.com/HelloWorld 11 hello world");
+}
+
+#[test]
+fn gate_attestation_statement_shape_and_round_trip() {
+    let b = bundle();
+    let Canon::Obj(st) = in_toto_statement(&b) else { unreachable!() };
+    assert_eq!(st["_type"], Canon::Str("https://in-toto.io/Statement/v1".into()));
+    let Canon::Arr(subjects) = &st["subject"] else { unreachable!() };
+    assert_eq!(subjects.len(), 3);
+    let Canon::Obj(first) = &subjects[0] else { unreachable!() };
+    assert_eq!(first["name"], Canon::Str("asc7".into()));
+    let Canon::Obj(set) = &first["digest"] else { unreachable!() };
+    assert_eq!(set["sha256"], Canon::Str(b.certs[0].kernel_hash.hex()));
+
+    let env = export_attestation(&b, &[&CertSigner::from_seed([1; 32])]);
+    let json = env.to_json();
+    let pretty = serde_json::to_string_pretty(&serde_json::from_str::<serde_json::Value>(&json).unwrap()).unwrap();
+    let back = DsseEnvelope::from_json(pretty.as_bytes()).unwrap();
+    assert_eq!(back, env);
+    let imported = import_attestation(&back, &trust(), 1, kernels().schemas()).unwrap();
+    assert_eq!(imported.chain.chain_hash, b.chain.chain_hash);
+    assert_eq!(imported.meta["generator"], "gate");
+
+    // Another producer's layout: pretty-printed rather than canonical.
+    let mut other = env.clone();
+    let statement: serde_json::Value = serde_json::from_slice(&env.payload).unwrap();
+    other.payload = serde_json::to_vec_pretty(&statement).unwrap();
+    assert_ne!(other.payload, env.payload);
+    other.signatures.clear();
+    other.sign(&CertSigner::from_seed([1; 32]));
+    let imported = import_attestation(&other, &trust(), 1, kernels().schemas()).unwrap();
+    assert_eq!(imported.chain.chain_hash, b.chain.chain_hash);
+
+    other.payload = b"{\"_type\": 1.5}".to_vec();
+    other.signatures.clear();
+    other.sign(&CertSigner::from_seed([1; 32]));
+    let err = import_attestation(&other, &trust(), 1, kernels().schemas());
+    assert!(matches!(err, Err(AttestationError::StatementParse(_))));
+}
+
+#[test]
+fn gate_attestation_envelope_signatures() {
+    let b = bundle();
+    let unsigned = export_attestation(&b, &[]);
+    assert!(matches!(
+        import_attestation(&unsigned, &trust(), 1, kernels().schemas()),
+        Err(AttestationError::Signature(SignatureError::BelowThreshold { required: 1, .. }))
+    ));
+    let outsider = export_attestation(&b, &[&CertSigner::from_seed([2; 32])]);
+    assert!(import_attestation(&outsider, &trust(), 1, kernels().schemas()).is_err());
+
+    let mut swapped = export_attestation(&b, &[&CertSigner::from_seed([1; 32])]);
+    swapped.payload = canon_bytes(&in_toto_statement(&b.clone().with_meta("generator", "evil")));
+    assert!(matches!(
+        import_attestation(&swapped, &trust(), 1, kernels().schemas()),
+        Err(AttestationError::Signature(SignatureError::BelowThreshold { .. }))
+    ));
+
+    // Unparseable, untrusted and invalid signatures are skipped, not fatal.
+    let mut noisy = export_attestation(&b, &[&CertSigner::from_seed([2; 32])]);
+    noisy.signatures.push(DsseSignature { keyid: "not a key".into(), sig: vec![0; 64] });
+    let keyid = CertSigner::from_seed([1; 32]).public_key().to_string();
+    noisy.signatures.push(DsseSignature { keyid, sig: vec![] });
+    assert!(noisy.verify_trusted(&trust(), 1).is_err());
+    noisy.sign(&CertSigner::from_seed([1; 32]));
+    assert_eq!(noisy.verify_trusted(&trust(), 1).unwrap(), ["ci"]);
+
+    let mut retyped = export_attestation(&b, &[&CertSigner::from_seed([1; 32])]);
+    retyped.payload_type = "application/json".into();
+    let imported = import_attestation(&retyped, &trust(), 1, kernels().schemas());
+    assert!(matches!(imported, Err(AttestationError::UnsupportedType(_))));
+}
+
+#[test]
+fn gate_attestation_rechecks_subjects_against_kernel_hashes() {
+    let b = bundle();
+    let statement = || {
+        let Canon::Obj(st) = parse_canon(&canon_bytes(&in_toto_statement(&b))).unwrap() else { unreachable!() };
+        st
+    };
+
+    let mut st = statement();
+    let Some(Canon::Arr(subjects)) = st.get_mut("subject") else { unreachable!() };
+    let Canon::Obj(s0) = &mut subjects[0] else { unreachable!() };
+    s0.insert("digest".into(), parse_canon(format!("{{\"sha256\":\"{}\"}}", "00".repeat(32)).as_bytes()).unwrap());
+    assert!(matches!(
+        import_attestation(&resigned(&Canon::Obj(st)), &trust(), 1, kernels().schemas()),
+        Err(AttestationError::SubjectMismatch { name, .. }) if name == "asc7"
+    ));
+
+    let mut st = statement();
+    let Some(Canon::Arr(subjects)) = st.get_mut("subject") else { unreachable!() };
+    subjects.pop();
+    assert!(matches!(
+        import_attestation(&resigned(&Canon::Obj(st)), &trust(), 1, kernels().schemas()),
+        Err(AttestationError::MissingSubject { name }) if name == "collapse:cert-chain"
+    ));
+
+    // A predicate cert whose payload no longer matches its kernel_hash.
+    let mut st = statement();
+    let Some(Canon::Obj(pred)) = st.get_mut("predicate") else { unreachable!() };
+    let Some(Canon::Arr(certs)) = pred.get_mut("certs") else { unreachable!() };
+    let Canon::Obj(c0) = &mut certs[0] else { unreachable!() };
+    let Some(Canon::Obj(payload)) = c0.get_mut("payload") else { unreachable!() };
+    payload.insert("profile_name".into(), Canon::Str("lenient".into()));
+    assert!(matches!(
+        import_attestation(&resigned(&Canon::Obj(st)), &trust(), 1, kernels().schemas()),
+        Err(AttestationError::Bundle(BundleError::Cert { index: 0, .. }))
+    ));
+
+    // Subject names must identify one cert, or the chain, each.
+    let certs = common::bundle().certs;
+    let twice = CertBundle::from_certs(vec![certs[0].clone(), certs[0].clone()], ChainMode::Flat, HashAlg::Sha256);
+    let env = export_attestation(&twice, &[&CertSigner::from_seed([1; 32])]);
+    assert!(matches!(
+        import_attestation(&env, &trust(), 1, kernels().schemas()),
+        Err(AttestationError::AmbiguousSubject { name }) if name == "asc7"
+    ));
+    let chain_named = KernelCert::new_unchecked(CHAIN_SUBJECT_NAME, "1.0.0", certs[0].payload.clone());
+    let chain_named = CertBundle::from_certs(vec![chain_named], ChainMode::Flat, HashAlg::Sha256);
+    let env = export_attestation(&chain_named, &[&CertSigner::from_seed([1; 32])]);
+    assert!(matches!(
+        import_attestation(&env, &trust(), 1, &SchemaRegistry::new().allow_unregistered()),
+        Err(AttestationError::AmbiguousSubject { name }) if name == CHAIN_SUBJECT_NAME
+    ));
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use asc7::Asc7Profile;
+use collapse_core::{
+    BundleError, Canon, CanonMode, CertBundle, CertItem, CertLoadError, ChainMode, HashAlg, KernelCert, Rational,
+    SchemaRegistry, cbor_bytes, read_bundle, write_bundle,
+};
+use sembit::sembit_kernel_cert;
+
+use common::kernels;
+
+fn spine_bundle(mode: ChainMode) -> CertBundle {
+    let mut certs = common::certs(Asc7Profile::code_safe());
+    let d = HashAlg::Sha256.hash(b"x");
+    certs.push(sembit_kernel_cert(&certs[0], &certs[1], &d, &d, 3, 1.5, &d).unwrap());
+    CertBundle::from_certs(certs, mode, HashAlg::Sha256).with_meta("run", "gate")
+}
+
+#[test]
+fn gate_bundle_round_trips_bytes() {
+    for mode in [ChainMode::Flat, ChainMode::Merkle, ChainMode::Linked] {
+        let b = spine_bundle(mode);
+        let bytes = b.to_bytes();
+        let back = CertBundle::from_bytes(&bytes, kernels().schemas()).unwrap();
+        assert_eq!(back.to_bytes(), bytes);
+        assert_eq!(back.chain.chain_hash, b.chain.chain_hash);
+        assert_eq!(back.chain.mode, mode);
+        assert_eq!(back.meta["run"], "gate");
+        assert_eq!(back.cert("sembit").unwrap().deps.len(), 2);
+    }
+}
+
+#[test]
+fn gate_bundle_rejects_tampering() {
+    let b = spine_bundle(ChainMode::Flat);
+    let text = String::from_utf8(b.to_bytes()).unwrap();
+    let edited = text.replace("\"witness_len\":89", "\"witness_len\":90");
+    assert_ne!(edited, text);
+    match CertBundle::from_bytes(edited.as_bytes(), kernels().schemas()) {
+        Err(BundleError::Cert { index: 0, error: CertLoadError::HashMismatch { .. } }) => {}
+        other => panic!("unexpected {other:?}"),
+    }
+
+    let mut extra = b.clone();
+    extra.certs.push(KernelCert::new_unchecked("stray", "1.0.0", Canon::U64(1)));
+    assert!(matches!(extra.verify(), Err(BundleError::CertNotInChain { name }) if name == "stray"));
+
+    let mut missing = b.clone();
+    missing.certs.remove(1);
+    assert!(matches!(missing.verify(), Err(BundleError::ItemWithoutCert { name }) if name == "asc7_confusables"));
+
+    let mut chain = b.clone();
+    chain.chain.items.swap(0, 1);
+    assert!(matches!(chain.verify(), Err(BundleError::Chain(_))));
+
+    let future = text.replace("collapse/bundle/v1", "collapse/bundle/v9");
+    let load = |bytes: &[u8]| CertBundle::from_bytes(bytes, kernels().schemas());
+    assert!(matches!(load(future.as_bytes()), Err(BundleError::UnsupportedFormat(_))));
+    assert!(matches!(load(b"{\"certs\": []}"), Err(BundleError::Parse(_))));
+}
+
+#[test]
+fn gate_bundle_keeps_typed_payloads() {
+    let mut o = BTreeMap::new();
+    o.insert("n".to_string(), Canon::I64(5));
+    o.insert("r".to_string(), Canon::Rat(Rational::new(1, 3)));
+    let payload = Canon::Obj(o);
+    let v2 = KernelCert::new_unchecked("typed", "2.0.0", payload.clone()).with_canon_mode(CanonMode::V2);
+    let cbor = KernelCert::new_unchecked("typed_cbor", "1.0.0", payload.clone()).with_canon_mode(CanonMode::Cbor);
+    let b = CertBundle::from_certs(vec![v2, cbor], ChainMode::Flat, HashAlg::Sha256);
+
+    let back = CertBundle::from_bytes(&b.to_bytes(), &SchemaRegistry::new().allow_unregistered()).unwrap();
+    assert_eq!(back.certs[0].payload, payload);
+    // CBOR does not record integer width either; the encoded bytes are what survive.
+    assert_eq!(cbor_bytes(&back.certs[1].payload), cbor_bytes(&payload));
+    assert!(String::from_utf8(b.to_bytes()).unwrap().contains("payload_bytes"));
+
+    // v1 writes a Rat as its object form and reloads it as that object, with the same
+    // hash. Only the explicit v1-compat conversion makes it a Rat again.
+    let rat = Canon::Rat(Rational::new(1, 3));
+    let v1 = KernelCert::new_unchecked("typed_v1", "1.0.0", rat.clone());
+    let b = CertBundle::from_certs(vec![v1.clone()], ChainMode::Flat, HashAlg::Sha256);
+    let back = CertBundle::from_bytes(&b.to_bytes(), &SchemaRegistry::new().allow_unregistered()).unwrap();
+    assert!(matches!(back.certs[0].payload, Canon::Obj(_)));
+    assert_ne!(back.certs[0].payload, rat);
+    assert_eq!(back.certs[0].payload.exact_from_v1_compat(), rat);
+    assert_eq!(back.certs[0].kernel_hash, v1.kernel_hash);
+}
+
+#[test]
+fn gate_bundle_file_io() {
+    let path = std::env::temp_dir().join(format!("collapse_gate_bundle_{}.json", std::process::id()));
+    let b = spine_bundle(ChainMode::Linked);
+    write_bundle(&path, &b).unwrap();
+    let back = read_bundle(&path, kernels().schemas()).unwrap();
+    // Nothing registered by an earlier load carries over: a fresh registry knows no
+    // kernels, so does not let the pinned asc7 cert load as legacy.
+    let fresh = read_bundle(&path, &SchemaRegistry::new());
+    assert!(matches!(fresh, Err(BundleError::Cert { index: 0, error: CertLoadError::LegacyScheme { .. } })));
+    assert!(read_bundle(&path, kernels().schemas()).is_ok());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(back.chain.links, b.chain.links);
+    assert_eq!(back.chain.items, b.certs.iter().map(CertItem::from_cert).collect::<Vec<_>>());
+    assert!(matches!(read_bundle(&path, kernels().schemas()), Err(BundleError::Io(_))));
+}
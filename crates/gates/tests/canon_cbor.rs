@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use asc7::{Asc7Profile, asc7_kernel_cert};
+use asc7::confusables::confusables_kernel_cert;
+use collapse_core::{
+    Canon, CanonMode, CanonParseErrorKind, CertHashScheme, canon_bytes, cbor_bytes, cbor_to_v1_bytes, parse_cbor,
+    sha256_bytes, v1_bytes_to_cbor,
+};
+
+fn obj(entries: &[(&str, Canon)]) -> Canon {
+    Canon::Obj(entries.iter().map(|(k, v)| (k.to_string(), v.clone())).collect::<BTreeMap<_, _>>())
+}
+
+#[test]
+fn gate_cbor_rfc8949_vectors() {
+    let cases: Vec<(Canon, &str)> = vec![
+        (Canon::U64(0), "00"),
+        (Canon::U64(23), "17"),
+        (Canon::U64(24), "1818"),
+        (Canon::I64(100), "1864"),
+        (Canon::U64(1000), "1903e8"),
+        (Canon::U64(1_000_000), "1a000f4240"),
+        (Canon::U64(1_000_000_000_000), "1b000000e8d4a51000"),
+        (Canon::I64(-1), "20"),
+        (Canon::I64(-100), "3863"),
+        (Canon::I64(-1000), "3903e7"),
+        (Canon::Str(String::new()), "60"),
+        (Canon::Str("IETF".to_string()), "6449455446"),
+        (Canon::Str("\u{6c34}".to_string()), "63e6b0b4"),
+        (Canon::Arr(vec![Canon::U64(1), Canon::U64(2), Canon::U64(3)]), "83010203"),
+        (obj(&[("a", Canon::U64(1)), ("b", Canon::Arr(vec![Canon::U64(2), Canon::U64(3)]))]), "a26161016162820203"),
+        (Canon::Arr(vec![Canon::Bool(false), Canon::Bool(true), Canon::Null]), "83f4f5f6"),
+        // Shorter encoded keys sort first, unlike BTreeMap order.
+        (obj(&[("aa", Canon::Null), ("b", Canon::Null)]), "a26162f6626161f6"),
+    ];
+    for (v, hex) in cases {
+        let bytes = cbor_bytes(&v);
+        assert_eq!(hex::encode(&bytes), hex, "value={v:?}");
+        let back = parse_cbor(&bytes).unwrap();
+        assert_eq!(canon_bytes(&back), canon_bytes(&v));
+    }
+}
+
+#[test]
+fn gate_cbor_rejects_non_deterministic() {
+    let cases: &[(&str, usize, CanonParseErrorKind)] = &[
+        ("1817", 0, CanonParseErrorKind::NonShortestHead),
+        ("9f01ff", 0, CanonParseErrorKind::UnexpectedByte(0x9f)),
+        ("f93c00", 0, CanonParseErrorKind::UnexpectedByte(0xf9)),
+        ("c11a514b67b0", 0, CanonParseErrorKind::UnexpectedByte(0xc1)),
+        ("4101", 0, CanonParseErrorKind::UnexpectedByte(0x41)),
+        ("a2626161f66162f6", 5, CanonParseErrorKind::UnsortedKey),
+        ("a26161f66161f6", 4, CanonParseErrorKind::DuplicateKey),
+        ("a101f6", 1, CanonParseErrorKind::UnexpectedByte(0x01)),
+        ("0000", 1, CanonParseErrorKind::TrailingBytes),
+        ("3b8000000000000000", 0, CanonParseErrorKind::IntegerOverflow),
+        ("62ff00", 1, CanonParseErrorKind::InvalidUtf8),
+    ];
+    for (hex_in, offset, kind) in cases {
+        let e = parse_cbor(&hex::decode(hex_in).unwrap()).unwrap_err();
+        assert_eq!((e.offset, &e.kind), (*offset, kind), "input={hex_in}");
+    }
+}
+
+#[test]
+fn gate_cbor_lossless_with_v1_and_cert_mode() {
+    for cert in [asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap(), confusables_kernel_cert().unwrap()] {
+        let v1 = canon_bytes(&cert.payload);
+        let cbor = v1_bytes_to_cbor(&v1).unwrap();
+        assert_eq!(cbor, cbor_bytes(&cert.payload));
+        assert_eq!(cbor_to_v1_bytes(&cbor).unwrap(), v1);
+
+        let c = cert.with_canon_mode(CanonMode::Cbor).with_hash_scheme(CertHashScheme::Legacy).unwrap();
+        assert_eq!(c.kernel_hash, sha256_bytes(&cbor));
+        assert_eq!(CanonMode::from_tag(c.canon_mode.tag()), Some(CanonMode::Cbor));
+    }
+}
@@ -0,0 +1,109 @@
+use asc7::{Asc7Profile, SemanticPredicateDef, asc7_kernel_cert, asc7_semantic_kernel_cert};
+use asc7::confusables::confusables_kernel_cert;
+use collapse_core::{
+    Canon, CertChain, CertGraph, CertGraphError, CertHashScheme, CertItem, CertLoadError, HashAlg, KernelCert,
+    KernelRegistry, SchemaRegistry,
+};
+use sembit::{register_sembit_kernels, sembit_kernel_cert};
+
+fn spine() -> Vec<KernelCert> {
+    let asc7 = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap();
+    let conf = confusables_kernel_cert().unwrap();
+    let d = HashAlg::Sha256.hash(b"x");
+    let sembit = sembit_kernel_cert(&asc7, &conf, &d, &d, 3, 1.5, &d).unwrap();
+    vec![asc7, conf, sembit]
+}
+
+#[test]
+fn gate_cert_graph_deps_declared_and_loaded() {
+    let certs = spine();
+    let sembit = &certs[2];
+    let names: Vec<&str> = sembit.deps.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, ["asc7", "asc7_confusables"]);
+    assert_eq!(sembit.deps[0].hash, certs[0].kernel_hash);
+    assert!(certs[0].deps.is_empty());
+
+    let mut kernels = KernelRegistry::new();
+    register_sembit_kernels(&mut kernels).unwrap();
+    let back = KernelCert::from_canon(&sembit.to_canon(), kernels.schemas()).unwrap();
+    assert_eq!(back.deps, sembit.deps);
+    let Canon::Obj(form) = certs[0].to_canon() else { panic!() };
+    assert!(!form.contains_key("deps"));
+
+    let sem = asc7_semantic_kernel_cert(&certs[0], Vec::<SemanticPredicateDef>::new()).unwrap();
+    assert_eq!(sem.deps, vec![CertItem::from_cert(&certs[0])]);
+}
+
+#[test]
+fn gate_cert_graph_validates_against_chain() {
+    let certs = spine();
+    let chain = CertChain::build(certs.iter().map(CertItem::from_cert).collect());
+    let graph = CertGraph::new(certs.clone());
+    assert!(graph.validate(&chain).is_ok());
+    let order: Vec<&str> = graph.topo_order().unwrap().iter().map(|c| c.kernel_name.as_str()).collect();
+    assert_eq!(order.last(), Some(&"sembit"));
+
+    // Drop confusables and swap in a re-hashed asc7.
+    let other = certs[0].with_hash_alg(HashAlg::Blake3);
+    let chain = CertChain::build(vec![CertItem::from_cert(&other), CertItem::from_cert(&certs[2])]);
+    let errs = graph.validate(&chain).unwrap_err();
+    assert_eq!(errs.len(), 2);
+    assert!(matches!(&errs[0], CertGraphError::DepHashMismatch { dep, found, .. }
+        if dep == "asc7" && *found == other.kernel_hash));
+    assert_eq!(errs[1], CertGraphError::MissingDep { kernel: "sembit".into(), dep: "asc7_confusables".into() });
+}
+
+#[test]
+fn gate_cert_graph_envelope_hash_covers_deps() {
+    let base = KernelCert::new_unchecked("base", "1.0.0", Canon::U64(1));
+    let plain = KernelCert::new_unchecked("top", "1.0.0", Canon::U64(2));
+    let with = plain.with_deps(vec![CertItem::from_cert(&base)]);
+    assert_ne!(with.kernel_hash, plain.kernel_hash);
+    assert_eq!(with.with_deps(vec![]).kernel_hash, plain.kernel_hash);
+
+    let mut forged = with.clone();
+    forged.deps[0].hash = HashAlg::Sha256.hash(b"other");
+    assert!(forged.verify().is_err());
+
+    // Legacy certs hash the payload alone, so their deps are unbound until the
+    // kernel reads them from the payload.
+    let legacy =
+        KernelCert::new_unchecked("top", "1.0.0", Canon::U64(2)).with_hash_scheme(CertHashScheme::Legacy).unwrap();
+    let unbound = legacy.with_deps(vec![CertItem::from_cert(&base)]);
+    assert_eq!(unbound.kernel_hash, legacy.kernel_hash);
+    assert!(unbound.verify().is_ok());
+    assert_eq!(unbound.unbound_deps().count(), 1);
+    assert!(CertGraph::new(vec![base, unbound.clone()]).to_dot().contains("\"base\" -> \"top\" [style=dotted];"));
+    let mut schemas = SchemaRegistry::new().allow_unregistered();
+    schemas.allow_legacy("top", "1.0.0", |_| Ok(Vec::new()));
+    let err = KernelCert::from_canon(&unbound.to_canon(), &schemas).unwrap_err();
+    assert!(matches!(err, CertLoadError::UnboundDep { name } if name == "base"));
+
+    // The sembit payload holds both upstream hashes; each dep must match its own field.
+    let mut kernels = KernelRegistry::new();
+    register_sembit_kernels(&mut kernels).unwrap();
+    let sembit = spine().remove(2);
+    assert!(KernelCert::from_canon(&sembit.to_canon(), kernels.schemas()).is_ok());
+    let mut swapped = sembit.clone();
+    swapped.deps[0].hash = sembit.deps[1].hash.clone();
+    assert!(swapped.verify().is_ok());
+    let err = KernelCert::from_canon(&swapped.to_canon(), kernels.schemas()).unwrap_err();
+    assert!(matches!(err, CertLoadError::UnboundDep { name } if name == "asc7"));
+}
+
+#[test]
+fn gate_cert_graph_cycles_and_dot() {
+    let a = KernelCert::new_unchecked("a", "1.0.0", Canon::U64(1));
+    let b = KernelCert::new_unchecked("b", "1.0.0", Canon::U64(2)).with_deps(vec![CertItem::from_cert(&a)]);
+    let a = a.with_deps(vec![CertItem::from_cert(&b)]);
+    let c = KernelCert::new_unchecked("c", "1.0.0", Canon::U64(3));
+    let graph = CertGraph::new(vec![a, b, c]);
+    assert_eq!(graph.topo_order().unwrap_err(), CertGraphError::Cycle(vec!["a".into(), "b".into()]));
+
+    let dot = CertGraph::new(spine()).to_dot();
+    assert!(dot.starts_with("digraph certs {"));
+    // sembit is legacy-hashed, so its deps are drawn unbound.
+    assert!(dot.contains("\"asc7\" -> \"sembit\" [style=dotted];"));
+    assert!(dot.contains("\"asc7_confusables\" -> \"sembit\" [style=dotted];"));
+    assert!(dot.contains("[label=\"sembit@1.0.0\\nsha256:"));
+}
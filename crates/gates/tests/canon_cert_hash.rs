@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+
+use asc7::{Asc7Profile, asc7_kernel_cert, register_asc7_kernels};
+use collapse_core::{
+    CERT_HASH_DOMAIN_V1, Canon, CertHashScheme, CertLoadError, KernelCert, KernelRegistry, SchemaRegistry,
+    sha256_bytes,
+};
+
+fn payload() -> Canon {
+    let mut o = BTreeMap::new();
+    o.insert("x".to_string(), Canon::U64(1));
+    Canon::Obj(o)
+}
+
+#[test]
+fn gate_cert_hash_envelope_commits_to_name_and_version() {
+    // Legacy: payload only, so these collide.
+    let legacy = |name, version| {
+        KernelCert::new_unchecked(name, version, payload()).with_hash_scheme(CertHashScheme::Legacy).unwrap()
+    };
+    assert_eq!(legacy("k1", "1.0.0").kernel_hash, legacy("k2", "2.0.0").kernel_hash);
+
+    let a = KernelCert::new_unchecked("k1", "1.0.0", payload());
+    let b = KernelCert::new_unchecked("k2", "1.0.0", payload());
+    let c = KernelCert::new_unchecked("k1", "2.0.0", payload());
+    assert_ne!(a.kernel_hash, b.kernel_hash);
+    assert_ne!(a.kernel_hash, c.kernel_hash);
+    assert_ne!(a.kernel_hash, legacy("k1", "1.0.0").kernel_hash);
+
+    let mut pre = CERT_HASH_DOMAIN_V1.as_bytes().to_vec();
+    pre.push(0);
+    pre.extend(br#"{"canon_mode":"v1","kernel_name":"k1","kernel_version":"1.0.0","payload":{"x":1}}"#);
+    assert_eq!(a.kernel_hash, sha256_bytes(&pre));
+}
+
+#[test]
+fn gate_cert_hash_verify_detects_tampering() {
+    let legacy = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap();
+    assert!(legacy.verify().is_ok());
+    let env = legacy.with_hash_scheme(CertHashScheme::EnvelopeV1).unwrap();
+    assert!(env.verify().is_ok());
+
+    let mut renamed = env.clone();
+    renamed.kernel_version = "9.0.0".to_string();
+    assert!(matches!(renamed.verify(), Err(CertLoadError::HashMismatch { .. })));
+    // The legacy scheme cannot see a version change.
+    let mut renamed = legacy.clone();
+    renamed.kernel_version = "9.0.0".to_string();
+    assert!(renamed.verify().is_ok());
+
+    let mut edited = env.clone();
+    if let Canon::Obj(m) = &mut edited.payload {
+        m.insert("witness_len".to_string(), Canon::U64(0));
+    }
+    assert!(edited.verify().is_err());
+}
+
+#[test]
+fn gate_cert_hash_scheme_survives_round_trip() {
+    let mut kernels = KernelRegistry::new();
+    register_asc7_kernels(&mut kernels).unwrap();
+    let env = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap();
+    let env = env.with_hash_scheme(CertHashScheme::EnvelopeV1).unwrap();
+    let back = KernelCert::from_canon(&env.to_canon(), kernels.schemas()).unwrap();
+    assert_eq!(back.hash_scheme, CertHashScheme::EnvelopeV1);
+    assert_eq!(back.kernel_hash, env.kernel_hash);
+
+    // The pinned asc7 kernel opts into legacy; other kernels do not.
+    let legacy = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap();
+    let back = KernelCert::from_canon(&legacy.to_canon(), kernels.schemas()).unwrap();
+    assert_eq!(back.hash_scheme, CertHashScheme::Legacy);
+    let other = KernelCert::new_unchecked("k1", "1.0.0", payload()).with_hash_scheme(CertHashScheme::Legacy).unwrap();
+    let mut schemas = SchemaRegistry::new().allow_unregistered();
+    let err = KernelCert::from_canon(&other.to_canon(), &schemas).unwrap_err();
+    assert_eq!(err.to_string(), "legacy hash scheme is not allowed for k1@1.0.0");
+    schemas.allow_legacy("k1", "1.0.0", |_| Ok(Vec::new()));
+    assert!(KernelCert::from_canon(&other.to_canon(), &schemas).is_ok());
+
+    // A cert must say which scheme it uses.
+    let mut unnamed = legacy.to_canon();
+    if let Canon::Obj(m) = &mut unnamed {
+        m.remove("hash_scheme");
+    }
+    let err = KernelCert::from_canon(&unnamed, kernels.schemas()).unwrap_err();
+    assert!(matches!(err, CertLoadError::Malformed(_)), "{err}");
+}
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use std::panic::catch_unwind;
+
+use asc7::{Asc7KernelCert, Asc7Profile, Asc7SemanticKernelCert, SemanticPredicateDef};
+use collapse_core::{Canon, FromCanon, HashAlg, ToCanon, canon_bytes};
+use sembit::SembitKernelPayload;
+
+#[derive(Clone, Debug, PartialEq, ToCanon, FromCanon)]
+struct Sample {
+    #[canon(rename = "kernel")]
+    name: String,
+    #[canon(skip)]
+    cache: Vec<u8>,
+    maybe: Option<u64>,
+    #[canon(skip_if_none)]
+    absent: Option<i64>,
+    #[canon(scale = 1000)]
+    ratio: f64,
+    mode: Mode,
+}
+
+#[derive(Clone, Debug, PartialEq, ToCanon, FromCanon)]
+enum Mode {
+    Off,
+    #[canon(rename = "lvl")]
+    Level(u8),
+    Pair(i64, String),
+    Window { lo: i64, hi: i64 },
+}
+
+#[test]
+fn gate_derive_attributes_and_roundtrip() {
+    let s = Sample {
+        name: "asc7".to_string(),
+        cache: vec![1, 2, 3],
+        maybe: None,
+        absent: None,
+        ratio: 1.5849625,
+        mode: Mode::Window { lo: -1, hi: 4 },
+    };
+    let c = s.to_canon();
+    assert_eq!(
+        String::from_utf8(canon_bytes(&c)).unwrap(),
+        "{\"kernel\":\"asc7\",\"maybe\":null,\"mode\":{\"Window\":{\"hi\":4,\"lo\":-1}},\"ratio\":1585}"
+    );
+
+    let back = Sample::from_canon(&c).unwrap();
+    assert_eq!(back, Sample { cache: vec![], ratio: 1.585, ..s });
+
+    for m in [Mode::Off, Mode::Level(3), Mode::Pair(-2, "x".to_string())] {
+        assert_eq!(Mode::from_canon(&m.to_canon()).unwrap(), m);
+    }
+    assert_eq!(Mode::Level(3).to_canon(), {
+        let mut o = BTreeMap::new();
+        o.insert("lvl".to_string(), Canon::U64(3));
+        Canon::Obj(o)
+    });
+}
+
+#[test]
+fn gate_derive_errors_carry_path() {
+    let mut p = SemanticPredicateDef {
+        bit_index: 0, id: 1, kind: "intrinsic".to_string(), name: "n".to_string(), resource_hash: None,
+    }
+    .to_canon();
+    if let Canon::Obj(m) = &mut p {
+        m.insert("name".to_string(), Canon::U64(7));
+    }
+    let sem = Asc7SemanticKernelCert {
+        ablation_rule: String::new(),
+        base_kernel_hash: HashAlg::Sha256.hash(b""),
+        filter_rule: String::new(),
+        predicate_registry_version: String::new(),
+        predicates: vec![],
+    };
+    let mut c = sem.to_canon();
+    if let Canon::Obj(m) = &mut c {
+        m.insert("predicates".to_string(), Canon::Arr(vec![p]));
+    }
+    let e = Asc7SemanticKernelCert::from_canon(&c).unwrap_err();
+    assert_eq!(e.path, "predicates[0].name");
+
+    let mut extra = Asc7KernelCert::from_profile(&Asc7Profile::code_safe()).to_canon();
+    if let Canon::Obj(m) = &mut extra {
+        m.insert("surprise".to_string(), Canon::Null);
+    }
+    assert!(Asc7KernelCert::from_canon(&extra).unwrap_err().msg.contains("surprise"));
+}
+
+#[test]
+fn gate_derive_sembit_payload_scale() {
+    let d = HashAlg::Sha256.hash(b"");
+    let p = SembitKernelPayload {
+        asc7_graph_hash: d.clone(),
+        confusables_graph_hash: d.clone(),
+        tests_hash: d.clone(),
+        domain_digest: d.clone(),
+        classes: 6,
+        h_sem_bits: (6f64).log2(),
+        quotient_digest: d,
+    };
+    match p.to_canon() {
+        Canon::Obj(m) => assert_eq!(m.get("h_sem_microbits"), Some(&Canon::I64(2_584_963))),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn gate_derive_scale_rejects_non_finite() {
+    let d = HashAlg::Sha256.hash(b"");
+    let payload = |h_sem_bits: f64| SembitKernelPayload {
+        asc7_graph_hash: d.clone(),
+        confusables_graph_hash: d.clone(),
+        tests_hash: d.clone(),
+        domain_digest: d.clone(),
+        classes: 6,
+        h_sem_bits,
+        quotient_digest: d.clone(),
+    };
+    for bad in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1e300] {
+        let p = payload(bad);
+        assert!(catch_unwind(|| p.to_canon()).is_err(), "{bad} must not be hashed as a saturated integer");
+    }
+}
@@ -0,0 +1,47 @@
+use asc7::{Asc7Profile, asc7_kernel_cert};
+use asc7::confusables::confusables_kernel_cert;
+use collapse_core::{Canon, CanonChange, Rational, canon_bytes, canon_diff, parse_canon};
+
+#[test]
+fn gate_diff_kernel_certs() {
+    let a = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap();
+    let b = asc7_kernel_cert(&Asc7Profile::auth_safe()).unwrap();
+    assert!(a.diff(&a).is_empty());
+
+    let d = a.diff(&b);
+    let paths: Vec<&str> = d.changes.iter().map(|c| c.path()).collect();
+    assert_eq!(paths, ["kernel_hash", "payload.graph_hash_hex", "payload.profile_name", "payload.witness_len"]);
+    assert!(d.render().contains("~ payload.profile_name: \"code_safe\" -> \"auth_safe\"\n"));
+}
+
+#[test]
+fn gate_diff_arrays_and_nested_paths() {
+    let old = confusables_kernel_cert().unwrap();
+    let mut payload = old.payload.clone();
+    if let Canon::Obj(m) = &mut payload {
+        if let Some(Canon::Arr(pairs)) = m.get_mut("pairs") {
+            if let Canon::Obj(p) = &mut pairs[3] {
+                p.insert("dst".to_string(), Canon::Str("I".to_string()));
+            }
+            pairs.push(Canon::Null);
+        }
+        m.remove("table_size");
+        m.insert("odd key".to_string(), Canon::Bool(true));
+    }
+
+    let d = canon_diff(&old.payload, &payload);
+    assert_eq!(d.changes.len(), 4);
+    assert!(matches!(&d.changes[0], CanonChange::Added { path, .. } if path == "[\"odd key\"]"));
+    assert!(matches!(&d.changes[1], CanonChange::Changed { path, .. } if path == "pairs[3].dst"));
+    assert!(matches!(&d.changes[2], CanonChange::Added { path, value: Canon::Null } if path == "pairs[19]"));
+    assert!(matches!(&d.changes[3], CanonChange::Removed { path, value: Canon::U64(19) } if path == "table_size"));
+
+    let root = canon_diff(&Canon::U64(1), &Canon::Str("1".to_string()));
+    assert_eq!(root.render(), "~ $: 1 -> \"1\"\n");
+    let width = canon_diff(&Canon::I64(5), &Canon::U64(5));
+    assert_eq!(width.render(), "~ $: 5 (i64) -> 5 (u64)\n");
+
+    // A Rat reloaded from v1 bytes is an object, but not a change.
+    let rat = Canon::Rat(Rational::new(1, 3));
+    assert!(canon_diff(&rat, &parse_canon(&canon_bytes(&rat)).unwrap()).changes.is_empty());
+}
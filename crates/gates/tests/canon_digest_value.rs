@@ -0,0 +1,66 @@
+use asc7::{Asc7KernelCert, Asc7Profile, asc7_kernel_cert};
+use collapse_core::{
+    Canon, CertChain, CertItem, DigestParseError, FromCanon, HashAlg, Sha256Digest, TaggedDigest, ToCanon,
+    cert_chain_hash, sha256_bytes,
+};
+
+#[test]
+fn gate_digest_value_parse_and_display() {
+    let d = sha256_bytes(b"abc");
+    let hex = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    assert_eq!(d.to_string(), hex);
+    assert_eq!(hex.parse::<Sha256Digest>().unwrap(), d);
+    assert_eq!(format!("sha256:{hex}").parse::<Sha256Digest>().unwrap(), d);
+    assert!(matches!("abc".parse::<Sha256Digest>(), Err(DigestParseError::BadHex(_))));
+    let upper = hex.to_uppercase();
+    assert!(matches!(upper.parse::<Sha256Digest>(), Err(DigestParseError::BadHex(_))));
+    assert!(format!("sha256:{upper}").parse::<TaggedDigest>().is_err());
+    assert!(matches!("00".repeat(31).parse::<Sha256Digest>(), Err(DigestParseError::BadLength { .. })));
+    let b3 = HashAlg::Blake3.hash(b"abc").to_string();
+    assert!(matches!(
+        b3.parse::<Sha256Digest>(),
+        Err(DigestParseError::WrongAlg { want: HashAlg::Sha256, got: HashAlg::Blake3 })
+    ));
+    assert_ne!(d, sha256_bytes(b"abd"));
+}
+
+#[test]
+fn gate_digest_value_serde_and_canon() {
+    let d = sha256_bytes(b"abc");
+    let json = serde_json::to_string(&d).unwrap();
+    assert_eq!(json, format!("\"{d}\""));
+    assert_eq!(serde_json::from_str::<Sha256Digest>(&json).unwrap(), d);
+    assert!(serde_json::from_str::<Sha256Digest>("\"zz\"").is_err());
+
+    let t = HashAlg::Sha3_256.hash(b"abc");
+    let json = serde_json::to_string(&t).unwrap();
+    assert!(json.starts_with("\"sha3-256:"));
+    assert_eq!(serde_json::from_str::<TaggedDigest>(&json).unwrap(), t);
+
+    assert_eq!(d.to_canon(), Canon::Str(d.hex()));
+    assert_eq!(TaggedDigest::from_canon(&d.to_canon()).unwrap(), d);
+    assert!(Sha256Digest::from_canon(&Canon::U64(1)).is_err());
+}
+
+#[test]
+fn gate_digest_value_payload_bytes_unchanged() {
+    let profile = Asc7Profile::code_safe();
+    let p = Asc7KernelCert::from_profile(&profile);
+    assert_eq!(p.graph_hash, Sha256Digest(profile.graph_hash));
+    let Canon::Obj(m) = p.to_canon() else { panic!() };
+    assert_eq!(m.get("graph_hash_hex"), Some(&Canon::Str(hex::encode(profile.graph_hash))));
+    let json = serde_json::to_value(&p).unwrap();
+    assert_eq!(json["graph_hash_hex"], hex::encode(profile.graph_hash));
+}
+
+#[test]
+fn gate_digest_value_chain_items() {
+    let cert = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap();
+    let item = CertItem::from_cert(&cert);
+    assert_eq!(item, CertItem::new("asc7", cert.kernel_hash.clone()));
+    assert_eq!(CertItem::new("asc7", cert.kernel_hash.to_sha256().unwrap()), item);
+
+    let chain = CertChain::build(vec![item.clone()]);
+    assert_eq!(chain.chain_hash.alg(), HashAlg::Sha256);
+    assert_eq!(chain.chain_hash.hex(), cert_chain_hash(&[item]));
+}
@@ -0,0 +1,119 @@
+use asc7::{Asc7Profile, SemanticPredicateDef, asc7_kernel_cert, asc7_semantic_kernel_cert, register_asc7_kernels};
+use collapse_core::{
+    Canon, CanonMode, CertHashScheme, CertLoadError, DisclosureError, HashAlg, KernelCert, KernelRegistry,
+    PayloadDisclosure, canon_bytes, parse_canon,
+};
+
+fn semantic() -> KernelCert {
+    let base = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap();
+    let predicates = vec![SemanticPredicateDef {
+        bit_index: 0,
+        id: 1,
+        kind: "resource".to_string(),
+        name: "internal_blocklist".to_string(),
+        resource_hash: Some(HashAlg::Sha256.hash(b"internal blocklist v7")),
+    }];
+    let c = asc7_semantic_kernel_cert(&base, predicates).unwrap();
+    c.with_disclosure_salt([3; 32])
+}
+
+#[test]
+fn gate_disclosure_cert_round_trips_and_commits_to_fields() {
+    let c = semantic();
+    assert_eq!(c.hash_scheme.tag(), "merkle/v1");
+    c.verify().unwrap();
+    let mut kernels = KernelRegistry::new();
+    register_asc7_kernels(&mut kernels).unwrap();
+    let back = KernelCert::from_canon(&c.to_canon(), kernels.schemas()).unwrap();
+    assert_eq!(back.hash_scheme, c.hash_scheme);
+    assert_eq!(back.kernel_hash, c.kernel_hash);
+
+    assert_ne!(c.with_disclosure_salt([4; 32]).kernel_hash, c.kernel_hash);
+    assert_ne!(c.with_hash_scheme(CertHashScheme::EnvelopeV1).unwrap().kernel_hash, c.kernel_hash);
+    for scheme in [CertHashScheme::Legacy, CertHashScheme::EnvelopeV1, CertHashScheme::MerkleV1] {
+        assert_eq!(CertHashScheme::from_tag(scheme.tag()), Some(scheme));
+    }
+
+    let mut tampered = c.clone();
+    if let Canon::Obj(obj) = &mut tampered.payload {
+        obj.insert("filter_rule".into(), Canon::Str("match everything".into()));
+    }
+    assert!(tampered.verify().is_err());
+}
+
+#[test]
+fn gate_disclosure_reveals_only_chosen_fields() {
+    let c = semantic();
+    let d = c.disclose(&["base_kernel_hash_hex", "predicate_registry_version"]).unwrap();
+    d.verify().unwrap();
+    assert_eq!(d.kernel_hash, c.kernel_hash);
+    assert_eq!(d.field("predicate_registry_version"), Some(&Canon::Str("1.0.0".into())));
+    assert_eq!(d.field("predicates"), None);
+    assert_eq!(d.fields[0].proof.tree_size, 5);
+
+    let bytes = canon_bytes(&d.to_canon());
+    let text = String::from_utf8(bytes.clone()).unwrap();
+    assert!(!text.contains("internal_blocklist") && !text.contains("filter_rule"));
+    assert_eq!(PayloadDisclosure::from_canon(&parse_canon(&bytes).unwrap()).unwrap(), d);
+}
+
+#[test]
+fn gate_disclosure_rejects_forgeries() {
+    let c = semantic();
+    let d = c.disclose(&["predicate_registry_version"]).unwrap();
+
+    let mut changed = d.clone();
+    changed.fields[0].value = Canon::Str("2.0.0".into());
+    assert!(matches!(changed.verify(), Err(DisclosureError::BadProof { key }) if key == "predicate_registry_version"));
+
+    let mut renamed = d.clone();
+    renamed.fields[0].key = "ablation_rule".into();
+    assert!(matches!(renamed.verify(), Err(DisclosureError::BadProof { .. })));
+
+    let mut relabelled = d.clone();
+    relabelled.kernel_version = "1.0.1".into();
+    assert!(matches!(relabelled.verify(), Err(DisclosureError::HashMismatch { .. })));
+
+    let mut undeclared = d;
+    undeclared.deps.clear();
+    assert!(matches!(undeclared.verify(), Err(DisclosureError::HashMismatch { .. })));
+
+    let missing = c.disclose(&["no_such_field"]);
+    assert!(matches!(missing, Err(DisclosureError::NoSuchField { key }) if key == "no_such_field"));
+    let plain = c.with_hash_scheme(CertHashScheme::EnvelopeV1).unwrap();
+    assert!(matches!(plain.disclose(&[]), Err(DisclosureError::NotMerkle { scheme: CertHashScheme::EnvelopeV1 })));
+}
+
+#[test]
+fn gate_disclosure_requires_a_salt() {
+    let payload = parse_canon(br#"{"count":5,"secret":"s"}"#).unwrap();
+    let c = KernelCert::new_unchecked("disclose_demo", "1.0.0", payload);
+    assert!(matches!(c.with_hash_scheme(CertHashScheme::MerkleV1), Err(DisclosureError::NoSalt)));
+
+    let a = c.with_random_disclosure_salt().unwrap();
+    let b = c.with_random_disclosure_salt().unwrap();
+    assert_ne!(a.disclosure_salt, b.disclosure_salt);
+    assert_ne!(a.kernel_hash, b.kernel_hash);
+    a.disclose(&["count"]).unwrap().verify().unwrap();
+    let back = a.with_hash_scheme(CertHashScheme::EnvelopeV1).unwrap().with_hash_scheme(CertHashScheme::MerkleV1);
+    assert_eq!(back.unwrap().kernel_hash, a.kernel_hash);
+
+    let mut unsalted = semantic();
+    unsalted.disclosure_salt = None;
+    assert!(matches!(unsalted.disclose(&["predicates"]), Err(DisclosureError::NoSalt)));
+    let mut kernels = KernelRegistry::new();
+    register_asc7_kernels(&mut kernels).unwrap();
+    let err = KernelCert::from_canon(&unsalted.to_canon(), kernels.schemas()).unwrap_err();
+    assert!(matches!(err, CertLoadError::Malformed(e) if e.path == "disclosure_salt"));
+}
+
+#[test]
+fn gate_disclosure_type_tagged_values() {
+    let payload = parse_canon(br#"{"count":5,"secret":"s"}"#).unwrap();
+    let c = KernelCert::new_unchecked("disclose_demo", "1.0.0", payload).with_disclosure_salt([9; 32]).to_v2().unwrap();
+    assert_eq!(c.canon_mode, CanonMode::V2);
+    let d = c.disclose(&["count"]).unwrap();
+    d.verify().unwrap();
+    let back = PayloadDisclosure::from_canon(&parse_canon(&canon_bytes(&d.to_canon())).unwrap()).unwrap();
+    assert_eq!(back.kernel_hash, c.kernel_hash);
+}
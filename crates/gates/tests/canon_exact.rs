@@ -0,0 +1,100 @@
+use collapse_core::{
+    Canon, CanonMode, CanonParseErrorKind, Decimal, FromCanon, Rational, Schema, ToCanon, canon_bytes, canon_bytes_with,
+    cbor_bytes, cbor_to_v1_bytes, parse_canon, parse_canon_v2, parse_cbor, sem_entropy_decimal, v1_bytes_to_cbor,
+};
+use structural_numbers::QE;
+
+#[test]
+fn gate_exact_values_normalize_and_display() {
+    let r = Rational::new(6, -4);
+    assert_eq!((r.num(), r.den()), (-3, 2));
+    assert_eq!(Rational::new(0, -7), Rational::new(0, 1));
+    assert_eq!(r.to_string(), "-3/2");
+    assert!(Rational::from_parts(2, 4).is_none());
+
+    assert_eq!(Decimal::new(150, 2).to_string(), "1.50");
+    assert_eq!(Decimal::new(-5, 2).to_string(), "-0.05");
+    assert_eq!(Decimal::new(42, 0).to_string(), "42");
+    assert_ne!(Decimal::new(150, 2), Decimal::new(15, 1));
+    assert_eq!(sem_entropy_decimal(3, 6), Decimal::new(1_584_963, 6));
+}
+
+#[test]
+fn gate_exact_encodings() {
+    let rat = Canon::Rat(Rational::new(1, 3));
+    let dec = Canon::Dec(Decimal::new(150, 2));
+
+    // v1 writes the object forms and parses back to them.
+    assert_eq!(canon_bytes(&rat), br#"{"den":3,"num":1}"#);
+    assert_eq!(canon_bytes(&dec), br#"{"mantissa":150,"scale":2}"#);
+    let parsed = parse_canon(&canon_bytes(&rat)).unwrap();
+    assert_eq!(Rational::from_canon(&parsed).unwrap(), Rational::new(1, 3));
+
+    // v2 and CBOR keep the variant.
+    for v in [&rat, &dec] {
+        assert_eq!(&parse_canon_v2(&canon_bytes_with(v, CanonMode::V2)).unwrap(), v);
+        assert_eq!(&parse_cbor(&cbor_bytes(v)).unwrap(), v);
+    }
+    assert_eq!(hex::encode(cbor_bytes(&rat)), "d81e820103");
+    assert_eq!(hex::encode(cbor_bytes(&dec)), "c482211896");
+}
+
+#[test]
+fn gate_exact_object_form_is_a_different_value() {
+    let rat = Canon::Rat(Rational::new(1, 3));
+    let obj = parse_canon(&canon_bytes(&rat)).unwrap();
+    assert!(matches!(obj, Canon::Obj(_)));
+    assert_ne!(obj, rat);
+    assert_eq!(canon_bytes(&obj), canon_bytes(&rat));
+    for mode in [CanonMode::V2, CanonMode::Cbor] {
+        assert_ne!(canon_bytes_with(&obj, mode), canon_bytes_with(&rat, mode));
+    }
+    assert_eq!(parse_canon_v2(&canon_bytes_with(&obj, CanonMode::V2)).unwrap(), obj);
+    assert_eq!(parse_cbor(&cbor_bytes(&obj)).unwrap(), obj);
+    // So going through v1 turns a CBOR rational into a map.
+    let through_v1 = v1_bytes_to_cbor(&cbor_to_v1_bytes(&cbor_bytes(&rat)).unwrap()).unwrap();
+    assert_eq!(parse_cbor(&through_v1).unwrap(), obj);
+
+    // Only the explicit conversion reads the object as a number, at any depth.
+    assert_eq!(obj.exact_from_v1_compat(), rat);
+    let nested = parse_canon(br#"[{"d":{"mantissa":150,"scale":2}}]"#).unwrap();
+    let Canon::Arr(xs) = nested.exact_from_v1_compat() else { panic!() };
+    let Canon::Obj(d) = &xs[0] else { panic!() };
+    assert_eq!(d["d"], Canon::Dec(Decimal::new(150, 2)));
+
+    // Not reduced, so not a rational even then.
+    let unreduced = parse_canon(br#"{"den":4,"num":2}"#).unwrap();
+    assert_eq!(unreduced.exact_from_v1_compat(), unreduced);
+
+    // A CBOR rational's denominator is a u64.
+    let wide = Canon::Rat(Rational::from_parts(1, u64::MAX).unwrap());
+    assert_eq!(parse_cbor(&cbor_bytes(&wide)).unwrap(), wide);
+}
+
+#[test]
+fn gate_exact_rejects_non_canonical() {
+    // tag 30 [2, 4]: not reduced.
+    let e = parse_cbor(&hex::decode("d81e820204").unwrap()).unwrap_err();
+    assert_eq!(e.kind, CanonParseErrorKind::NonCanonicalNumber);
+    // tag 4 with a positive exponent.
+    let e = parse_cbor(&hex::decode("c482010f").unwrap()).unwrap_err();
+    assert_eq!(e.kind, CanonParseErrorKind::NonCanonicalNumber);
+
+    let mut v2 = canon_bytes_with(&Canon::Rat(Rational::new(1, 3)), CanonMode::V2);
+    let n = v2.len();
+    v2[n - 1] = 0; // den = 0
+    assert_eq!(parse_canon_v2(&v2).unwrap_err().kind, CanonParseErrorKind::NonCanonicalNumber);
+}
+
+#[test]
+fn gate_exact_qe_and_schema() {
+    let q = QE::new(-4, 6);
+    assert_eq!(q.to_canon(), Canon::Rat(Rational::new(-2, 3)));
+    assert_eq!(QE::from_canon(&parse_canon(&canon_bytes(&q.to_canon())).unwrap()).unwrap(), q);
+
+    assert!(Schema::Rational.validate(&q.to_canon()).is_ok());
+    assert!(Schema::Decimal(6).validate(&Canon::Dec(Decimal::new(1, 6))).is_ok());
+    assert!(Schema::Decimal(6).validate(&parse_canon(br#"{"mantissa":1,"scale":6}"#).unwrap()).is_ok());
+    let errs = Schema::Decimal(6).validate(&Canon::Dec(Decimal::new(1, 2))).unwrap_err();
+    assert_eq!(errs[0].msg, "expected decimal scale 6, got 2");
+}
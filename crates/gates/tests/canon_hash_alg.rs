@@ -0,0 +1,76 @@
+use asc7::{Asc7Profile, asc7_kernel_cert, register_asc7_kernels};
+use asc7::confusables::confusables_kernel_cert;
+use collapse_core::{
+    Canon, CertChain, CertItem, DigestParseError, HashAlg, KernelCert, KernelRegistry, TaggedDigest, cert_chain_hash,
+    sha256_bytes,
+};
+use structural_numbers::{domain_digest, domain_digest_hex, domain_qe_bounded};
+
+#[test]
+fn gate_hash_alg_known_vectors() {
+    let cases = [
+        (HashAlg::Sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+        (
+            HashAlg::Sha512,
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+        ),
+        (HashAlg::Sha3_256, "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"),
+        (HashAlg::Blake3, "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"),
+    ];
+    for (alg, hex) in cases {
+        let d = alg.hash(b"abc");
+        assert_eq!(d.hex(), hex, "{alg}");
+        assert_eq!(d.to_string(), format!("{}:{hex}", alg.name()));
+        assert_eq!(d.to_string().parse::<TaggedDigest>().unwrap(), d);
+    }
+    assert_eq!(HashAlg::Sha256.hash(b"abc"), sha256_bytes(b"abc"));
+}
+
+#[test]
+fn gate_hash_alg_parsing() {
+    let hex = "00".repeat(32);
+    assert_eq!(TaggedDigest::parse_compat(&hex).unwrap().alg(), HashAlg::Sha256);
+    assert_eq!(TaggedDigest::parse_compat(&hex).unwrap().to_compat_string(), hex);
+    assert!(matches!(hex.parse::<TaggedDigest>(), Err(DigestParseError::MissingAlg(_))));
+    assert!(matches!(format!("md5:{hex}").parse::<TaggedDigest>(), Err(DigestParseError::UnknownAlg(_))));
+    assert!(matches!(
+        format!("sha512:{hex}").parse::<TaggedDigest>(),
+        Err(DigestParseError::BadLength { alg: HashAlg::Sha512, got: 32 })
+    ));
+}
+
+#[test]
+fn gate_hash_alg_certs_stay_compatible() {
+    let sha = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap();
+    let Canon::Obj(form) = sha.to_canon() else { panic!() };
+    assert_eq!(form.get("kernel_hash"), Some(&Canon::Str(sha.kernel_hash_hex())));
+
+    let b3 = sha.with_hash_alg(HashAlg::Blake3);
+    assert_eq!(b3.kernel_hash.alg(), HashAlg::Blake3);
+    assert_eq!(b3.kernel_hash_hex().len(), 64);
+    let Canon::Obj(form) = b3.to_canon() else { panic!() };
+    assert_eq!(form.get("kernel_hash"), Some(&Canon::Str(b3.kernel_hash.to_string())));
+    let mut kernels = KernelRegistry::new();
+    register_asc7_kernels(&mut kernels).unwrap();
+    let back = KernelCert::from_canon(&b3.to_canon(), kernels.schemas()).unwrap();
+    assert_eq!(back.kernel_hash, b3.kernel_hash);
+}
+
+#[test]
+fn gate_hash_alg_chain_and_domain() {
+    let asc7 = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap();
+    let conf = confusables_kernel_cert().unwrap().with_hash_alg(HashAlg::Sha3_256);
+    let items = vec![CertItem::from_cert(&asc7), CertItem::from_cert(&conf)];
+    assert_eq!(items[1].hash, conf.kernel_hash);
+
+    let legacy = CertChain::build(items.clone());
+    assert_eq!(legacy.chain_hash.hex(), cert_chain_hash(&items));
+    let b3 = CertChain::build_with_alg(items, HashAlg::Blake3);
+    assert_eq!(b3.chain_hash.alg(), HashAlg::Blake3);
+    assert_ne!(b3.chain_hash.hex(), legacy.chain_hash.hex());
+
+    let domain = domain_qe_bounded(5, 5);
+    assert_eq!(domain_digest(&domain, HashAlg::Sha256).hex(), domain_digest_hex(&domain));
+    assert_eq!(domain_digest(&domain, HashAlg::Sha512).as_bytes().len(), 64);
+}
@@ -0,0 +1,53 @@
+use std::collections::BTreeMap;
+
+use asc7::{Asc7Profile, asc7_kernel_cert};
+use collapse_core::{Canon, CanonMode, CertHashScheme, KernelCert, canon_bytes, canon_bytes_with, sha256_bytes};
+
+#[test]
+fn gate_jcs_key_order_is_utf16() {
+    // RFC 8785 §3.2.3 sorting example: U+1F600 (surrogate pair D83D) sorts before U+FB33
+    // in UTF-16, but after it in UTF-8 byte order.
+    let mut obj = BTreeMap::new();
+    for (k, n) in [("\u{20ac}", 1u64), ("\r", 2), ("\u{fb33}", 3), ("1", 4), ("\u{1f600}", 5), ("\u{80}", 6), ("\u{f6}", 7)] {
+        obj.insert(k.to_string(), Canon::U64(n));
+    }
+    let v = Canon::Obj(obj);
+    let jcs = String::from_utf8(canon_bytes_with(&v, CanonMode::Jcs)).unwrap();
+    assert_eq!(jcs, "{\"\\r\":2,\"1\":4,\"\u{80}\":6,\"\u{f6}\":7,\"\u{20ac}\":1,\"\u{1f600}\":5,\"\u{fb33}\":3}");
+}
+
+#[test]
+fn gate_jcs_escaping_and_numbers() {
+    let v = Canon::Arr(vec![
+        Canon::Str("\u{1}\u{8}\u{c}\n\u{1f}\u{7f}\"\\/é".to_string()),
+        Canon::I64(-9_007_199_254_740_991),
+        Canon::U64(0),
+        Canon::Null,
+    ]);
+    let jcs = String::from_utf8(canon_bytes_with(&v, CanonMode::Jcs)).unwrap();
+    assert_eq!(jcs, "[\"\\u0001\\b\\f\\n\\u001f\u{7f}\\\"\\\\/é\",-9007199254740991,0,null]");
+    assert_eq!(canon_bytes_with(&v, CanonMode::V1), canon_bytes(&v));
+}
+
+#[test]
+fn gate_jcs_writes_unsafe_integers_as_strings() {
+    let v = Canon::Arr(vec![Canon::U64((1u64 << 53) - 1), Canon::U64(1u64 << 53), Canon::I64(i64::MIN)]);
+    let jcs = String::from_utf8(canon_bytes_with(&v, CanonMode::Jcs)).unwrap();
+    assert_eq!(jcs, r#"[9007199254740991,"9007199254740992","-9223372036854775808"]"#);
+}
+
+#[test]
+fn gate_kernel_cert_records_mode() {
+    let legacy = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap();
+    assert_eq!(legacy.canon_mode, CanonMode::V1);
+
+    let jcs = KernelCert::new_unchecked("asc7", "1.0.0", legacy.payload.clone()).with_canon_mode(CanonMode::Jcs)
+        .with_hash_scheme(CertHashScheme::Legacy).unwrap();
+    assert_eq!(jcs.kernel_hash, sha256_bytes(&canon_bytes_with(&jcs.payload, CanonMode::Jcs)));
+    // ASCII-only payload with no control characters: both modes agree.
+    assert_eq!(jcs.kernel_hash, legacy.kernel_hash);
+    match jcs.to_canon() {
+        Canon::Obj(m) => assert_eq!(m.get("canon_mode"), Some(&Canon::Str("jcs".to_string()))),
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use asc7::{
+    Asc7KernelCert, Asc7Profile, ConfusablesKernel, SemanticPredicateDef, asc7_kernel_cert, asc7_semantic_kernel_cert,
+    confusables::confusables_kernel_cert,
+};
+use collapse_core::{
+    BundleError, Canon, CertBundle, CertItem, ChainMode, DuplicateKernel, FromCanon, HashAlg, Kernel, KernelCert,
+    KernelError, KernelInputs, KernelRegistry, Provenance,
+};
+use sembit::{SembitKernelPayload, sembit_kernel_cert};
+
+use common::kernels;
+
+fn certs() -> Vec<KernelCert> {
+    let asc7 = asc7_kernel_cert(&Asc7Profile::auth_safe()).unwrap();
+    let conf = confusables_kernel_cert().unwrap();
+    let pred = SemanticPredicateDef {
+        bit_index: 0,
+        id: 1,
+        kind: "builtin".into(),
+        name: "is_ident".into(),
+        resource_hash: None,
+    };
+    let sem = asc7_semantic_kernel_cert(&asc7, vec![pred]).unwrap();
+    let d = HashAlg::Sha256.hash(b"digest");
+    let sembit = sembit_kernel_cert(&asc7, &conf, &d, &d, 8, 3.0, &d).unwrap();
+    vec![asc7, conf, sem, sembit]
+}
+
+#[test]
+fn gate_kernel_trait_certs_match_free_functions() {
+    let p = Asc7Profile::code_safe();
+    assert_eq!(Asc7KernelCert::from_profile(&p).cert().unwrap().kernel_hash, asc7_kernel_cert(&p).unwrap().kernel_hash);
+    assert_eq!(ConfusablesKernel.cert().unwrap().kernel_hash, confusables_kernel_cert().unwrap().kernel_hash);
+    assert_eq!(ConfusablesKernel.cert().unwrap().deps, Vec::new());
+
+    let c = certs();
+    let sembit = SembitKernelPayload::from_canon(&c[3].payload).unwrap();
+    assert_eq!(sembit.cert().unwrap().kernel_hash, c[3].kernel_hash);
+    assert_eq!(SembitKernelPayload::deps_of(&c[3].payload).unwrap(), c[3].deps);
+    assert_eq!(c[2].deps[0].hash, c[0].kernel_hash);
+}
+
+#[test]
+fn gate_kernel_registry_dispatches_bundle_certs() {
+    let bundle = CertBundle::from_certs(certs(), ChainMode::Flat, HashAlg::Sha256);
+    let checks = bundle.verify_kernels(&kernels()).unwrap();
+    let names: Vec<&str> = checks.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["asc7", "asc7_confusables", "asc7_semantic", "sembit"]);
+    assert!(checks.iter().all(|c| c.verified_by == c.version));
+
+    // Hashes are consistent, but the payload is not what the profile compiles to.
+    let mut wrong = Asc7KernelCert::from_profile(&Asc7Profile::code_safe());
+    wrong.witness_len += 1;
+    let mut c = certs();
+    c[0] = wrong.cert().unwrap();
+    let err = kernels().verify_cert(&c[0], &KernelInputs::default()).unwrap_err();
+    assert_eq!(err, KernelError::invalid("witness_len", "differs from the compiled profile"));
+    let bundle = CertBundle::from_certs(c.drain(..2).collect(), ChainMode::Flat, HashAlg::Sha256);
+    assert!(matches!(bundle.verify_kernels(&kernels()), Err(BundleError::Kernel { index: 0, .. })));
+
+    let d = HashAlg::Sha256.hash(b"digest");
+    let c = certs();
+    let off = sembit_kernel_cert(&c[0], &c[1], &d, &d, 8, 2.5, &d).unwrap();
+    let bundle = CertBundle::from_certs(vec![c[0].clone(), c[1].clone(), off], ChainMode::Flat, HashAlg::Sha256);
+    assert!(matches!(
+        bundle.verify_kernels(&kernels()),
+        Err(BundleError::Kernel { index: 2, error: KernelError::Invalid { path, .. } }) if path == "h_sem_microbits"
+    ));
+}
+
+#[test]
+fn gate_kernel_inputs_and_registration() {
+    let c = certs();
+    let only_asc7 = &c[..1];
+    assert!(matches!(
+        kernels().verify_cert(&c[3], &KernelInputs::new(only_asc7)),
+        Err(KernelError::MissingInput { name, .. }) if name == "asc7_confusables"
+    ));
+    kernels().verify_cert(&c[2], &KernelInputs::new(only_asc7)).unwrap();
+
+    let prov = Provenance::current(include_bytes!("../../../Cargo.lock")).cert().unwrap();
+    assert!(matches!(
+        kernels().verify_cert(&prov, &KernelInputs::default()),
+        Err(KernelError::Unregistered { name, .. }) if name == "provenance"
+    ));
+    let mut r = kernels();
+    r.register::<Provenance>().unwrap();
+    assert_eq!(r.verify_cert(&prov, &KernelInputs::default()).unwrap().verified_by, "1.0.0");
+
+    // Consistently hashed, but declaring deps the payload does not name.
+    let inputs = KernelInputs::new(&c);
+    for deps in [vec![], vec![CertItem::new("asc7", c[1].kernel_hash.clone())]] {
+        let forged = c[2].with_deps(deps.clone());
+        forged.verify().unwrap();
+        assert_eq!(
+            kernels().verify_cert(&forged, &inputs),
+            Err(KernelError::DepsMismatch { declared: deps, expected: c[2].deps.clone() })
+        );
+    }
+}
+
+struct Counter(u64);
+struct CounterV2(u64);
+
+fn counter_payload(n: u64) -> Canon {
+    Canon::Obj(BTreeMap::from([("n".to_string(), Canon::U64(n))]))
+}
+
+fn verify_counter(payload: &Canon) -> Result<(), KernelError> {
+    match payload {
+        Canon::Obj(o) if matches!(o.get("n"), Some(Canon::U64(_))) => Ok(()),
+        _ => Err(KernelError::invalid("n", "expected an unsigned count")),
+    }
+}
+
+impl Kernel for Counter {
+    const NAME: &'static str = "counter";
+    const VERSION: &'static str = "1.0.0";
+    fn payload(&self) -> Canon {
+        counter_payload(self.0)
+    }
+    fn verify(payload: &Canon, _inputs: &KernelInputs<'_>) -> Result<(), KernelError> {
+        verify_counter(payload)
+    }
+}
+
+impl Kernel for CounterV2 {
+    const NAME: &'static str = "counter";
+    const VERSION: &'static str = "2.0.0";
+    const COMPATIBLE: &'static [&'static str] = &["1.0.0"];
+    fn payload(&self) -> Canon {
+        counter_payload(self.0)
+    }
+    fn verify(payload: &Canon, _inputs: &KernelInputs<'_>) -> Result<(), KernelError> {
+        verify_counter(payload)
+    }
+}
+
+#[test]
+fn gate_kernel_newer_versions_verify_compatible_older_certs() {
+    let mut r = KernelRegistry::new();
+    r.register::<CounterV2>().unwrap();
+    let none = KernelInputs::default();
+    let check = r.verify_cert(&Counter(3).cert().unwrap(), &none).unwrap();
+    assert_eq!((check.version.as_str(), check.verified_by.as_str()), ("1.0.0", "2.0.0"));
+    assert!(!r.contains("counter", "1.5.0"));
+
+    // Once the old version is registered itself, it takes precedence.
+    r.register::<Counter>().unwrap();
+    assert_eq!(r.verify_cert(&Counter(3).cert().unwrap(), &none).unwrap().verified_by, "1.0.0");
+    assert_eq!(r.verify_cert(&CounterV2(3).cert().unwrap(), &none).unwrap().verified_by, "2.0.0");
+}
+
+#[test]
+fn gate_kernel_registered_once() {
+    let mut r = KernelRegistry::new();
+    r.register::<Counter>().unwrap();
+    let Err(err) = r.register::<Counter>() else { panic!("registered twice") };
+    assert_eq!(err, DuplicateKernel { kernel_name: "counter".into(), kernel_version: "1.0.0".into() });
+    assert_eq!(err.to_string(), "counter@1.0.0 is already registered");
+    assert!(r.register::<CounterV2>().is_ok());
+}
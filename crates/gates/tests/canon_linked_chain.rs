@@ -0,0 +1,68 @@
+use collapse_core::{CertChain, CertItem, ChainMode, ChainVerifyError, HashAlg, cert_link_hash};
+
+fn items() -> Vec<CertItem> {
+    ["asc7", "asc7_confusables", "asc7_semantic", "sembit"]
+        .into_iter()
+        .map(|n| CertItem::new(n, HashAlg::Sha256.hash(n.as_bytes())))
+        .collect()
+}
+
+#[test]
+fn gate_linked_chain_links_commit_to_predecessor() {
+    let items = items();
+    let chain = CertChain::build_linked(items.clone());
+    assert_eq!(chain.mode, ChainMode::Linked);
+    assert_eq!(chain.links.len(), 4);
+    assert_eq!(chain.links[0], cert_link_hash(None, &items[0], HashAlg::Sha256));
+    assert_eq!(chain.links[2], cert_link_hash(Some(&chain.links[1]), &items[2], HashAlg::Sha256));
+    assert_eq!(chain.chain_hash, chain.links[3]);
+    assert!(chain.verify().is_ok());
+
+    let mut swapped = items.clone();
+    swapped.swap(1, 2);
+    assert_ne!(CertChain::build_linked(swapped.clone()).chain_hash, chain.chain_hash);
+    // The flat hash also depends on order, but cannot say where it changed.
+    assert_ne!(CertChain::build(swapped).chain_hash, CertChain::build(items).chain_hash);
+}
+
+#[test]
+fn gate_linked_chain_push_matches_build() {
+    let items = items();
+    let mut grown = CertChain::build_with(vec![], ChainMode::Linked, HashAlg::Blake3);
+    assert_eq!(grown.chain_hash, HashAlg::Blake3.hash(b""));
+    for it in &items {
+        grown.push(it.clone());
+        assert!(grown.verify().is_ok());
+    }
+    let built = CertChain::build_with(items.clone(), ChainMode::Linked, HashAlg::Blake3);
+    assert_eq!(grown.links, built.links);
+    assert_eq!(grown.chain_hash, built.chain_hash);
+
+    let mut flat = CertChain::build(items[..3].to_vec());
+    flat.push(items[3].clone());
+    assert_eq!(flat.chain_hash, CertChain::build(items).chain_hash);
+}
+
+#[test]
+fn gate_linked_chain_reports_breaking_link() {
+    let chain = CertChain::build_linked(items());
+
+    let mut tampered = chain.clone();
+    tampered.items[2].hash = HashAlg::Sha256.hash(b"evil");
+    match tampered.verify() {
+        Err(ChainVerifyError::BrokenLink { index, name, .. }) => assert_eq!((index, name.as_str()), (2, "asc7_semantic")),
+        other => panic!("unexpected {other:?}"),
+    }
+
+    let mut reordered = chain.clone();
+    reordered.items.swap(0, 1);
+    assert!(matches!(reordered.verify(), Err(ChainVerifyError::BrokenLink { index: 0, .. })));
+
+    let mut spliced = chain.clone();
+    spliced.items.insert(3, CertItem::new("extra", HashAlg::Sha256.hash(b"extra")));
+    assert_eq!(spliced.verify(), Err(ChainVerifyError::LinkCount { items: 5, links: 4 }));
+
+    let mut head = chain.clone();
+    head.chain_hash = chain.links[2].clone();
+    assert!(matches!(head.verify(), Err(ChainVerifyError::HashMismatch { .. })));
+}
@@ -0,0 +1,94 @@
+use asc7::{Asc7Profile, asc7_kernel_cert};
+use asc7::confusables::confusables_kernel_cert;
+use collapse_core::{
+    CertChain, CertItem, ChainMode, HashAlg, TaggedDigest, cert_chain_hash, inclusion_proof, merkle_leaf_hash,
+    merkle_root, verify_cert_inclusion,
+};
+
+/// Leaves from the RFC 6962 reference test suite.
+fn rfc_leaves() -> Vec<TaggedDigest> {
+    let data: [&[u8]; 8] = [
+        b"",
+        b"\x00",
+        b"\x10",
+        b"\x20\x21",
+        b"\x30\x31",
+        b"\x40\x41\x42\x43",
+        b"\x50\x51\x52\x53\x54\x55\x56\x57",
+        b"\x60\x61\x62\x63\x64\x65\x66\x67\x68\x69\x6a\x6b\x6c\x6d\x6e\x6f",
+    ];
+    data.iter().map(|d| merkle_leaf_hash(HashAlg::Sha256, d)).collect()
+}
+
+#[test]
+fn gate_merkle_rfc6962_vectors() {
+    let leaves = rfc_leaves();
+    assert_eq!(
+        merkle_root(HashAlg::Sha256, &[]).hex(),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        merkle_root(HashAlg::Sha256, &leaves[..1]).hex(),
+        "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d"
+    );
+    assert_eq!(
+        merkle_root(HashAlg::Sha256, &leaves).hex(),
+        "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328"
+    );
+}
+
+#[test]
+fn gate_merkle_every_proof_verifies() {
+    let all = rfc_leaves();
+    for n in 1..=all.len() {
+        let leaves = &all[..n];
+        let root = merkle_root(HashAlg::Sha256, leaves);
+        for (i, leaf) in leaves.iter().enumerate() {
+            let proof = inclusion_proof(HashAlg::Sha256, leaves, i).unwrap();
+            assert!(proof.verify(leaf, &root), "n={n} i={i}");
+            let other = &leaves[(i + 1) % n];
+            assert_eq!(proof.verify(other, &root), other == leaf, "n={n} i={i}");
+            let mut wrong_index = proof.clone();
+            wrong_index.leaf_index ^= 1;
+            assert!(!wrong_index.verify(leaf, &root), "n={n} i={i}");
+        }
+        assert_eq!(inclusion_proof(HashAlg::Sha256, leaves, n), None);
+    }
+}
+
+#[test]
+fn gate_merkle_cert_chain() {
+    let items = vec![
+        CertItem::from_cert(&asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap()),
+        CertItem::from_cert(&confusables_kernel_cert().unwrap()),
+        CertItem::new("sembit", HashAlg::Sha256.hash(b"sembit")),
+    ];
+    let flat = CertChain::build(items.clone());
+    assert_eq!(flat.mode, ChainMode::Flat);
+    assert_eq!(flat.chain_hash.hex(), cert_chain_hash(&items));
+
+    let chain = CertChain::build_merkle(items.clone());
+    assert_ne!(chain.chain_hash, flat.chain_hash);
+    let proof = chain.inclusion_proof(2).unwrap();
+    assert_eq!(chain.inclusion_proof(3), None);
+    assert_eq!(flat.inclusion_proof(0), None);
+    assert_eq!(proof.path.len(), 1);
+    assert!(verify_cert_inclusion(&items[2], &proof, &chain.chain_hash));
+
+    let forged = CertItem::new("sembit", HashAlg::Sha256.hash(b"forged"));
+    assert!(!verify_cert_inclusion(&forged, &proof, &chain.chain_hash));
+    let renamed = CertItem::new("sembit2", items[2].hash.clone());
+    assert!(!verify_cert_inclusion(&renamed, &proof, &chain.chain_hash));
+}
+
+#[test]
+fn gate_merkle_cert_chain_other_alg() {
+    let items: Vec<CertItem> = (0..5).map(|i| CertItem::new(format!("k{i}"), HashAlg::Sha512.hash(&[i]))).collect();
+    let chain = CertChain::build_with(items.clone(), ChainMode::Merkle, HashAlg::Blake3);
+    assert_eq!(chain.chain_hash.alg(), HashAlg::Blake3);
+    for (i, it) in items.iter().enumerate() {
+        assert!(verify_cert_inclusion(it, &chain.inclusion_proof(i).unwrap(), &chain.chain_hash));
+    }
+    let sha = CertChain::build_with(items.clone(), ChainMode::Merkle, HashAlg::Sha256);
+    assert!(!verify_cert_inclusion(&items[0], &chain.inclusion_proof(0).unwrap(), &sha.chain_hash));
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use asc7::{Asc7Profile, asc7_kernel_cert};
+use asc7::confusables::confusables_kernel_cert;
+use collapse_core::{Canon, CanonParseErrorKind, canon_bytes, parse_canon};
+use collapse_core::parse::MAX_CANON_DEPTH;
+
+fn sample() -> Canon {
+    let mut inner = BTreeMap::new();
+    inner.insert("a".to_string(), Canon::I64(-7));
+    inner.insert("b".to_string(), Canon::U64(u64::MAX));
+    inner.insert("c".to_string(), Canon::Str("quote\" back\\ nl\n cr\r tab\t ctl\u{1} Α".to_string()));
+    let mut obj = BTreeMap::new();
+    obj.insert("".to_string(), Canon::Null);
+    obj.insert("arr".to_string(), Canon::Arr(vec![Canon::Bool(true), Canon::Bool(false), Canon::U64(0), Canon::Arr(vec![])]));
+    obj.insert("inner".to_string(), Canon::Obj(inner));
+    obj.insert("min".to_string(), Canon::I64(i64::MIN));
+    Canon::Obj(obj)
+}
+
+#[test]
+fn gate_parse_roundtrip() {
+    let v = sample();
+    assert_eq!(parse_canon(&canon_bytes(&v)).unwrap(), v);
+
+    for cert in [asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap(), confusables_kernel_cert().unwrap()] {
+        let bytes = canon_bytes(&cert.payload);
+        let parsed = parse_canon(&bytes).unwrap();
+        assert_eq!(parsed, cert.payload);
+        assert_eq!(canon_bytes(&parsed), bytes);
+    }
+}
+
+#[test]
+fn gate_parse_rejects_non_canonical() {
+    let cases: &[(&[u8], usize, CanonParseErrorKind)] = &[
+        (b"{\"b\":1,\"a\":2}", 7, CanonParseErrorKind::UnsortedKey),
+        (b"{\"a\":1,\"a\":2}", 7, CanonParseErrorKind::DuplicateKey),
+        (b"[1, 2]", 3, CanonParseErrorKind::UnexpectedByte(b' ')),
+        (b" null", 0, CanonParseErrorKind::UnexpectedByte(b' ')),
+        (b"null ", 4, CanonParseErrorKind::TrailingBytes),
+        (b"007", 0, CanonParseErrorKind::LeadingZero),
+        (b"-0", 0, CanonParseErrorKind::NegativeZero),
+        (b"18446744073709551616", 0, CanonParseErrorKind::IntegerOverflow),
+        (b"\"\\u0041\"", 2, CanonParseErrorKind::UnknownEscape(b'u')),
+        (b"\"a\nb\"", 2, CanonParseErrorKind::UnescapedByte(b'\n')),
+        (b"\"\xff\"", 1, CanonParseErrorKind::InvalidUtf8),
+        (b"[1,", 3, CanonParseErrorKind::UnexpectedEnd),
+        (b"1.5", 1, CanonParseErrorKind::TrailingBytes),
+    ];
+    for (input, offset, kind) in cases {
+        let e = parse_canon(input).unwrap_err();
+        assert_eq!((e.offset, &e.kind), (*offset, kind), "input={:?}", String::from_utf8_lossy(input));
+    }
+
+    let ok = "[".repeat(MAX_CANON_DEPTH) + &"]".repeat(MAX_CANON_DEPTH);
+    assert!(parse_canon(ok.as_bytes()).is_ok());
+    let deep = "[".repeat(MAX_CANON_DEPTH + 1) + &"]".repeat(MAX_CANON_DEPTH + 1);
+    let e = parse_canon(deep.as_bytes()).unwrap_err();
+    assert_eq!((e.offset, e.kind), (MAX_CANON_DEPTH, CanonParseErrorKind::TooDeep));
+}
@@ -0,0 +1,152 @@
+mod common;
+
+use asc7::{Asc7Profile, SemanticPredicateDef, asc7_semantic_kernel_cert};
+use collapse_core::{
+    BundleError, Canon, CertBundle, CertSigner, ChainMode, Condition, HashAlg, Policy, PolicyLoadError,
+    PolicyViolation, RuleCheck, TrustStore, parse_canon,
+};
+
+fn bundle(profile: Asc7Profile) -> CertBundle {
+    let mut certs = common::certs(profile);
+    let predicates = ["is_ident", "is_keyword"]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| SemanticPredicateDef {
+            bit_index: i as u8,
+            id: i as u8 + 1,
+            kind: "builtin".into(),
+            name: name.to_string(),
+            resource_hash: None,
+        })
+        .collect();
+    certs.push(asc7_semantic_kernel_cert(&certs[0], predicates).unwrap());
+    CertBundle::from_certs(certs, ChainMode::Flat, HashAlg::Sha256)
+}
+
+fn violation(kernel: &str, path: &str, reason: &str) -> PolicyViolation {
+    PolicyViolation { kernel: Some(kernel.into()), path: path.into(), reason: reason.into() }
+}
+
+#[test]
+fn gate_policy_payload_rules_name_rule_and_path() {
+    let mut policy = Policy::new();
+    let safe = vec![Canon::Str("code_safe".into()), Canon::Str("auth_safe".into())];
+    let builtin = Condition::Eq(Canon::Str("builtin".into()));
+    let zero = Condition::Eq(Canon::U64(0));
+    for (id, kernel, path, condition) in [
+        ("asc7-profile", "asc7", "profile_name", Condition::In(safe)),
+        ("strict", "asc7", "syntax_strict", Condition::Eq(Canon::Bool(true))),
+        ("witness", "asc7", "witness_len", Condition::Min(80)),
+        ("few-pairs", "asc7_confusables", "table_size", Condition::Max(10)),
+        ("builtin-only", "asc7_semantic", "predicates[*].kind", builtin),
+        ("first-bit", "asc7_semantic", "predicates[1].bit_index", zero),
+    ] {
+        policy.rule(id, RuleCheck::payload(kernel, path, condition).unwrap()).unwrap();
+    }
+    let dup = policy.rule("witness", RuleCheck::ChainIncludes { kernel: "asc7".into() }).unwrap_err();
+    assert!(dup.to_string().contains("duplicate rule id"));
+    let bad = RuleCheck::payload("asc7", "preds[x]", Condition::Min(0)).unwrap_err();
+    assert_eq!(bad.to_string(), r#"path: invalid payload path "preds[x]""#);
+
+    let report = policy.evaluate(&bundle(Asc7Profile::code_safe())).unwrap();
+    assert!(!report.passed());
+    let failed: Vec<&str> = report.failed().map(|r| r.id.as_str()).collect();
+    assert_eq!(failed, ["few-pairs", "first-bit"]);
+    assert_eq!(report.rules[3].violations, vec![violation("asc7_confusables", "table_size", "19 is above 10")]);
+    assert_eq!(report.rules[5].violations, vec![violation("asc7_semantic", "predicates[1].bit_index", "1 is not 0")]);
+
+    // auth_safe also folds case, so its witness alphabet drops below 80.
+    let report = policy.evaluate(&bundle(Asc7Profile::auth_safe())).unwrap();
+    assert!(report.failed().any(|r| r.id == "witness"));
+
+    let Canon::Obj(out) = report.to_canon() else { unreachable!() };
+    assert_eq!(out["passed"], Canon::Bool(false));
+}
+
+#[test]
+fn gate_policy_chain_and_signature_rules() {
+    let (ci, release) = (CertSigner::from_seed([1; 32]), CertSigner::from_seed([3; 32]));
+    let mut trust = TrustStore::new();
+    trust.add("ci", ci.public_key()).add("release", release.public_key());
+    let mut policy = Policy::new();
+    policy
+        .rule("ci-signed", RuleCheck::SignedBy { kernel: None, trust: trust.clone(), threshold: 2 })
+        .unwrap()
+        .rule("asc7-signed", RuleCheck::SignedBy { kernel: Some("asc7".into()), trust, threshold: 1 })
+        .unwrap()
+        .rule("has-sembit", RuleCheck::ChainIncludes { kernel: "sembit".into() })
+        .unwrap()
+        .rule("has-semantic", RuleCheck::ChainIncludes { kernel: "asc7_semantic".into() })
+        .unwrap();
+
+    let mut b = bundle(Asc7Profile::code_safe());
+    b.sign_chain(&CertSigner::from_seed([2; 32]));
+    b.sign_chain(&ci);
+    let report = policy.evaluate(&b).unwrap();
+    let failed: Vec<&str> = report.failed().map(|r| r.id.as_str()).collect();
+    assert_eq!(failed, ["ci-signed", "asc7-signed", "has-sembit"]);
+    assert_eq!(report.rules[0].violations[0].path, "");
+    assert_eq!(report.rules[0].violations[0].kernel, None);
+    assert_eq!(report.rules[0].violations[0].reason, "chain root is signed by 1 of the 2 trusted keys required");
+
+    b.sign_chain(&release);
+    b.sign_cert(&ci, "asc7");
+    let failed: Vec<String> = policy.evaluate(&b).unwrap().failed().map(|r| r.id.clone()).collect();
+    assert_eq!(failed, ["has-sembit"]);
+
+    // Rules are not evaluated over a bundle that does not verify.
+    b.signatures[1].signature[0] ^= 1;
+    assert!(matches!(policy.evaluate(&b), Err(BundleError::Signature(_))));
+}
+
+#[test]
+fn gate_policy_canonical_round_trip() {
+    let key = CertSigner::from_seed([1; 32]).public_key();
+    let text = format!(
+        concat!(
+            r#"{{"format":"collapse/policy/v1","rules":["#,
+            r#"{{"id":"profile","in":["code_safe","auth_safe"],"kernel":"asc7","path":"profile_name"}},"#,
+            r#"{{"id":"signed","signed_by":{{"{}":"ci"}},"threshold":1}},"#,
+            r#"{{"chain_includes":"asc7_semantic","id":"semantic"}},"#,
+            r#"{{"id":"kinds","kernel":"asc7_semantic","max":63,"path":"predicates[*].bit_index"}}]}}"#
+        ),
+        key
+    );
+    let policy = Policy::from_bytes(text.as_bytes()).unwrap();
+    assert_eq!(policy.rules().len(), 4);
+    let mut trust = TrustStore::new();
+    trust.add("ci", key);
+    assert_eq!(policy.rules()[1].check, RuleCheck::SignedBy { kernel: None, trust, threshold: 1 });
+    assert_eq!(policy.to_bytes(), text.as_bytes());
+    assert_eq!(Policy::from_canon(&parse_canon(&policy.to_bytes()).unwrap()).unwrap(), policy);
+
+    // Hand-written policies need not be canonical.
+    let pretty = serde_json::to_string_pretty(&serde_json::from_str::<serde_json::Value>(&text).unwrap()).unwrap();
+    assert_eq!(Policy::from_bytes(pretty.as_bytes()).unwrap(), policy);
+}
+
+#[test]
+fn gate_policy_rejects_malformed_rules() {
+    let load =
+        |rules: &str| Policy::from_bytes(format!(r#"{{"format":"collapse/policy/v1","rules":[{rules}]}}"#).as_bytes());
+    let malformed = |rules: &str| match load(rules) {
+        Err(PolicyLoadError::Malformed(e)) => e.to_string(),
+        other => panic!("expected a malformed policy, got {other:?}"),
+    };
+
+    let typo = r#"{"id":"a","kernel":"asc7","minimum":1,"path":"witness_len"}"#;
+    assert!(malformed(typo).contains("exactly one of"));
+    let two = r#"{"eq":1,"id":"a","kernel":"asc7","max":2,"path":"witness_len"}"#;
+    assert!(malformed(two).contains("exactly one of"));
+    let extra = r#"{"eq":1,"id":"a","kernel":"asc7","path":"witness_len","severity":"low"}"#;
+    assert!(malformed(extra).contains("severity"));
+    let bad_path = r#"{"eq":1,"id":"a","kernel":"asc7","path":"preds[x]"}"#;
+    assert_eq!(malformed(bad_path), r#"rules[0].path: invalid payload path "preds[x]""#);
+    let dup = r#"{"chain_includes":"asc7","id":"a"},{"chain_includes":"sembit","id":"a"}"#;
+    assert!(malformed(dup).contains("duplicate rule id"));
+    assert!(malformed(r#"{"id": "a"}"#).contains("exactly one of"));
+    assert!(matches!(load(r#"{"id": "a""#), Err(PolicyLoadError::Parse(_))));
+    let float = r#"{"eq":1.5,"id":"a","kernel":"asc7","path":"witness_len"}"#;
+    assert!(matches!(load(float), Err(PolicyLoadError::Parse(_))));
+    assert!(Policy::from_bytes(br#"{"format":"collapse/policy/v2","rules":[]}"#).is_err());
+}
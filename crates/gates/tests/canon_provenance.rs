@@ -0,0 +1,109 @@
+mod common;
+
+use collapse_core::{
+    BundleError, CertChain, CertItem, ChainMode, HashAlg, KernelCert, KernelRegistry, PROVENANCE_KERNEL, Provenance,
+    chain_drift, parse_canon, canon_bytes,
+};
+
+use common::bundle;
+
+const LOCK: &str = r#"# This file is automatically @generated by Cargo.
+version = 4
+
+[[package]]
+name = "sha2"
+version = "0.10.9"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "digest",
+]
+
+[[package]]
+name = "digest"
+version = "0.10.7"
+
+[[package]]
+name = "digest"
+version = "0.9.0"
+
+[[package]]
+name = "collapse_core"
+version = "0.1.0"
+"#;
+
+fn provenance(rustc: &str) -> Provenance {
+    Provenance::from_lock(LOCK.as_bytes(), rustc, "x86_64-unknown-linux-gnu", vec!["std".into()])
+}
+
+#[test]
+fn gate_provenance_reads_cargo_lock() {
+    let p = provenance("rustc 1.90.0");
+    assert_eq!(p.crates.len(), 4);
+    assert_eq!(p.crates[0], ("collapse_core".to_string(), "0.1.0".to_string()));
+    assert_eq!(p.crate_versions("digest"), vec!["0.10.7", "0.9.0"]);
+    assert_eq!(p.cargo_lock_hash, HashAlg::Sha256.hash(LOCK.as_bytes()));
+
+    let here = Provenance::current(include_bytes!("../../../Cargo.lock"));
+    assert!(here.rustc_version.starts_with("rustc "));
+    assert!(!here.target.is_empty());
+    assert!(here.features.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(here.crate_versions("gates"), vec![env!("CARGO_PKG_VERSION")]);
+}
+
+#[test]
+fn gate_provenance_cert_round_trips() {
+    let p = provenance("rustc 1.90.0");
+    let c = p.to_kernel_cert().unwrap();
+    assert_eq!((c.kernel_name.as_str(), c.kernel_version.as_str()), (PROVENANCE_KERNEL, "1.0.0"));
+    assert_eq!(c.hash_scheme.tag(), "envelope/v1");
+    let mut kernels = KernelRegistry::new();
+    kernels.register::<Provenance>().unwrap();
+    let back = KernelCert::from_canon(&parse_canon(&canon_bytes(&c.to_canon())).unwrap(), kernels.schemas()).unwrap();
+    assert_eq!(back.kernel_hash, c.kernel_hash);
+    assert_eq!(Provenance::from_canon(&back.payload).unwrap(), p);
+    assert_ne!(provenance("rustc 1.91.0").to_kernel_cert().unwrap().kernel_hash, c.kernel_hash);
+
+    let b = bundle().with_provenance(&p).unwrap();
+    b.verify().unwrap();
+    assert_eq!(b.chain.items.last().unwrap().name, PROVENANCE_KERNEL);
+    assert_eq!(b.provenance(), Some(p));
+    assert_eq!(bundle().provenance(), None);
+}
+
+#[test]
+fn gate_provenance_drift_is_reported_apart_from_semantic_drift() {
+    let pinned = bundle().with_provenance(&provenance("rustc 1.90.0")).unwrap();
+    let rebuilt = bundle().with_provenance(&provenance("rustc 1.91.0")).unwrap();
+    let drift = chain_drift(&pinned.chain, &rebuilt.chain);
+    assert!(drift.semantic.is_empty());
+    assert_eq!(drift.provenance.len(), 1);
+    let diff = pinned.provenance().unwrap().diff(&rebuilt.provenance().unwrap());
+    assert_eq!(diff.changes.len(), 1);
+    assert_eq!(diff.changes[0].path(), "rustc_version");
+
+    let features = vec!["std".into(), "blake3".into(), "std".into()];
+    let featured = Provenance::from_lock(LOCK.as_bytes(), "rustc 1.90.0", "x86_64-unknown-linux-gnu", features);
+    assert_eq!(featured.features, vec!["blake3", "std"]);
+    let diff = provenance("rustc 1.90.0").diff(&featured);
+    assert_eq!(diff.changes.iter().map(|c| c.path()).collect::<Vec<_>>(), vec!["features[0]", "features[1]"]);
+
+    // A chain that never pinned provenance does not drift when one is added.
+    assert!(chain_drift(&bundle().chain, &rebuilt.chain).is_empty());
+
+    let mut items = rebuilt.chain.items.clone();
+    items[0] = CertItem::new("asc7", HashAlg::Sha256.hash(b"other asc7"));
+    let changed = CertChain::build_with(items, ChainMode::Flat, HashAlg::Sha256);
+    let drift = chain_drift(&pinned.chain, &changed);
+    assert_eq!(drift.semantic.len(), 1);
+    assert_eq!(drift.semantic[0].name, "asc7");
+    assert_eq!(drift.provenance.len(), 1);
+}
+
+#[test]
+fn gate_provenance_added_once() {
+    let p = provenance("rustc 1.90.0");
+    let once = bundle().with_provenance(&p).unwrap();
+    let err = once.with_provenance(&p).unwrap_err();
+    assert!(matches!(&err, BundleError::DuplicateCert { name } if name == PROVENANCE_KERNEL));
+    assert_eq!(err.to_string(), "bundle already has a provenance cert");
+}
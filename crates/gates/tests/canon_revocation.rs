@@ -0,0 +1,129 @@
+mod common;
+
+use collapse_core::{
+    BundleError, Canon, CertBundle, CertHashScheme, CertId, ChainMode, ChainVerifyError, HashAlg, RevocationList,
+    RevocationLoadError, RevocationPolicy, canon_bytes,
+};
+
+fn bundle() -> CertBundle {
+    CertBundle::from_certs(common::bundle().certs, ChainMode::Linked, HashAlg::Sha256)
+}
+
+#[test]
+fn gate_revocation_list_is_canonical() {
+    let a = CertId { kernel_name: "asc7".into(), payload_digest: HashAlg::Sha256.hash(b"a") };
+    let b = CertId { kernel_name: "sembit".into(), payload_digest: HashAlg::Sha256.hash(b"b") };
+    let mut one = RevocationList::new();
+    one.revoke(a.clone(), "bad profile", Some(HashAlg::Sha256.hash(b"a2"))).revoke(b.clone(), "wrong impl_tag", None);
+    let mut two = RevocationList::new();
+    two.revoke(b.clone(), "wrong impl_tag", None).revoke(a.clone(), "bad profile", Some(HashAlg::Sha256.hash(b"a2")));
+
+    assert_eq!(one.to_bytes(), two.to_bytes(), "insertion order must not matter");
+    assert_eq!(one.digest(HashAlg::Sha256), two.digest(HashAlg::Sha256));
+    assert_eq!(RevocationList::from_bytes(&one.to_bytes()).unwrap(), one);
+    assert!(one.is_revoked(&b) && one.get(&b).unwrap().superseded_by.is_none());
+
+    let mut three = one.clone();
+    three.revoke(a, "bad profile (CVE)", None);
+    assert_ne!(three.digest(HashAlg::Sha256), one.digest(HashAlg::Sha256));
+
+    let h = HashAlg::Sha256.hash(b"kernel");
+    let mut four = one.clone();
+    four.revoke_hash(h.clone(), "wrong impl_tag", None);
+    assert_eq!(RevocationList::from_bytes(&four.to_bytes()).unwrap(), four);
+    assert!(four.is_hash_revoked(&h) && !one.is_hash_revoked(&h));
+}
+
+#[test]
+fn gate_revocation_rejects_bad_lists() {
+    let mut l = RevocationList::new();
+    for name in ["x", "y"] {
+        l.revoke(CertId { kernel_name: name.into(), payload_digest: HashAlg::Sha256.hash(b"x") }, "x", None);
+    }
+    let Canon::Obj(mut obj) = l.to_canon() else { unreachable!() };
+    let Canon::Arr(items) = obj["revoked"].clone() else { unreachable!() };
+    for bad in [vec![items[0].clone(), items[0].clone()], vec![items[1].clone(), items[0].clone()]] {
+        obj.insert("revoked".into(), Canon::Arr(bad));
+        let bytes = canon_bytes(&Canon::Obj(obj.clone()));
+        let err = RevocationList::from_bytes(&bytes);
+        assert!(matches!(err, Err(RevocationLoadError::Malformed(e)) if e.path == "revoked[1]"));
+    }
+
+    obj.insert("format".into(), Canon::Str("collapse/revocations/v9".into()));
+    let future = canon_bytes(&Canon::Obj(obj));
+    assert!(matches!(RevocationList::from_bytes(&future), Err(RevocationLoadError::Malformed(_))));
+}
+
+#[test]
+fn gate_revocation_survives_rehashing() {
+    let b = bundle();
+    let clean = RevocationList::new();
+    assert_eq!(b.verify_with_revocations(&clean, RevocationPolicy::Deny).unwrap(), vec![]);
+
+    let replacement = HashAlg::Sha256.hash(b"asc7 v2");
+    let mut revoked = RevocationList::new();
+    revoked.revoke(CertId::of(&b.certs[0]), "profile maps 0 to O", Some(replacement.clone()));
+
+    match b.verify_with_revocations(&revoked, RevocationPolicy::Deny) {
+        Err(BundleError::Revoked(r)) => {
+            assert_eq!((r.index, r.name.as_str()), (0, "asc7"));
+            assert_eq!(r.revocation.superseded_by, Some(replacement));
+        }
+        other => panic!("expected a revoked cert, got {other:?}"),
+    }
+    let warnings = b.verify_with_revocations(&revoked, RevocationPolicy::Warn).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].revocation.reason, "profile maps 0 to O");
+
+    let cert = &b.certs[0];
+    for rehashed in [
+        cert.with_hash_alg(HashAlg::Blake3),
+        cert.with_hash_scheme(CertHashScheme::EnvelopeV1).unwrap(),
+        cert.to_v2().unwrap(),
+    ] {
+        assert_ne!(rehashed.kernel_hash, cert.kernel_hash);
+        assert!(revoked.is_revoked(&CertId::of(&rehashed)), "{} must stay revoked", rehashed.kernel_hash);
+    }
+}
+
+#[test]
+fn gate_revocation_chain_verification() {
+    let b = bundle();
+    let chain = b.chain.clone();
+    let mut revoked = RevocationList::new();
+    revoked.revoke_hash(chain.items[1].hash.clone(), "bad profile", Some(HashAlg::Sha256.hash(b"v2")));
+
+    assert_eq!(chain.verify_with_revocations(&RevocationList::new(), RevocationPolicy::Deny).unwrap(), vec![]);
+    let err = chain.verify_with_revocations(&revoked, RevocationPolicy::Deny).unwrap_err();
+    assert!(matches!(&err, ChainVerifyError::Revoked(r) if r.index == 1 && r.name == chain.items[1].name));
+    assert!(err.to_string().contains("bad profile, superseded by"));
+    let warnings = chain.verify_with_revocations(&revoked, RevocationPolicy::Warn).unwrap();
+    assert_eq!(warnings.iter().map(|r| r.index).collect::<Vec<_>>(), vec![1]);
+
+    // The bundle sees the same entry through its certs.
+    assert!(matches!(
+        b.verify_with_revocations(&revoked, RevocationPolicy::Deny),
+        Err(BundleError::Revoked(r)) if r.index == 1
+    ));
+}
+
+#[test]
+fn gate_revocation_bundle_verification() {
+    let b = bundle();
+    let mut revoked = RevocationList::new();
+    revoked.revoke(CertId::of(&b.certs[1]), "stale confusables table", None);
+
+    assert!(b.verify().is_ok(), "plain verify does not consult revocations");
+    let err = b.verify_with_revocations(&revoked, RevocationPolicy::Deny).unwrap_err();
+    assert!(matches!(&err, BundleError::Revoked(r) if r.name == "asc7_confusables"));
+    assert!(err.to_string().contains("stale confusables table"));
+    assert_eq!(b.verify_with_revocations(&revoked, RevocationPolicy::Warn).unwrap().len(), 1);
+
+    // Integrity failures still come first under Warn.
+    let mut tampered = b.clone();
+    tampered.chain.links[0] = HashAlg::Sha256.hash(b"forged");
+    assert!(matches!(
+        tampered.verify_with_revocations(&revoked, RevocationPolicy::Warn),
+        Err(BundleError::Chain(ChainVerifyError::BrokenLink { index: 0, .. }))
+    ));
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use asc7::{Asc7Profile, asc7_kernel_cert};
+use asc7::confusables::confusables_kernel_cert;
+use collapse_core::{
+    Canon, CertLoadError, Kernel, KernelCert, KernelError, KernelInputs, ObjectSchema, Schema, SchemaRegistry,
+};
+
+use common::kernels;
+
+#[test]
+fn gate_schema_reports_every_violation_with_paths() {
+    let s = ObjectSchema::new()
+        .required("hash", Schema::Hex(4))
+        .required("items", Schema::array_of(Schema::U64))
+        .optional("note", Schema::Str.nullable())
+        .build();
+
+    let mut ok = BTreeMap::new();
+    ok.insert("hash".to_string(), Canon::Str("00ff".to_string()));
+    ok.insert("items".to_string(), Canon::Arr(vec![Canon::U64(1), Canon::I64(2)]));
+    ok.insert("note".to_string(), Canon::Null);
+    assert!(s.validate(&Canon::Obj(ok)).is_ok());
+
+    let mut bad = BTreeMap::new();
+    bad.insert("hash".to_string(), Canon::Str("00FF".to_string()));
+    bad.insert("items".to_string(), Canon::Arr(vec![Canon::U64(1), Canon::I64(-2)]));
+    bad.insert("extra".to_string(), Canon::Bool(true));
+    let errs = s.validate(&Canon::Obj(bad)).unwrap_err();
+    let paths: Vec<&str> = errs.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, ["hash", "items[1]", "extra"]);
+}
+
+#[test]
+fn gate_schema_builtin_kernels_validate() {
+    let asc7 = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap();
+    let conf = confusables_kernel_cert().unwrap();
+    let kernels = kernels();
+    let schemas = kernels.schemas();
+    assert!(schemas.get("asc7", "1.0.0").is_some());
+    assert!(schemas.validate("asc7", "1.0.0", &asc7.payload).is_ok());
+    assert!(schemas.validate("asc7_confusables", "1.0.0", &conf.payload).is_ok());
+
+    // The asc7 payload is not a confusables payload.
+    let errs = schemas.validate("asc7_confusables", "1.0.0", &asc7.payload).unwrap_err();
+    assert!(errs.iter().any(|e| e.path == "pairs" && e.msg == "missing required key"));
+
+    // A kernel without a schema fails unless the registry opts out.
+    let errs = schemas.validate("stray", "1.0.0", &Canon::Null).unwrap_err();
+    assert_eq!(errs[0].to_string(), "$: no schema registered for stray@1.0.0");
+    assert!(SchemaRegistry::new().allow_unregistered().validate("stray", "1.0.0", &Canon::Null).is_ok());
+
+    let mut reg = SchemaRegistry::new();
+    reg.register("k", "1.0.0", Schema::U64).unwrap().register("k", "1.0.0", Schema::U64).unwrap();
+    let conflict = reg.register("k", "1.0.0", Schema::Str).unwrap_err();
+    assert_eq!(conflict.to_string(), "conflicting schema registered for k@1.0.0");
+}
+
+#[test]
+fn gate_schema_loader_rechecks_schema_and_hash() {
+    let kernels = kernels();
+    let load = |v: &Canon| KernelCert::from_canon(v, kernels.schemas());
+    let cert = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap();
+    let loaded = load(&cert.to_canon()).unwrap();
+    assert_eq!(loaded.kernel_hash_hex(), cert.kernel_hash_hex());
+
+    let mut tampered = cert.to_canon();
+    if let Canon::Obj(m) = &mut tampered {
+        if let Some(Canon::Obj(p)) = m.get_mut("payload") {
+            p.insert("witness_len".to_string(), Canon::U64(0));
+        }
+    }
+    assert!(matches!(load(&tampered), Err(CertLoadError::HashMismatch { .. })));
+
+    if let Canon::Obj(m) = &mut tampered {
+        if let Some(Canon::Obj(p)) = m.get_mut("payload") {
+            p.insert("witness_len".to_string(), Canon::Str("many".to_string()));
+        }
+    }
+    assert!(matches!(load(&tampered), Err(CertLoadError::Schema(_))));
+
+    // Unchecked construction is caught on load.
+    let empty = KernelCert::new_unchecked("asc7_confusables", "1.0.0", Canon::Obj(BTreeMap::new()));
+    assert!(matches!(load(&empty.to_canon()), Err(CertLoadError::Schema(_))));
+}
+
+struct Mislabelled;
+
+impl Kernel for Mislabelled {
+    const NAME: &'static str = "mislabelled";
+    const VERSION: &'static str = "1.0.0";
+    fn payload(&self) -> Canon {
+        Canon::Str("three".to_string())
+    }
+    fn schema() -> Option<Schema> {
+        Some(Schema::U64)
+    }
+    fn verify(_payload: &Canon, _inputs: &KernelInputs<'_>) -> Result<(), KernelError> {
+        Ok(())
+    }
+}
+
+#[test]
+fn gate_schema_checked_at_construction() {
+    let kernels = kernels();
+    let empty = Canon::Obj(BTreeMap::new());
+    let errs = KernelCert::new_checked("asc7_confusables", "1.0.0", empty, kernels.schemas()).unwrap_err();
+    assert!(errs.iter().any(|e| e.path == "pairs" && e.msg == "missing required key"));
+
+    let conf = confusables_kernel_cert().unwrap();
+    let checked = KernelCert::new_checked("asc7_confusables", "1.0.0", conf.payload.clone(), kernels.schemas());
+    let unchecked = KernelCert::new_unchecked("asc7_confusables", "1.0.0", conf.payload);
+    assert_eq!(checked.unwrap().kernel_hash, unchecked.kernel_hash);
+
+    let errs = Mislabelled.cert().unwrap_err();
+    assert_eq!(errs[0].to_string(), "$: expected non-negative integer, got string");
+}
+
+#[test]
+fn gate_schema_json_schema_export() {
+    let Canon::Obj(all) = kernels().schemas().to_json_schemas() else { panic!() };
+    let Some(Canon::Obj(asc7)) = all.get("asc7@1.0.0") else { panic!("asc7 schema not exported") };
+    assert_eq!(asc7.get("additionalProperties"), Some(&Canon::Bool(false)));
+    let Some(Canon::Obj(props)) = asc7.get("properties") else { panic!() };
+    let Some(Canon::Obj(hash)) = props.get("graph_hash_hex") else { panic!() };
+    assert_eq!(hash.get("pattern"), Some(&Canon::Str("^[0-9a-f]{64}$".to_string())));
+}
@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use asc7::{Asc7KernelCert, Asc7Profile, Asc7SemanticKernelCert, SemanticPredicateDef, asc7_kernel_cert};
+use collapse_core::{Canon, HashAlg, ToCanon, canon_bytes, from_canon_value, to_canon_value};
+
+fn predicates() -> Vec<SemanticPredicateDef> {
+    vec![
+        SemanticPredicateDef { bit_index: 0, id: 1, kind: "intrinsic".to_string(), name: "upper_initial".to_string(), resource_hash: None },
+        SemanticPredicateDef { bit_index: 3, id: 9, kind: "resource".to_string(), name: "wordlist".to_string(), resource_hash: Some(HashAlg::Blake3.hash(b"wordlist")) },
+    ]
+}
+
+#[test]
+fn gate_serde_matches_hand_built_canon() {
+    let profile = Asc7Profile::code_safe();
+    let asc7 = Asc7KernelCert::from_profile(&profile);
+    let via_serde = to_canon_value(&asc7).unwrap();
+    assert_eq!(via_serde, asc7.to_canon());
+    assert_eq!(canon_bytes(&via_serde), canon_bytes(&asc7_kernel_cert(&profile).unwrap().payload));
+
+    for p in predicates() {
+        assert_eq!(to_canon_value(&p).unwrap(), p.to_canon());
+    }
+
+    let sem = Asc7SemanticKernelCert {
+        ablation_rule: "a".to_string(),
+        base_kernel_hash: HashAlg::Sha256.hash(b"base"),
+        filter_rule: "f".to_string(),
+        predicate_registry_version: "1.0.0".to_string(),
+        predicates: predicates(),
+    };
+    assert_eq!(canon_bytes(&to_canon_value(&sem).unwrap()), canon_bytes(&sem.to_canon()));
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Unit,
+    Newtype(i32),
+    Tuple(u8, String),
+    Struct { w: u64, h: u64 },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Payload {
+    shapes: Vec<Shape>,
+    tags: BTreeMap<String, bool>,
+    note: Option<String>,
+    pair: (i64, char),
+}
+
+#[test]
+fn gate_serde_roundtrip_and_rejects_floats() {
+    let mut tags = BTreeMap::new();
+    tags.insert("z".to_string(), true);
+    tags.insert("a".to_string(), false);
+    let p = Payload {
+        shapes: vec![Shape::Unit, Shape::Newtype(-3), Shape::Tuple(7, "x".to_string()), Shape::Struct { w: 2, h: 5 }],
+        tags,
+        note: None,
+        pair: (-1, 'Ω'),
+    };
+    let c = to_canon_value(&p).unwrap();
+    assert_eq!(
+        String::from_utf8(canon_bytes(&c)).unwrap(),
+        "{\"note\":null,\"pair\":[-1,\"Ω\"],\"shapes\":[\"Unit\",{\"Newtype\":-3},{\"Tuple\":[7,\"x\"]},{\"Struct\":{\"h\":5,\"w\":2}}],\"tags\":{\"a\":false,\"z\":true}}"
+    );
+    assert_eq!(from_canon_value::<Payload>(c).unwrap(), p);
+
+    assert!(to_canon_value(&1.5f64).unwrap_err().0.contains("floats"));
+    assert!(from_canon_value::<f64>(Canon::I64(1)).unwrap_err().0.contains("floats"));
+
+    let mut bad_keys = BTreeMap::new();
+    bad_keys.insert(vec![1u8], 1u8);
+    assert!(to_canon_value(&bad_keys).is_err());
+}
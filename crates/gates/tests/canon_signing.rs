@@ -0,0 +1,118 @@
+mod common;
+
+use collapse_core::{
+    BundleError, CertBundle, CertSignature, CertSigner, ChainMode, FromCanon, HashAlg, SignatureError, SignedSubject,
+    ToCanon, TrustStore,
+};
+
+use common::{bundle, kernels};
+
+fn signer(n: u8) -> CertSigner {
+    CertSigner::from_seed([n; 32])
+}
+
+#[test]
+fn gate_signing_signatures_verify_and_round_trip() {
+    let b = bundle();
+    let sig = signer(1).sign_cert(&b.certs[0]);
+    assert!(sig.is_valid());
+    assert_eq!(sig, signer(1).sign_cert(&b.certs[0]), "Ed25519 is deterministic");
+    assert_eq!(CertSignature::from_canon(&sig.to_canon()).unwrap(), sig);
+
+    let mut moved = sig.clone();
+    moved.subject = SignedSubject::of_cert(&b.certs[1]);
+    assert!(!moved.is_valid());
+    let mut flipped = sig.clone();
+    flipped.signature[0] ^= 1;
+    assert!(!flipped.is_valid());
+    let mut impostor = sig;
+    impostor.public_key = signer(2).public_key();
+    assert!(!impostor.is_valid());
+}
+
+#[test]
+fn gate_signing_threshold_policy() {
+    let b = bundle();
+    let subject = SignedSubject::of_chain(&b.chain, &b.certs);
+    let mut trust = TrustStore::new();
+    trust.add("alice", signer(1).public_key()).add("bob", signer(2).public_key()).add("carol", signer(3).public_key());
+
+    let sigs = vec![
+        signer(1).sign_chain(&b.chain, &b.certs),
+        signer(1).sign_chain(&b.chain, &b.certs),
+        signer(9).sign_chain(&b.chain, &b.certs),
+        signer(2).sign_cert(&b.certs[0]),
+    ];
+    // alice twice counts once, the outsider not at all, and bob signed something else.
+    assert_eq!(
+        trust.check(&subject, &sigs, 2),
+        Err(SignatureError::BelowThreshold { required: 2, trusted: vec!["alice".into()] })
+    );
+
+    let mut sigs = sigs;
+    sigs.push(signer(3).sign_chain(&b.chain, &b.certs));
+    assert_eq!(trust.check(&subject, &sigs, 2).unwrap(), ["alice", "carol"]);
+    assert!(trust.check(&subject, &sigs, 3).is_err());
+
+    sigs[0].signature[5] ^= 0xff;
+    assert!(matches!(trust.check(&subject, &sigs, 2), Err(SignatureError::Invalid { .. })));
+}
+
+#[test]
+fn gate_signing_threshold_counts_keys_not_names() {
+    let b = bundle();
+    let subject = SignedSubject::of_chain(&b.chain, &b.certs);
+    let sigs = vec![signer(1).sign_chain(&b.chain, &b.certs), signer(2).sign_chain(&b.chain, &b.certs)];
+
+    // One operator holding two keys: two signers.
+    let mut trust = TrustStore::new();
+    trust.add("ops", signer(1).public_key()).add("ops", signer(2).public_key());
+    assert_eq!(trust.check(&subject, &sigs, 2).unwrap(), ["ops", "ops"]);
+
+    // One key under two names is one key, named by its last `add`.
+    let mut trust = TrustStore::new();
+    trust.add("alice", signer(1).public_key()).add("mallory", signer(1).public_key());
+    assert_eq!(trust.len(), 1);
+    let one_key = [sigs[0].clone(), sigs[0].clone()];
+    assert_eq!(
+        trust.check(&subject, &one_key, 2),
+        Err(SignatureError::BelowThreshold { required: 2, trusted: vec!["mallory".into()] })
+    );
+    let keys = [signer(1).public_key(), signer(1).public_key()];
+    assert_eq!(trust.check_signers(&keys, 1).unwrap(), ["mallory"]);
+}
+
+#[test]
+fn gate_signing_bundle_carries_signatures() {
+    let mut b = bundle();
+    b.sign_chain(&signer(1));
+    b.sign_chain(&signer(2));
+    b.sign_cert(&signer(1), "asc7");
+
+    let kernels = kernels();
+    let back = CertBundle::from_bytes(&b.to_bytes(), kernels.schemas()).unwrap();
+    assert_eq!(back.signatures, b.signatures);
+    let mut trust = TrustStore::new();
+    trust.add("alice", signer(1).public_key()).add("bob", signer(2).public_key()).add("carol", signer(3).public_key());
+    assert_eq!(back.verify_trusted(&trust, 2).unwrap(), ["alice", "bob"]);
+    assert!(matches!(back.verify_trusted(&trust, 3), Err(BundleError::Signature(_))));
+
+    // A signature lifted from a different chain is rejected even if valid.
+    let other = CertBundle::from_certs(b.certs.clone(), ChainMode::Merkle, HashAlg::Sha256);
+    let mut lifted = b.clone();
+    lifted.signatures.push(signer(3).sign_chain(&other.chain, &other.certs));
+    assert!(matches!(lifted.verify(), Err(BundleError::UnboundSignature { .. })));
+
+    // The legacy hashes and chain items survive a version rewrite; the signed headers do not.
+    let mut renamed = b.clone();
+    renamed.certs[0].kernel_version = "9.0.0".into();
+    renamed.certs[0].verify().unwrap();
+    assert!(matches!(renamed.verify(), Err(BundleError::UnboundSignature { .. })));
+
+    let text = String::from_utf8(b.to_bytes()).unwrap();
+    let sig_hex = hex::encode(b.signatures[0].signature);
+    let mut bad = sig_hex.clone();
+    bad.replace_range(..1, if sig_hex.starts_with('0') { "1" } else { "0" });
+    let forged = text.replace(&sig_hex, &bad);
+    assert!(matches!(CertBundle::from_bytes(forged.as_bytes(), kernels.schemas()), Err(BundleError::Signature(_))));
+}
@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+
+use asc7::{Asc7Profile, asc7_kernel_cert};
+use collapse_core::{
+    Canon, CanonMode, HashSink, canon_bytes, canon_bytes_with, canon_sha256_with, sha256_bytes, sha256_hex, write_array,
+    write_object,
+};
+use structural_numbers::domain_qe_bounded;
+use structural_numbers::q_e::domain_digest_hex;
+
+#[test]
+fn gate_stream_matches_materialized_bytes() {
+    let domain = domain_qe_bounded(60, 60);
+    let mut arr = Vec::new();
+    for q in &domain {
+        let mut obj = BTreeMap::new();
+        obj.insert("num".to_string(), Canon::I64(q.num()));
+        obj.insert("den".to_string(), Canon::I64(q.den()));
+        arr.push(Canon::Obj(obj));
+    }
+    let materialized = Canon::Arr(arr);
+    assert_eq!(domain_digest_hex(&domain), sha256_hex(sha256_bytes(&canon_bytes(&materialized))));
+
+    let payload = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap().payload;
+    for mode in [CanonMode::V1, CanonMode::Jcs, CanonMode::V2] {
+        assert_eq!(canon_sha256_with(&payload, mode), sha256_bytes(&canon_bytes_with(&payload, mode)));
+    }
+}
+
+#[test]
+fn gate_stream_nested_builders() {
+    let mut bytes = Vec::new();
+    write_object(&mut bytes, |o| {
+        o.field_array("a", |arr| {
+            arr.item(&Canon::U64(1));
+            arr.item_array(|_| {});
+            arr.item_object(|inner| inner.field("k\n", &Canon::Null));
+        });
+        o.field_object("b", |_| {});
+        o.field("c", &Canon::Str("x".to_string()));
+    });
+    assert_eq!(bytes, b"{\"a\":[1,[],{\"k\\n\":null}],\"b\":{},\"c\":\"x\"}");
+
+    let mut h = HashSink::new();
+    write_array(&mut h, |arr| arr.item(&Canon::Bool(true)));
+    assert_eq!(h.finish(), sha256_bytes(b"[true]"));
+}
+
+#[test]
+#[should_panic(expected = "breaks canonical order")]
+fn gate_stream_rejects_unsorted_keys() {
+    let mut bytes = Vec::new();
+    write_object(&mut bytes, |o| {
+        o.field("num", &Canon::I64(1));
+        o.field("den", &Canon::I64(1));
+    });
+}
@@ -0,0 +1,140 @@
+use collapse_core::{
+    CertChain, CertItem, CertSigner, HashAlg, LogEntry, LogError, LogVerifier, TransparencyLog, TrustStore,
+    canon_bytes, consistency_proof, merkle_leaf_hash, merkle_root,
+};
+
+fn chain(n: u8) -> CertChain {
+    CertChain::build_merkle(vec![CertItem::new(format!("k{n}"), HashAlg::Sha256.hash(&[n]))])
+}
+
+fn log_path(tag: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("collapse_gate_log_{tag}_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn gate_translog_consistency_proofs_all_sizes() {
+    let leaves: Vec<_> = (0u8..9).map(|i| merkle_leaf_hash(HashAlg::Sha256, &[i])).collect();
+    for n in 0..=leaves.len() {
+        let new_root = merkle_root(HashAlg::Sha256, &leaves[..n]);
+        for m in 0..=n {
+            let old_root = merkle_root(HashAlg::Sha256, &leaves[..m]);
+            let p = consistency_proof(HashAlg::Sha256, &leaves[..n], m).unwrap();
+            assert!(p.verify(&old_root, &new_root), "m={m} n={n}");
+            if m > 0 && m < n {
+                let other = merkle_root(HashAlg::Sha256, &leaves[1..=m]);
+                assert!(!p.verify(&other, &new_root), "m={m} n={n} accepted a forged old root");
+            }
+        }
+        assert_eq!(consistency_proof(HashAlg::Sha256, &leaves[..n], n + 1), None);
+    }
+    // 3 -> 8: leaves 2 and 3, then the subtrees over [0, 2) and [4, 8).
+    let p = consistency_proof(HashAlg::Sha256, &leaves[..8], 3).unwrap();
+    let want = vec![
+        leaves[2].clone(),
+        leaves[3].clone(),
+        merkle_root(HashAlg::Sha256, &leaves[..2]),
+        merkle_root(HashAlg::Sha256, &leaves[4..8]),
+    ];
+    assert_eq!(p.path, want);
+}
+
+#[test]
+fn gate_translog_persists_and_proves_inclusion() {
+    let path = log_path("persist");
+    let mut log = TransparencyLog::open(&path).unwrap();
+    assert_eq!(log.tree_head().root_hash, HashAlg::Sha256.hash(b""));
+    for n in 0..5 {
+        assert_eq!(log.append(&chain(n)).unwrap(), n as u64);
+    }
+    let head = log.tree_head();
+
+    let mut log = TransparencyLog::open(&path).unwrap();
+    assert_eq!(log.tree_head(), head);
+    assert_eq!(log.entries()[2], LogEntry::of_chain(&chain(2)));
+    for i in 0..5 {
+        assert!(log.inclusion_proof(i, 5).unwrap().verify(&log.entries()[i as usize].leaf_hash(), &head.root_hash));
+    }
+    assert!(!log.inclusion_proof(1, 5).unwrap().verify(&LogEntry::of_chain(&chain(9)).leaf_hash(), &head.root_hash));
+    assert_eq!(log.inclusion_proof(5, 5), None);
+    assert_eq!(log.inclusion_proof(0, 6), None);
+    assert_eq!(log.consistency_proof(3, 6), None);
+    assert_eq!(log.tree_head_at(6), None);
+
+    // Appends are locked, and a handle that missed another writer's append refuses to write.
+    let mut stale = TransparencyLog::open(&path).unwrap();
+    log.append(&chain(5)).unwrap();
+    assert!(matches!(stale.append(&chain(6)), Err(LogError::Stale { .. })));
+    assert_eq!(TransparencyLog::open(&path).unwrap().size(), 6);
+
+    std::fs::write(&path, b"{\"chain_hash\":\"00\"}").unwrap();
+    assert!(matches!(TransparencyLog::open(&path), Err(LogError::Malformed { line: 1, .. })));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn gate_translog_verifier_follows_signed_heads() {
+    let path = log_path("verifier");
+    let signer = CertSigner::from_seed([7; 32]);
+    let mut trust = TrustStore::new();
+    trust.add("log", signer.public_key());
+    let mut client = LogVerifier::new(trust, 1);
+
+    let mut log = TransparencyLog::open(&path).unwrap();
+    for n in 0..3 {
+        log.append(&chain(n)).unwrap();
+    }
+    let head3 = log.tree_head();
+    let first = (LogEntry::of_chain(&chain(0)), log.inclusion_proof(0, 3).unwrap());
+    assert!(matches!(client.verify_inclusion(&first.0, &first.1), Err(LogError::NoHead)));
+    client.update(&head3, &[log.sign_tree_head(&signer)], &log.consistency_proof(0, 3).unwrap()).unwrap();
+
+    for n in 3..7 {
+        log.append(&chain(n)).unwrap();
+    }
+    let head7 = log.tree_head();
+    let sth7 = vec![log.sign_tree_head(&signer)];
+    let outsider = CertSigner::from_seed([8; 32]).sign(head7.subject());
+    let proof = log.consistency_proof(3, 7).unwrap();
+    assert!(matches!(client.update(&head7, &[outsider], &proof), Err(LogError::Signature(_))));
+    assert!(matches!(
+        client.update(&head7, &sth7, &log.consistency_proof(2, 7).unwrap()),
+        Err(LogError::Inconsistent { old_size: 3, new_size: 7 })
+    ));
+    client.update(&head7, &sth7, &proof).unwrap();
+    assert_eq!(client.head(), Some(&head7));
+
+    let entry = LogEntry::of_chain(&chain(4));
+    client.verify_inclusion(&entry, &log.inclusion_proof(4, 7).unwrap()).unwrap();
+    assert!(client.verify_inclusion(&entry, &log.inclusion_proof(4, 5).unwrap()).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn gate_translog_detects_rewritten_history() {
+    let path = log_path("rewrite");
+    let mut log = TransparencyLog::open(&path).unwrap();
+    for n in 0..4 {
+        log.append(&chain(n)).unwrap();
+    }
+    let published = log.tree_head();
+    log.check_head(&published).unwrap();
+
+    // Rewrite entry 1 on disk and append as if nothing happened.
+    let text = std::fs::read_to_string(&path).unwrap();
+    let old_line = String::from_utf8(canon_bytes(&LogEntry::of_chain(&chain(1)).to_canon())).unwrap();
+    let new_line = String::from_utf8(canon_bytes(&LogEntry::of_chain(&chain(42)).to_canon())).unwrap();
+    std::fs::write(&path, text.replace(&old_line, &new_line)).unwrap();
+    let mut forged = TransparencyLog::open(&path).unwrap();
+    forged.append(&chain(4)).unwrap();
+
+    assert!(matches!(forged.check_head(&published), Err(LogError::RootMismatch { tree_size: 4, .. })));
+    let p = forged.consistency_proof(4, 5).unwrap();
+    assert!(!p.verify(&published.root_hash, &forged.tree_head().root_hash));
+
+    std::fs::write(&path, b"").unwrap();
+    let empty = TransparencyLog::open(&path).unwrap();
+    assert!(matches!(empty.check_head(&published), Err(LogError::Truncated { tree_size: 4, entries: 0 })));
+    std::fs::remove_file(&path).unwrap();
+}
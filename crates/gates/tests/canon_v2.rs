@@ -0,0 +1,53 @@
+use asc7::{Asc7Profile, asc7_kernel_cert};
+use collapse_core::{
+    Canon, CanonMode, CanonParseErrorKind, KernelCert, VersionBumpError, canon_bytes, canon_bytes_with, parse_canon_v2,
+    parse_canon_versioned, sha256_bytes,
+};
+
+#[test]
+fn gate_v2_distinguishes_integer_width() {
+    assert_eq!(canon_bytes(&Canon::I64(5)), canon_bytes(&Canon::U64(5)));
+    assert_ne!(canon_bytes_with(&Canon::I64(5), CanonMode::V2), canon_bytes_with(&Canon::U64(5), CanonMode::V2));
+
+    let v = Canon::Arr(vec![Canon::I64(5), Canon::U64(5), Canon::I64(i64::MIN), Canon::Str("é\"".to_string())]);
+    assert_eq!(parse_canon_v2(&canon_bytes_with(&v, CanonMode::V2)).unwrap(), v);
+}
+
+#[test]
+fn gate_v2_roundtrip_and_versioned_dispatch() {
+    let cert = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap();
+    let v1 = canon_bytes(&cert.payload);
+    let v2 = canon_bytes_with(&cert.payload, CanonMode::V2);
+
+    assert_eq!(parse_canon_versioned(&v1).unwrap(), (CanonMode::V1, cert.payload.clone()));
+    assert_eq!(parse_canon_versioned(&v2).unwrap(), (CanonMode::V2, cert.payload.clone()));
+
+    let e = parse_canon_v2(&v2[..v2.len() - 1]).unwrap_err();
+    assert_eq!(e.kind, CanonParseErrorKind::UnexpectedEnd);
+    assert_eq!(parse_canon_v2(&v1).unwrap_err().kind, CanonParseErrorKind::BadMagic);
+
+    // Hand-built {"b":null,"a":null}: well-formed v2, but keys out of order.
+    let mut bytes = collapse_core::canon::CANON_V2_MAGIC.to_vec();
+    bytes.push(b'o');
+    bytes.extend_from_slice(&2u64.to_be_bytes());
+    for k in [b'b', b'a'] {
+        bytes.extend_from_slice(&1u64.to_be_bytes());
+        bytes.push(k);
+        bytes.push(b'N');
+    }
+    assert_eq!(parse_canon_v2(&bytes).unwrap_err().kind, CanonParseErrorKind::UnsortedKey);
+}
+
+#[test]
+fn gate_v2_cert_bumps_kernel_version() {
+    let v1 = asc7_kernel_cert(&Asc7Profile::code_safe()).unwrap();
+    let v2 = v1.to_v2().unwrap();
+    assert_eq!(v2.kernel_version, "2.0.0");
+    assert_eq!(v2.canon_mode, CanonMode::V2);
+    assert_eq!(v2.kernel_hash, sha256_bytes(&canon_bytes_with(&v1.payload, CanonMode::V2)));
+    assert_ne!(v2.kernel_hash, v1.kernel_hash);
+    assert_eq!(v2.to_v2().unwrap().kernel_version, "2.0.0");
+
+    let named = KernelCert::new_unchecked("v2_demo", "stable", Canon::Null);
+    assert_eq!(named.to_v2().unwrap_err(), VersionBumpError { version: "stable".into() });
+}
@@ -0,0 +1,25 @@
+//! Fixtures shared by the gate tests. Each test binary uses a different subset.
+#![allow(dead_code)]
+
+use asc7::confusables::confusables_kernel_cert;
+use asc7::{Asc7Profile, asc7_kernel_cert, register_asc7_kernels};
+use collapse_core::{CertBundle, ChainMode, HashAlg, KernelCert, KernelRegistry};
+use sembit::register_sembit_kernels;
+
+/// The `asc7` cert under `profile` and the confusables cert.
+pub fn certs(profile: Asc7Profile) -> Vec<KernelCert> {
+    vec![asc7_kernel_cert(&profile).unwrap(), confusables_kernel_cert().unwrap()]
+}
+
+/// `certs(code_safe)` in a flat SHA-256 chain.
+pub fn bundle() -> CertBundle {
+    CertBundle::from_certs(certs(Asc7Profile::code_safe()), ChainMode::Flat, HashAlg::Sha256)
+}
+
+/// The asc7 and sembit kernels, with their schemas.
+pub fn kernels() -> KernelRegistry {
+    let mut k = KernelRegistry::new();
+    register_asc7_kernels(&mut k).unwrap();
+    register_sembit_kernels(&mut k).unwrap();
+    k
+}
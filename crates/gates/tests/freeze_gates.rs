@@ -3,10 +3,10 @@ use std::fs;
 
 use asc7::{Asc7Profile, asc7_kernel_cert, normalize_str};
 use asc7::confusables::confusables_kernel_cert;
-use collapse_core::{CertChain, CertItem, sem_entropy_bits};
-use sembit::{Test, TestFamily, sembit_quotient, tests_hash_hex, quotient_digest_hex, sembit_kernel_cert};
+use collapse_core::{CertChain, CertItem, HashAlg, sem_entropy_bits};
+use sembit::{Test, TestFamily, sembit_quotient, tests_digest, quotient_digest, sembit_kernel_cert};
 use structural_numbers::{QE, domain_qe_bounded};
-use structural_numbers::q_e::domain_digest_hex;
+use structural_numbers::q_e::domain_digest;
 
 fn t_sign(x: &QE) -> bool { x.num() > 0 }
 fn t_is_int(x: &QE) -> bool { x.den() == 1 }
@@ -14,13 +14,13 @@ fn t_den_gt_3(x: &QE) -> bool { x.den() > 3 }
 
 fn compute_spine() -> BTreeMap<String, String> {
     let profile = Asc7Profile::code_safe();
-    let asc7_cert = asc7_kernel_cert(&profile);
-    let asc7_hash = asc7_cert.kernel_hash_hex();
-    let conf_cert = confusables_kernel_cert();
-    let conf_hash = conf_cert.kernel_hash_hex();
+    let asc7_cert = asc7_kernel_cert(&profile).unwrap();
+    let asc7_hash = asc7_cert.kernel_hash.clone();
+    let conf_cert = confusables_kernel_cert().unwrap();
+    let conf_hash = conf_cert.kernel_hash.clone();
 
     let domain: Vec<QE> = domain_qe_bounded(20, 20);
-    let domain_digest = domain_digest_hex(&domain);
+    let domain_digest = domain_digest(&domain, HashAlg::Sha256);
 
     let id1 = normalize_str(&profile, "sign", true).unwrap();
     let id2 = normalize_str(&profile, "is_int", true).unwrap();
@@ -32,36 +32,36 @@ fn compute_spine() -> BTreeMap<String, String> {
         Test { id_norm: id3, f: t_den_gt_3 },
     ]);
 
-    let tests_hash = tests_hash_hex(&tf, "impl:static_v1");
+    let tests_hash = tests_digest(&tf, "impl:static_v1", HashAlg::Sha256);
     let q = sembit_quotient(&domain, &tf);
     let h = sem_entropy_bits(q.size());
-    let qdig = quotient_digest_hex(&q);
+    let qdig = quotient_digest(&q, HashAlg::Sha256);
 
     let sembit_cert = sembit_kernel_cert(
-        &asc7_hash,
-        &conf_hash,
+        &asc7_cert,
+        &conf_cert,
         &tests_hash,
         &domain_digest,
         q.size(),
         h,
         &qdig,
-    );
+    ).unwrap();
 
-    let sembit_hash = sembit_cert.kernel_hash_hex();
+    let sembit_hash = sembit_cert.kernel_hash.clone();
 
     let chain = CertChain::build(vec![
-        CertItem { name: "asc7".to_string(), hash_hex: asc7_hash.clone() },
-        CertItem { name: "asc7_confusables".to_string(), hash_hex: conf_hash.clone() },
-        CertItem { name: "sembit".to_string(), hash_hex: sembit_hash.clone() },
+        CertItem::new("asc7", asc7_hash.clone()),
+        CertItem::new("asc7_confusables", conf_hash.clone()),
+        CertItem::new("sembit", sembit_hash.clone()),
     ]);
 
     let mut m = BTreeMap::new();
-    m.insert("confusables_hash".to_string(), conf_hash);
-    m.insert("asc7_hash".to_string(), asc7_hash);
-    m.insert("domain_digest".to_string(), domain_digest);
-    m.insert("tests_hash".to_string(), tests_hash);
-    m.insert("sembit_hash".to_string(), sembit_hash);
-    m.insert("chain_hash".to_string(), chain.chain_hash_hex);
+    m.insert("confusables_hash".to_string(), conf_hash.hex());
+    m.insert("asc7_hash".to_string(), asc7_hash.hex());
+    m.insert("domain_digest".to_string(), domain_digest.hex());
+    m.insert("tests_hash".to_string(), tests_hash.hex());
+    m.insert("sembit_hash".to_string(), sembit_hash.hex());
+    m.insert("chain_hash".to_string(), chain.chain_hash.hex());
     m
 }
 
@@ -1,9 +1,13 @@
 use std::collections::BTreeMap;
 
-use collapse_core::canon::{Canon, canon_bytes};
+use collapse_core::canon::Canon;
 use collapse_core::cert::KernelCert;
-use collapse_core::digest::{sha256_bytes, sha256_hex};
+use collapse_core::digest::{HashAlg, TaggedDigest};
+use collapse_core::kernel::Kernel;
+use collapse_core::schema::SchemaError;
+use collapse_core::stream::write_array;
 use collapse_core::quotient::{Quotient, Signature};
+use collapse_core::{FromCanon, ToCanon};
 
 use crate::tests::TestFamily;
 
@@ -24,44 +28,72 @@ fn sig_to_canon(sig: &Signature) -> Canon {
 }
 
 pub fn tests_hash_hex<E>(tf: &TestFamily<E>, impl_tag: &str) -> String {
-    let mut arr = Vec::with_capacity(tf.tests.len() + 1);
-    for t in &tf.tests {
-        arr.push(Canon::Str(t.id_norm.clone()));
-    }
-    arr.push(Canon::Str(impl_tag.to_string()));
-    sha256_hex(sha256_bytes(&canon_bytes(&Canon::Arr(arr))))
+    tests_digest(tf, impl_tag, HashAlg::Sha256).hex()
+}
+
+pub fn tests_digest<E>(tf: &TestFamily<E>, impl_tag: &str, alg: HashAlg) -> TaggedDigest {
+    let mut h = alg.hasher();
+    write_array(&mut h, |arr| {
+        for t in &tf.tests {
+            arr.item(&Canon::Str(t.id_norm.clone()));
+        }
+        arr.item(&Canon::Str(impl_tag.to_string()));
+    });
+    h.finish()
 }
 
 pub fn quotient_digest_hex<E>(q: &Quotient<E>) -> String {
-    let mut arr = Vec::with_capacity(q.classes.len());
-    for (sig, members) in q.classes.iter() {
-        let mut obj = BTreeMap::new();
-        obj.insert("sig".to_string(), sig_to_canon(sig));
-        obj.insert("count".to_string(), Canon::U64(members.len() as u64));
-        arr.push(Canon::Obj(obj));
-    }
-    sha256_hex(sha256_bytes(&canon_bytes(&Canon::Arr(arr))))
+    quotient_digest(q, HashAlg::Sha256).hex()
+}
+
+/// Streams `[{count, sig}, ...]` class by class into the hasher.
+pub fn quotient_digest<E>(q: &Quotient<E>, alg: HashAlg) -> TaggedDigest {
+    let mut h = alg.hasher();
+    write_array(&mut h, |arr| {
+        for (sig, members) in q.classes.iter() {
+            arr.item_object(|obj| {
+                obj.field("count", &Canon::U64(members.len() as u64));
+                obj.field("sig", &sig_to_canon(sig));
+            });
+        }
+    });
+    h.finish()
+}
+
+/// Payload of the `sembit` kernel cert. Entropy is stored as integer microbits.
+///
+/// Despite their names, `asc7_graph_hash` and `confusables_graph_hash` hold the
+/// upstream *kernel* hashes; the names are frozen into the pinned bytes.
+#[derive(Clone, Debug, ToCanon, FromCanon)]
+pub struct SembitKernelPayload {
+    pub asc7_graph_hash: TaggedDigest,
+    pub confusables_graph_hash: TaggedDigest,
+    pub tests_hash: TaggedDigest,
+    pub domain_digest: TaggedDigest,
+    pub classes: u64,
+    #[canon(rename = "h_sem_microbits", scale = 1_000_000)]
+    pub h_sem_bits: f64,
+    pub quotient_digest: TaggedDigest,
 }
 
+/// Declares `asc7` and `confusables` as deps and embeds their kernel hashes.
 pub fn sembit_kernel_cert(
-    asc7_graph_hash_hex: &str,
-    confusables_graph_hash_hex: &str,
-    tests_hash_hex: &str,
-    domain_digest_hex: &str,
+    asc7: &KernelCert,
+    confusables: &KernelCert,
+    tests_hash: &TaggedDigest,
+    domain_digest: &TaggedDigest,
     q_classes: usize,
     h_sem_bits: f64,
-    quotient_digest_hex: &str,
-) -> KernelCert {
-    let h_scaled = (h_sem_bits * 1_000_000.0).round() as i64;
-
-    let mut obj = BTreeMap::new();
-    obj.insert("asc7_graph_hash".to_string(), Canon::Str(asc7_graph_hash_hex.to_string()));
-    obj.insert("confusables_graph_hash".to_string(), Canon::Str(confusables_graph_hash_hex.to_string()));
-    obj.insert("tests_hash".to_string(), Canon::Str(tests_hash_hex.to_string()));
-    obj.insert("domain_digest".to_string(), Canon::Str(domain_digest_hex.to_string()));
-    obj.insert("classes".to_string(), Canon::U64(q_classes as u64));
-    obj.insert("h_sem_microbits".to_string(), Canon::I64(h_scaled));
-    obj.insert("quotient_digest".to_string(), Canon::Str(quotient_digest_hex.to_string()));
-
-    KernelCert::new("sembit", "1.0.0", Canon::Obj(obj))
+    quotient_digest: &TaggedDigest,
+) -> Result<KernelCert, Vec<SchemaError>> {
+    let payload = SembitKernelPayload {
+        asc7_graph_hash: asc7.kernel_hash.clone(),
+        confusables_graph_hash: confusables.kernel_hash.clone(),
+        tests_hash: tests_hash.clone(),
+        domain_digest: domain_digest.clone(),
+        classes: q_classes as u64,
+        h_sem_bits,
+        quotient_digest: quotient_digest.clone(),
+    };
+    payload.cert()
 }
@@ -0,0 +1,53 @@
+use collapse_core::canon::Canon;
+use collapse_core::cert::CertItem;
+use collapse_core::entropy::sem_entropy_bits;
+use collapse_core::kernel::{DuplicateKernel, Kernel, KernelError, KernelInputs, KernelRegistry};
+use collapse_core::schema::Schema;
+use collapse_core::{FromCanon, ToCanon};
+
+use crate::cert::SembitKernelPayload;
+use crate::schema::sembit_schema;
+
+impl Kernel for SembitKernelPayload {
+    const NAME: &'static str = "sembit";
+    const VERSION: &'static str = "1.0.0";
+    const LEGACY_HASH: bool = true;
+
+    fn payload(&self) -> Canon {
+        self.to_canon()
+    }
+
+    fn deps_of(payload: &Canon) -> Result<Vec<CertItem>, KernelError> {
+        let p = SembitKernelPayload::from_canon(payload)?;
+        Ok(vec![
+            CertItem::new("asc7", p.asc7_graph_hash),
+            CertItem::new("asc7_confusables", p.confusables_graph_hash),
+        ])
+    }
+
+    fn schema() -> Option<Schema> {
+        Some(sembit_schema())
+    }
+
+    /// The domain, tests and quotient are not in the bundle, so their digests are
+    /// taken as given. The upstream certs must be inputs, and the entropy must be
+    /// `log2(classes)` at the payload's microbit precision.
+    fn verify(payload: &Canon, inputs: &KernelInputs<'_>) -> Result<(), KernelError> {
+        let p = SembitKernelPayload::from_canon(payload)?;
+        inputs.require("asc7", &p.asc7_graph_hash)?;
+        inputs.require("asc7_confusables", &p.confusables_graph_hash)?;
+        if p.classes == 0 {
+            return Err(KernelError::invalid("classes", "a quotient has at least one class"));
+        }
+        let microbits = |bits: f64| (bits * 1e6).round() as i64;
+        if microbits(p.h_sem_bits) != microbits(sem_entropy_bits(p.classes as usize)) {
+            return Err(KernelError::invalid("h_sem_microbits", "is not log2(classes)"));
+        }
+        Ok(())
+    }
+}
+
+pub fn register_sembit_kernels(registry: &mut KernelRegistry) -> Result<(), DuplicateKernel> {
+    registry.register::<SembitKernelPayload>()?;
+    Ok(())
+}
@@ -1,7 +1,11 @@
 pub mod tests;
 pub mod quotient;
 pub mod cert;
+pub mod schema;
+pub mod kernel;
 
 pub use tests::{Test, TestFamily};
 pub use quotient::sembit_quotient;
-pub use cert::{tests_hash_hex, quotient_digest_hex, sembit_kernel_cert};
+pub use cert::{SembitKernelPayload, quotient_digest, quotient_digest_hex, sembit_kernel_cert, tests_digest, tests_hash_hex};
+pub use schema::sembit_schema;
+pub use kernel::register_sembit_kernels;
@@ -0,0 +1,14 @@
+use collapse_core::schema::{ObjectSchema, Schema};
+
+/// Payload schema of the `sembit` kernel, collected by `register_sembit_kernels`.
+pub fn sembit_schema() -> Schema {
+    ObjectSchema::new()
+        .required("asc7_graph_hash", Schema::Digest)
+        .required("classes", Schema::U64)
+        .required("confusables_graph_hash", Schema::Digest)
+        .required("domain_digest", Schema::Digest)
+        .required("h_sem_microbits", Schema::I64)
+        .required("quotient_digest", Schema::Digest)
+        .required("tests_hash", Schema::Digest)
+        .build()
+}
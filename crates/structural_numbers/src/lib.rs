@@ -3,7 +3,7 @@ pub mod domain;
 pub mod n_e;
 pub mod z_e;
 
-pub use q_e::QE;
+pub use q_e::{QE, domain_digest, domain_digest_hex};
 pub use domain::domain_qe_bounded;
 pub use n_e::{NE, domain_ne, domain_digest_hex_ne, domain_view_ne};
 pub use z_e::{ZE, domain_ze, domain_digest_hex_ze, domain_view_ze};
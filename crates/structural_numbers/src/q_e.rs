@@ -1,9 +1,11 @@
 use num_integer::gcd;
 use std::cmp::Ordering;
-use std::collections::BTreeMap;
 
-use collapse_core::canon::{Canon, canon_bytes};
-use collapse_core::digest::{sha256_bytes, sha256_hex};
+use collapse_core::canon::Canon;
+use collapse_core::convert::{FromCanon, FromCanonError, ToCanon};
+use collapse_core::digest::{HashAlg, TaggedDigest, sha256_hex};
+use collapse_core::exact::Rational;
+use collapse_core::stream::{HashSink, write_array};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct QE {
@@ -46,25 +48,44 @@ impl PartialOrd for QE {
     }
 }
 
+/// `QE` is already reduced with a positive denominator, so it maps onto
+/// `Canon::Rat` one to one.
+impl ToCanon for QE {
+    fn to_canon(&self) -> Canon {
+        Canon::Rat(Rational::new(self.num, self.den))
+    }
+}
+
+impl FromCanon for QE {
+    fn from_canon(v: &Canon) -> Result<Self, FromCanonError> {
+        let r = Rational::from_canon(v)?;
+        let den = i64::try_from(r.den()).map_err(|_| FromCanonError::new(format!("denominator {} overflows i64", r.den())))?;
+        Ok(QE::new(r.num(), den))
+    }
+}
+
 pub fn canon_domain_digest_hex_u64(domain: &[u64]) -> String {
-    let arr: Vec<Canon> = domain.iter().map(|x| Canon::U64(*x)).collect();
-    sha256_hex(sha256_bytes(&canon_bytes(&Canon::Arr(arr))))
+    let mut h = HashSink::new();
+    write_array(&mut h, |arr| domain.iter().for_each(|x| arr.item(&Canon::U64(*x))));
+    sha256_hex(h.finish())
 }
 
 pub fn canon_domain_digest_hex_i64(domain: &[i64]) -> String {
-    let arr: Vec<Canon> = domain.iter().map(|x| Canon::I64(*x)).collect();
-    sha256_hex(sha256_bytes(&canon_bytes(&Canon::Arr(arr))))
+    let mut h = HashSink::new();
+    write_array(&mut h, |arr| domain.iter().for_each(|x| arr.item(&Canon::I64(*x))));
+    sha256_hex(h.finish())
 }
 
 /// Canonical domain digest: sha256(canon([{num,den}, ...])).
 /// Caller should supply deterministic order; our enumerators sort+dedup.
 pub fn domain_digest_hex(domain: &[QE]) -> String {
-    let mut arr = Vec::with_capacity(domain.len());
-    for q in domain {
-        let mut obj = BTreeMap::new();
-        obj.insert("num".to_string(), Canon::I64(q.num()));
-        obj.insert("den".to_string(), Canon::I64(q.den()));
-        arr.push(Canon::Obj(obj));
-    }
-    sha256_hex(sha256_bytes(&canon_bytes(&Canon::Arr(arr))))
+    domain_digest(domain, HashAlg::Sha256).hex()
+}
+
+/// `domain_digest_hex` under any algorithm. Streamed element by element; each
+/// element is a `Canon::Rat`, whose v1 form is `{den,num}`.
+pub fn domain_digest(domain: &[QE], alg: HashAlg) -> TaggedDigest {
+    let mut h = alg.hasher();
+    write_array(&mut h, |arr| domain.iter().for_each(|q| arr.item(&q.to_canon())));
+    h.finish()
 }